@@ -0,0 +1,50 @@
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion, Throughput};
+use valog::{sync::ValueLog, Builder, LogWriter, LogWriterExt, VacantBuffer, ValueBuilder};
+
+const N: u64 = 10_000;
+const VALUE: &[u8] = b"Hello, valog! Hello, valog!";
+
+fn new_log() -> ValueLog {
+  Builder::new()
+    .with_capacity((N as u32 + 1) * 64)
+    .alloc::<ValueLog>(0)
+    .unwrap()
+}
+
+fn insert(c: &mut Criterion) {
+  let mut group = c.benchmark_group("insert");
+  group.throughput(Throughput::Elements(N));
+
+  group.bench_function("slice", |b| {
+    b.iter_batched(
+      new_log,
+      |log| {
+        for _ in 0..N {
+          log.insert(VALUE).unwrap();
+        }
+      },
+      BatchSize::LargeInput,
+    )
+  });
+
+  group.bench_function("builder", |b| {
+    b.iter_batched(
+      new_log,
+      |log| {
+        for _ in 0..N {
+          let vb = ValueBuilder::new(VALUE.len(), |buf: &mut VacantBuffer<'_>| {
+            buf.put_slice_unchecked(VALUE);
+            Ok::<_, ()>(())
+          });
+          log.insert_with(vb).unwrap();
+        }
+      },
+      BatchSize::LargeInput,
+    )
+  });
+
+  group.finish();
+}
+
+criterion_group!(benches, insert);
+criterion_main!(benches);
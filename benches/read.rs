@@ -0,0 +1,61 @@
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use valog::{
+  sync::{ImmutableValueLog, ValueLog},
+  Builder, LogExt, LogReader, LogWriter, ValuePointer,
+};
+
+const N: u64 = 10_000;
+const VALUE: &[u8] = b"Hello, valog!";
+
+fn read(c: &mut Criterion) {
+  // the checksum validation dominates the small reads, disable it to measure the read path itself.
+  let builder = Builder::new().with_validate_checksum(false);
+  let log = builder
+    .clone()
+    .with_capacity((N as u32 + 1) * 32)
+    .with_unify(true)
+    .alloc::<ValueLog>(0)
+    .unwrap();
+  let mut ptrs = (0..N)
+    .map(|_| log.insert(VALUE).unwrap())
+    .collect::<Vec<ValuePointer<u32>>>();
+
+  // read in a pseudo-random order
+  let mut state = 0x2545_f491_4f6c_dd1du64;
+  for i in (1..ptrs.len()).rev() {
+    state ^= state << 13;
+    state ^= state >> 7;
+    state ^= state << 17;
+    ptrs.swap(i, state as usize % (i + 1));
+  }
+
+  let mut bytes = Vec::new();
+  log.flush_to(&mut bytes).unwrap();
+  let frozen = builder.from_bytes::<ImmutableValueLog>(&bytes, 0).unwrap();
+
+  let mut group = c.benchmark_group("read");
+  group.throughput(Throughput::Elements(N));
+
+  group.bench_function("mutable", |b| {
+    b.iter(|| {
+      for vp in &ptrs {
+        let data = unsafe { log.read(vp.id(), vp.offset(), vp.size()).unwrap() };
+        criterion::black_box(data);
+      }
+    })
+  });
+
+  group.bench_function("immutable", |b| {
+    b.iter(|| {
+      for vp in &ptrs {
+        let data = unsafe { frozen.read(vp.id(), vp.offset(), vp.size()).unwrap() };
+        criterion::black_box(data);
+      }
+    })
+  });
+
+  group.finish();
+}
+
+criterion_group!(benches, read);
+criterion_main!(benches);
@@ -0,0 +1,92 @@
+use aes_gcm::{
+  aead::{rand_core::RngCore, AeadInPlace, KeyInit, OsRng},
+  Aes256Gcm, Nonce, Tag,
+};
+
+use crate::{error::Error, options::Cipher};
+
+/// The size of the nonce stored before the ciphertext of a value.
+pub(crate) const NONCE_SIZE: usize = 12;
+/// The size of the authentication tag stored after the ciphertext of a value.
+pub(crate) const TAG_SIZE: usize = 16;
+/// The number of bytes a sealed value takes more than the value.
+pub(crate) const SEAL_OVERHEAD: usize = NONCE_SIZE + TAG_SIZE;
+/// The size of the key check recorded in the header.
+pub(crate) const KEY_CHECK_SIZE: usize = TAG_SIZE;
+
+#[inline]
+fn aes_256_gcm(cipher: &Cipher) -> Aes256Gcm {
+  match cipher {
+    Cipher::Aes256Gcm { key } => Aes256Gcm::new(key.into()),
+  }
+}
+
+/// Returns a random salt for the nonces of the values.
+#[inline]
+pub(crate) fn random_salt() -> u64 {
+  OsRng.next_u64()
+}
+
+/// Returns the key check recorded in the header, which is the authentication tag of an empty message.
+///
+/// The values are never stored at offset `0`, so the all-zero nonce of the key check is never used by a value.
+#[inline]
+pub(crate) fn key_check(cipher: &Cipher) -> [u8; KEY_CHECK_SIZE] {
+  let tag = aes_256_gcm(cipher)
+    .encrypt_in_place_detached(
+      &Nonce::default(),
+      crate::options::MAGIC_TEXT.as_slice(),
+      &mut [],
+    )
+    .expect("the empty message is never too long");
+  tag.into()
+}
+
+/// Seals the value in place, the value is at `NONCE_SIZE..buf.len() - TAG_SIZE` of `buf`.
+///
+/// The nonce is the little-endian `salt` followed by the little-endian `offset` of the value, it is written
+/// to the start of `buf`, and the authentication tag is written to the end of `buf`.
+#[inline]
+pub(crate) fn seal(cipher: &Cipher, salt: u64, offset: u32, buf: &mut [u8]) {
+  let (nonce, rest) = buf.split_at_mut(NONCE_SIZE);
+  nonce[..8].copy_from_slice(&salt.to_le_bytes());
+  nonce[8..].copy_from_slice(&offset.to_le_bytes());
+  let (value, tag) = rest.split_at_mut(rest.len() - TAG_SIZE);
+  let computed = aes_256_gcm(cipher)
+    .encrypt_in_place_detached(Nonce::from_slice(nonce), &[], value)
+    .expect("the value is never too long");
+  tag.copy_from_slice(&computed);
+}
+
+/// Opens the sealed value and appends the value to `out`.
+///
+/// An empty `sealed` is an unstored value, which is opened as an empty value.
+pub(crate) fn open(
+  cipher: &Cipher,
+  sealed: &[u8],
+  out: &mut std::vec::Vec<u8>,
+) -> Result<(), Error> {
+  if sealed.is_empty() {
+    return Ok(());
+  }
+
+  if sealed.len() < SEAL_OVERHEAD {
+    return Err(Error::AuthenticationFailed);
+  }
+
+  let (nonce, rest) = sealed.split_at(NONCE_SIZE);
+  let (value, tag) = rest.split_at(rest.len() - TAG_SIZE);
+  let start = out.len();
+  out.extend_from_slice(value);
+  aes_256_gcm(cipher)
+    .decrypt_in_place_detached(
+      Nonce::from_slice(nonce),
+      &[],
+      &mut out[start..],
+      Tag::from_slice(tag),
+    )
+    .map_err(|_| {
+      out.truncate(start);
+      Error::AuthenticationFailed
+    })
+}
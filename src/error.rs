@@ -29,12 +29,121 @@ pub enum Error {
     end_offset: u32,
   },
 
+  /// Returned when the allocator accesses an offset that is out of the bounds of its allocated memory.
+  AllocatorOutOfBounds {
+    /// The offset
+    offset: usize,
+    /// The allocated memory size of the allocator
+    allocated: usize,
+  },
+
   /// Returned when the file id is not matched when reading.
   IdMismatch,
 
   /// Returned when checksum verification fails.
   ChecksumMismatch,
 
+  /// Returned when the magic text in the header of the value log is not matched.
+  BadMagicText,
+
+  /// Returned when the magic version in the header of the value log is not matched.
+  BadMagicVersion {
+    /// The expected magic version
+    expected: u16,
+    /// The magic version found in the header
+    found: u16,
+  },
+
+  /// Returned when the bytes are not in a valid value log layout.
+  InvalidLayout,
+
+  /// Returned when the file is written in an on-disk layout version which is not supported,
+  /// e.g. by a newer version of this crate.
+  UnsupportedVersion {
+    /// The layout version supported
+    expected: u16,
+    /// The layout version of the file
+    found: u16,
+  },
+
+  /// Returned when the checksum algorithm recorded in the header of the value log is not matched.
+  ChecksumAlgorithmMismatch {
+    /// The checksum algorithm of the checksumer
+    expected: u8,
+    /// The checksum algorithm found in the header
+    found: u8,
+  },
+
+  /// Returned when the width of the id type recorded in the header of the value log is not matched.
+  IdTypeMismatch {
+    /// The width of the id type of the value log
+    expected: u8,
+    /// The width of the id type found in the header
+    found: u8,
+  },
+
+  /// Returned when the options are not valid, see [`Options::validate`](crate::options::Options::validate).
+  InvalidOptions {
+    /// The description of the first problem found in the options
+    reason: &'static str,
+  },
+
+  /// Returned when the capacity does not fit in `u32`, see [`Builder::try_with_capacity`](crate::Builder::try_with_capacity).
+  CapacityOverflow {
+    /// The requested capacity
+    capacity: u64,
+  },
+
+  /// Returned when a [`Type`](crate::Type) writes a different number of bytes than its
+  /// `encoded_len` when inserting a generic value.
+  EncodeLengthMismatch {
+    /// The length reported by `encoded_len`
+    expected: usize,
+    /// The number of bytes actually written
+    actual: usize,
+  },
+
+  /// Returned when copying the raw entries between the value logs whose checksumers, checksum lengths or ciphers
+  /// are different, see [`LogWriterExt::copy_raw_from`](crate::LogWriterExt::copy_raw_from).
+  IncompatibleLayout,
+
+  /// Returned when reopening a value log with the options which do not match the on-disk layout
+  /// recorded in the file, e.g. a different [`reserved`](crate::options::Options::reserved).
+  OptionsMismatch {
+    /// The name of the first mismatched option
+    field: &'static str,
+  },
+
+  /// Returned when the number of the values in the value log reaches the cap,
+  /// see [`Options::with_max_entries`](crate::options::Options::with_max_entries).
+  MaxEntriesReached {
+    /// The maximum number of the values
+    maximum: u32,
+  },
+
+  /// Returned when inserting a value at an offset which overlaps the written entries,
+  /// see [`LogWriter::insert_at`](crate::LogWriter::insert_at).
+  Overlap {
+    /// The requested offset
+    offset: u32,
+    /// The end of the written entries
+    allocated: u32,
+  },
+
+  /// Returned when trying to write to a value log whose backend is read-only,
+  /// e.g. a value log opened by `Builder::map`.
+  ReadOnly,
+
+  /// Returned when the checksum of the header of the value log is not matched,
+  /// which means the header is corrupted.
+  HeaderCorrupt,
+
+  /// Returned when an encrypted value or the key check in the header of the value log cannot be authenticated,
+  /// e.g. the value log is reopened with a wrong key, see [`Options::with_cipher`](crate::options::Options::with_cipher).
+  #[cfg(feature = "encryption")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "encryption")))]
+  AuthenticationFailed,
+
   /// Returned when an IO error occurs.
   #[cfg(feature = "std")]
   #[cfg_attr(docsrs, doc(cfg(feature = "std")))]
@@ -48,6 +157,23 @@ impl From<std::io::Error> for Error {
   }
 }
 
+impl From<rarena_allocator::Error> for Error {
+  #[inline]
+  fn from(err: rarena_allocator::Error) -> Self {
+    match err {
+      rarena_allocator::Error::InsufficientSpace {
+        requested,
+        available,
+      } => Self::insufficient_space(requested, available),
+      rarena_allocator::Error::ReadOnly => Self::read_only(),
+      rarena_allocator::Error::OutOfBounds { offset, allocated } => {
+        Self::allocator_out_of_bounds(offset, allocated)
+      }
+      rarena_allocator::Error::DecodeVarintError(_) => Self::InvalidLayout,
+    }
+  }
+}
+
 impl core::fmt::Display for Error {
   fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
     match self {
@@ -72,10 +198,63 @@ impl core::fmt::Display for Error {
         "out of bounds, offset: {}, len: {}, data offset: {}, end offset: {}",
         offset, len, data_offset, end_offset
       ),
+      Self::AllocatorOutOfBounds { offset, allocated } => write!(
+        f,
+        "allocator out of bounds, offset: {}, allocated: {}",
+        offset, allocated
+      ),
       Self::IdMismatch => {
         write!(f, "file id mismatch")
       }
       Self::ChecksumMismatch => f.write_str("checksum mismatch"),
+      Self::BadMagicText => f.write_str("bad magic text"),
+      Self::BadMagicVersion { expected, found } => write!(
+        f,
+        "bad magic version, expected: {}, found: {}",
+        expected, found
+      ),
+      Self::InvalidLayout => f.write_str("invalid value log layout"),
+      Self::UnsupportedVersion { expected, found } => write!(
+        f,
+        "unsupported layout version, expected: {}, found: {}",
+        expected, found
+      ),
+      Self::ChecksumAlgorithmMismatch { expected, found } => write!(
+        f,
+        "checksum algorithm mismatch, expected: {}, found: {}",
+        expected, found
+      ),
+      Self::IdTypeMismatch { expected, found } => write!(
+        f,
+        "id type width mismatch, expected: {}, found: {}",
+        expected, found
+      ),
+      Self::HeaderCorrupt => f.write_str("header corrupt"),
+      #[cfg(feature = "encryption")]
+      Self::AuthenticationFailed => f.write_str("authentication failed"),
+      Self::ReadOnly => f.write_str("value log is read-only"),
+      Self::Overlap { offset, allocated } => write!(
+        f,
+        "offset {} overlaps the written entries, which end at {}",
+        offset, allocated
+      ),
+      Self::IncompatibleLayout => f.write_str("incompatible layout"),
+      Self::OptionsMismatch { field } => write!(f, "options mismatch: {}", field),
+      Self::MaxEntriesReached { maximum } => {
+        write!(f, "maximum number of entries reached: {}", maximum)
+      }
+      Self::InvalidOptions { reason } => write!(f, "invalid options: {}", reason),
+      Self::CapacityOverflow { capacity } => write!(
+        f,
+        "capacity overflow, requested: {}, maximum: {}",
+        capacity,
+        u32::MAX
+      ),
+      Self::EncodeLengthMismatch { expected, actual } => write!(
+        f,
+        "encode length mismatch, expected: {}, actual: {}",
+        expected, actual
+      ),
       #[cfg(feature = "std")]
       Self::IO(err) => err.fmt(f),
     }
@@ -84,12 +263,69 @@ impl core::fmt::Display for Error {
 
 impl core::error::Error for Error {}
 
+/// Returned by [`LogWriter::insert_if_fits`](crate::LogWriter::insert_if_fits) when there is not enough space
+/// in the value log for the value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct InsufficientSpaceError {
+  requested: u32,
+  available: u32,
+}
+
+impl InsufficientSpaceError {
+  #[inline]
+  pub(crate) const fn new(requested: u32, available: u32) -> Self {
+    Self {
+      requested,
+      available,
+    }
+  }
+
+  /// Returns the requested size, including the checksum and the padding.
+  #[inline]
+  pub const fn requested(&self) -> u32 {
+    self.requested
+  }
+
+  /// Returns the remaining size of the value log.
+  #[inline]
+  pub const fn available(&self) -> u32 {
+    self.available
+  }
+}
+
+impl core::fmt::Display for InsufficientSpaceError {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    write!(
+      f,
+      "insufficient space, requested: {}, available: {}",
+      self.requested, self.available
+    )
+  }
+}
+
+impl core::error::Error for InsufficientSpaceError {}
+
+impl From<InsufficientSpaceError> for Error {
+  #[inline]
+  fn from(e: InsufficientSpaceError) -> Self {
+    Self::insufficient_space(e.requested, e.available)
+  }
+}
+
 impl Error {
   #[inline]
   pub(crate) const fn value_too_large(size: usize, maximum: usize) -> Self {
     Self::ValueTooLarge { size, maximum }
   }
 
+  #[inline]
+  pub(crate) const fn insufficient_space(requested: u32, available: u32) -> Self {
+    Self::InsufficientSpace {
+      requested,
+      available,
+    }
+  }
+
   #[inline]
   pub(crate) const fn out_of_bounds(
     offset: u32,
@@ -105,22 +341,81 @@ impl Error {
     }
   }
 
+  #[inline]
+  pub(crate) const fn allocator_out_of_bounds(offset: usize, allocated: usize) -> Self {
+    Self::AllocatorOutOfBounds { offset, allocated }
+  }
+
   #[inline]
   pub(crate) const fn checksum_mismatch() -> Self {
     Self::ChecksumMismatch
   }
 
   #[inline]
-  pub(crate) const fn from_insufficient_space(err: rarena_allocator::Error) -> Self {
-    match err {
-      rarena_allocator::Error::InsufficientSpace {
-        requested,
-        available,
-      } => Self::InsufficientSpace {
-        requested,
-        available,
-      },
-      _ => unreachable!(),
+  pub(crate) const fn bad_magic_version(expected: u16, found: u16) -> Self {
+    Self::BadMagicVersion { expected, found }
+  }
+
+  #[inline]
+  pub(crate) const fn checksum_algorithm_mismatch(expected: u8, found: u8) -> Self {
+    Self::ChecksumAlgorithmMismatch { expected, found }
+  }
+
+  #[inline]
+  pub(crate) const fn id_type_mismatch(expected: u8, found: u8) -> Self {
+    Self::IdTypeMismatch { expected, found }
+  }
+
+  #[inline]
+  pub(crate) const fn invalid_options(reason: &'static str) -> Self {
+    Self::InvalidOptions { reason }
+  }
+
+  #[inline]
+  pub(crate) const fn capacity_overflow(capacity: u64) -> Self {
+    Self::CapacityOverflow { capacity }
+  }
+
+  #[inline]
+  pub(crate) const fn max_entries_reached(maximum: u32) -> Self {
+    Self::MaxEntriesReached { maximum }
+  }
+
+  #[inline]
+  pub(crate) const fn unsupported_version(expected: u16, found: u16) -> Self {
+    Self::UnsupportedVersion { expected, found }
+  }
+
+  #[inline]
+  pub(crate) const fn options_mismatch(field: &'static str) -> Self {
+    Self::OptionsMismatch { field }
+  }
+
+  #[inline]
+  pub(crate) const fn overlap(offset: u32, allocated: u32) -> Self {
+    Self::Overlap { offset, allocated }
+  }
+
+  #[inline]
+  pub(crate) const fn read_only() -> Self {
+    Self::ReadOnly
+  }
+
+  #[inline]
+  pub(crate) const fn encode_length_mismatch(expected: usize, actual: usize) -> Self {
+    Self::EncodeLengthMismatch { expected, actual }
+  }
+
+  #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+  #[inline]
+  pub(crate) fn into_io_err(self) -> std::io::Error {
+    match self {
+      Self::IO(e) => e,
+      e @ (Self::InvalidOptions { .. }
+      | Self::CapacityOverflow { .. }
+      | Self::OptionsMismatch { .. }) => std::io::Error::new(std::io::ErrorKind::InvalidInput, e),
+      Self::ReadOnly => std::io::Error::new(std::io::ErrorKind::PermissionDenied, Self::ReadOnly),
+      e => std::io::Error::new(std::io::ErrorKind::InvalidData, e),
     }
   }
 
@@ -135,18 +430,6 @@ impl Error {
   }
 }
 
-#[cfg(all(feature = "memmap", not(target_family = "wasm")))]
-#[inline]
-pub(crate) fn bad_magic_text() -> std::io::Error {
-  std::io::Error::new(std::io::ErrorKind::InvalidData, "bad magic text")
-}
-
-#[cfg(all(feature = "memmap", not(target_family = "wasm")))]
-#[inline]
-pub(crate) fn bad_magic_version() -> std::io::Error {
-  std::io::Error::new(std::io::ErrorKind::InvalidData, "bad magic version")
-}
-
 #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
 #[inline]
 fn bad_version() -> std::io::Error {
@@ -7,15 +7,28 @@
 #[cfg(all(feature = "std", not(feature = "alloc")))]
 extern crate std;
 
+#[cfg(all(feature = "alloc", not(feature = "std")))]
+extern crate alloc as std;
+
 /// Value log implementation for concurrent environment.
+#[cfg(feature = "sync")]
+#[cfg_attr(docsrs, doc(cfg(feature = "sync")))]
 pub mod sync;
 
 /// Value log implementation for single-threaded environment.
 pub mod unsync;
 
+/// Value log implementation which only exposes the API guaranteed to build on `wasm` targets.
+#[cfg(feature = "wasm")]
+#[cfg_attr(docsrs, doc(cfg(feature = "wasm")))]
+pub mod wasm;
+
 mod log;
 pub use log::*;
 
+#[cfg(feature = "encryption")]
+mod cipher;
+
 #[cfg(test)]
 #[macro_use]
 pub(crate) mod tests;
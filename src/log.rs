@@ -1,4 +1,4 @@
-use core::ptr::NonNull;
+use core::{ptr::NonNull, sync::atomic::AtomicU32};
 
 use dbutils::{
   buffer::VacantBuffer,
@@ -8,20 +8,45 @@ use dbutils::{
 };
 use rarena_allocator::{either::Either, Allocator, Buffer};
 
-use super::{error::Error, options::Options, ValueBuilder};
+use super::{
+  error::{Error, InsufficientSpaceError},
+  options::{ChecksumLen, Freelist, Options, OversizePolicy},
+  ValueBuilder,
+};
 
 mod reader;
+use reader::{read_in_bounds, readable_bounds};
 pub use reader::{GenericLogReader, LogReader, LogReaderExt};
 
 mod writer;
+use writer::PAIR_PREFIX_SIZE;
 pub use writer::{GenericLogWriter, LogWriter, LogWriterExt};
 
 mod common;
 pub use common::{Log, LogExt, MutableLog};
 
-pub(super) mod sealed;
+mod buffered;
+pub use buffered::BufferedWriter;
+
+#[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+mod pread;
+#[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+#[cfg_attr(docsrs, doc(cfg(all(feature = "memmap", not(target_family = "wasm")))))]
+pub use pread::PreadValueLog;
 
-const CHECKSUM_LEN: usize = 8;
+#[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+mod range;
+#[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+#[cfg_attr(docsrs, doc(cfg(all(feature = "memmap", not(target_family = "wasm")))))]
+pub use range::RangeValueLog;
+
+#[cfg(feature = "cache")]
+mod cache;
+#[cfg(feature = "cache")]
+#[cfg_attr(docsrs, doc(cfg(feature = "cache")))]
+pub use cache::{CachedLog, DEFAULT_CACHE_CAPACITY};
+
+pub(super) mod sealed;
 
 /// A marker trait which means that the log is frozen and cannot be modified.
 pub trait Frozen {}
@@ -29,6 +54,62 @@ pub trait Frozen {}
 /// A marker trait which means that the log is mutable and can be modified.
 pub trait Mutable {}
 
+/// The statistics of the freelist of a log, see [`Log::freelist_stats`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FreelistStats {
+  kind: Freelist,
+  discarded: u32,
+  minimum_segment_size: u32,
+}
+
+impl FreelistStats {
+  /// Returns the [`Freelist`] kind of the log.
+  #[inline]
+  pub const fn kind(&self) -> Freelist {
+    self.kind
+  }
+
+  /// Returns the bytes which are freed but too small to be tracked by the freelist,
+  /// (including the discarded bytes of tombstones and alignment paddings), these bytes cannot be reused.
+  #[inline]
+  pub const fn discarded(&self) -> u32 {
+    self.discarded
+  }
+
+  /// Returns the minimum size of a segment which can be tracked by the freelist.
+  #[inline]
+  pub const fn minimum_segment_size(&self) -> u32 {
+    self.minimum_segment_size
+  }
+}
+
+/// The page faults taken by the first scan of a log, see [`Log::page_fault_stats`].
+#[cfg(feature = "metrics")]
+#[cfg_attr(docsrs, doc(cfg(feature = "metrics")))]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PageFaultStats {
+  major: u64,
+  minor: u64,
+}
+
+#[cfg(feature = "metrics")]
+impl PageFaultStats {
+  /// Returns the number of the major page faults, which required reading the pages from the disk.
+  #[inline]
+  pub const fn major(&self) -> u64 {
+    self.major
+  }
+
+  /// Returns the number of the minor page faults, which were served from the page cache.
+  #[inline]
+  pub const fn minor(&self) -> u64 {
+    self.minor
+  }
+}
+
+/// The offset of the null value pointers, no value can be stored at this offset.
+const NULL_OFFSET: u32 = u32::MAX;
+
 /// The pointer to the value in the log.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct ValuePointer<I> {
@@ -36,10 +117,45 @@ pub struct ValuePointer<I> {
   offset: u32,
   size: u32,
   tombstone: bool,
+  truncated: bool,
 }
 
 impl<I: CheapClone> CheapClone for ValuePointer<I> {}
 
+impl<I: core::fmt::Display> core::fmt::Display for ValuePointer<I> {
+  /// Formats the value pointer in a compact form, e.g. `vp(id=3, off=128, size=64)`,
+  /// tombstones are formatted as `vp(id=3, off=128, size=64, tombstone)`, truncated values as
+  /// `vp(id=3, off=128, size=64, truncated)`, and null pointers as `vp(id=3, null)`.
+  ///
+  /// ## Example
+  ///
+  /// ```rust
+  /// use valog::ValuePointer;
+  ///
+  /// let vp = ValuePointer::new(3u32, 128, 64);
+  /// assert_eq!(vp.to_string(), "vp(id=3, off=128, size=64)");
+  /// assert_eq!(ValuePointer::null(3u32).to_string(), "vp(id=3, null)");
+  /// ```
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    if self.is_null() {
+      return write!(f, "vp(id={}, null)", self.id);
+    }
+
+    write!(
+      f,
+      "vp(id={}, off={}, size={}",
+      self.id, self.offset, self.size
+    )?;
+    if self.tombstone {
+      f.write_str(", tombstone")?;
+    }
+    if self.truncated {
+      f.write_str(", truncated")?;
+    }
+    f.write_str(")")
+  }
+}
+
 impl<I> ValuePointer<I> {
   /// Creates a new value pointer.
   #[inline]
@@ -49,9 +165,63 @@ impl<I> ValuePointer<I> {
       offset,
       size,
       tombstone: false,
+      truncated: false,
     }
   }
 
+  /// Creates a null value pointer, which is a sentinel for an absent entry, e.g. a placeholder in an index.
+  ///
+  /// A null pointer is different from the pointer of an empty value, which is returned by inserting `&[]`.
+  /// Reading a null pointer returns an empty value.
+  ///
+  /// ## Example
+  ///
+  /// ```rust
+  /// use valog::{sync::ValueLog, Builder, LogWriter, ValuePointer};
+  ///
+  /// let null = ValuePointer::null(0u32);
+  /// assert!(null.is_null());
+  /// assert_eq!(null.size(), 0);
+  ///
+  /// let log = Builder::new().with_capacity(1024).alloc::<ValueLog>(0).unwrap();
+  /// let empty = log.insert(&[]).unwrap();
+  /// assert!(!empty.is_null());
+  /// assert_ne!(null, empty);
+  /// ```
+  #[inline]
+  pub const fn null(id: I) -> Self {
+    Self::new(id, NULL_OFFSET, 0)
+  }
+
+  /// Returns `true` if the value pointer is created by [`ValuePointer::null`].
+  #[inline]
+  pub const fn is_null(&self) -> bool {
+    self.offset == NULL_OFFSET && self.size == 0
+  }
+
+  /// Returns `true` if the value pointer refers to an entry stored in the log.
+  ///
+  /// The empty values inserted by [`LogWriter::insert`] are not stored, and their value pointers have offset `0`,
+  /// which is never the offset of an entry. The [`ValuePointer::null`] pointers are not stored either.
+  /// An empty value stored by [`LogWriter::insert_empty`] has a real offset in the log.
+  ///
+  /// ## Example
+  ///
+  /// ```rust
+  /// use valog::{sync::ValueLog, Builder, LogWriter, ValuePointer};
+  ///
+  /// let log = Builder::new().with_capacity(1024).alloc::<ValueLog>(0).unwrap();
+  ///
+  /// assert!(log.insert(b"foo").unwrap().is_stored());
+  /// assert!(log.insert_empty().unwrap().is_stored());
+  /// assert!(!log.insert(b"").unwrap().is_stored());
+  /// assert!(!ValuePointer::null(0u32).is_stored());
+  /// ```
+  #[inline]
+  pub const fn is_stored(&self) -> bool {
+    !self.is_null() && (self.offset != 0 || self.size != 0)
+  }
+
   /// Returns the log id of this value pointer.
   #[inline]
   pub const fn id(&self) -> &I {
@@ -70,6 +240,45 @@ impl<I> ValuePointer<I> {
     self.size
   }
 
+  /// Returns the byte range of the value payload in the log, the checksum is not included.
+  ///
+  /// ## Example
+  ///
+  /// ```rust
+  /// use valog::ValuePointer;
+  ///
+  /// let vp = ValuePointer::new(0u32, 10, 5);
+  /// assert_eq!(vp.payload_range(), 10..15);
+  /// ```
+  #[inline]
+  pub const fn payload_range(&self) -> core::ops::Range<u64> {
+    self.offset as u64..self.offset as u64 + self.size as u64
+  }
+
+  /// Returns the byte range of the whole entry in the log, including the value payload and the checksum.
+  ///
+  /// The checksum length is configured by [`Options::with_checksum_len`](crate::options::Options::with_checksum_len).
+  /// Empty values do not have a checksum stored, so the range is always empty.
+  ///
+  /// ## Example
+  ///
+  /// ```rust
+  /// use valog::{ValuePointer, options::ChecksumLen};
+  ///
+  /// let vp = ValuePointer::new(0u32, 10, 5);
+  /// assert_eq!(vp.range(ChecksumLen::Eight), 10..23);
+  /// assert_eq!(vp.range(ChecksumLen::Four), 10..19);
+  /// ```
+  #[inline]
+  pub const fn range(&self, checksum_len: ChecksumLen) -> core::ops::Range<u64> {
+    let end = if self.size == 0 {
+      self.offset as u64
+    } else {
+      self.offset as u64 + self.size as u64 + checksum_len.len() as u64
+    };
+    self.offset as u64..end
+  }
+
   /// Returns `true` if the value pointer points to a tombstone.
   #[inline]
   pub const fn is_tombstone(&self) -> bool {
@@ -84,6 +293,38 @@ impl<I> ValuePointer<I> {
       ..self
     }
   }
+
+  /// Returns `true` if the value was truncated to the [`maximum_value_size`](crate::options::Options::maximum_value_size)
+  /// when inserting, see [`OversizePolicy::Truncate`](crate::options::OversizePolicy::Truncate).
+  ///
+  /// ## Example
+  ///
+  /// ```rust
+  /// use valog::{options::OversizePolicy, sync::ValueLog, Builder, LogWriter};
+  ///
+  /// let log = Builder::new()
+  ///   .with_capacity(1024)
+  ///   .with_maximum_value_size(13)
+  ///   .with_oversize_policy(OversizePolicy::Truncate)
+  ///   .alloc::<ValueLog>(0)
+  ///   .unwrap();
+  ///
+  /// let vp = log.insert(b"Hello, valog!").unwrap();
+  /// assert!(vp.is_truncated());
+  /// // the 8 bytes checksum is counted in the maximum value size
+  /// assert_eq!(vp.size(), 5);
+  /// assert!(!log.insert(b"Hello").unwrap().is_truncated());
+  /// ```
+  #[inline]
+  pub const fn is_truncated(&self) -> bool {
+    self.truncated
+  }
+
+  /// Sets the truncation mark of the value pointer.
+  #[inline]
+  fn with_truncated(self, truncated: bool) -> Self {
+    Self { truncated, ..self }
+  }
 }
 
 /// The value log implementation.
@@ -93,6 +334,22 @@ pub struct ValueLog<I, A, C = Crc32> {
   allocator: A,
   checksumer: C,
   options: Options,
+  /// The number of the non-empty values inserted, shared with the clones of the log.
+  entries: std::sync::Arc<AtomicU32>,
+  /// The end of the values allocated in the sub-range of the log, shared with the clones of the log.
+  cursor: std::sync::Arc<AtomicU32>,
+  /// The page faults taken by the first scan, shared with the clones of the log.
+  #[cfg(feature = "metrics")]
+  page_faults: std::sync::Arc<std::sync::OnceLock<PageFaultStats>>,
+  /// The file to flush the synced values, see [`SyncStrategy`](crate::options::SyncStrategy).
+  #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+  sync_file: Option<std::sync::Arc<std::fs::File>>,
+  /// The path of the file shared by the memory map, used to evict the page cache.
+  #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+  shared_file_path: Option<std::sync::Arc<std::path::PathBuf>>,
+  /// Serializes the updates of the header, shared with the clones of the log.
+  #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+  header_lock: std::sync::Arc<std::sync::Mutex<()>>,
 }
 
 impl<I, A, C> sealed::Sealed for ValueLog<I, A, C>
@@ -105,6 +362,45 @@ where
   fn allocator(&self) -> &Self::Allocator {
     &self.allocator
   }
+
+  #[inline]
+  fn allocator_and_options_mut(&mut self) -> (&mut Self::Allocator, &mut Options) {
+    (&mut self.allocator, &mut self.options)
+  }
+
+  #[inline]
+  fn entry_counter(&self) -> Option<&AtomicU32> {
+    Some(&self.entries)
+  }
+
+  #[inline]
+  fn range_cursor(&self) -> Option<&AtomicU32> {
+    self.options.sub_range().map(|_| &*self.cursor)
+  }
+
+  #[cfg(feature = "metrics")]
+  #[inline]
+  fn page_faults(&self) -> Option<&std::sync::OnceLock<PageFaultStats>> {
+    Some(&self.page_faults)
+  }
+
+  #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+  #[inline]
+  fn sync_file(&self) -> Option<&std::fs::File> {
+    self.sync_file.as_deref()
+  }
+
+  #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+  #[inline]
+  fn shared_file_path(&self) -> Option<&std::path::Path> {
+    self.shared_file_path.as_deref().map(|p| p.as_path())
+  }
+
+  #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+  #[inline]
+  fn header_lock(&self) -> Option<&std::sync::Mutex<()>> {
+    Some(&self.header_lock)
+  }
 }
 
 impl<I, A, C> sealed::Constructor for ValueLog<I, A, C>
@@ -121,13 +417,44 @@ where
     checksumer: Self::Checksumer,
     options: Options,
   ) -> Self {
+    let cursor = options.sub_range().map_or(0, |(start, end)| {
+      (allocator.allocated() as u32).clamp(start, end)
+    });
+
     Self {
       fid,
       allocator,
       checksumer,
       options,
+      entries: std::sync::Arc::new(AtomicU32::new(0)),
+      cursor: std::sync::Arc::new(AtomicU32::new(cursor)),
+      #[cfg(feature = "metrics")]
+      page_faults: std::sync::Arc::new(std::sync::OnceLock::new()),
+      #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+      sync_file: None,
+      #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+      shared_file_path: None,
+      #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+      header_lock: std::sync::Arc::new(std::sync::Mutex::new(())),
     }
   }
+
+  #[inline]
+  fn id_mut(&mut self) -> &mut Self::Id {
+    &mut self.fid
+  }
+
+  #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+  #[inline]
+  fn set_sync_file(&mut self, file: std::fs::File) {
+    self.sync_file = Some(std::sync::Arc::new(file));
+  }
+
+  #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+  #[inline]
+  fn set_shared_file_path(&mut self, path: std::path::PathBuf) {
+    self.shared_file_path = Some(std::sync::Arc::new(path));
+  }
 }
 
 impl<I, A, C> Log for ValueLog<I, A, C>
@@ -170,6 +497,43 @@ where
 
 impl<I, A, C> Mutable for ValueLog<I, A, C> {}
 
+impl<I, A, C> ValueLog<I, A, C>
+where
+  A: Allocator,
+{
+  /// Returns a borrowed read-only view of the log, which is [`Frozen`] and implements [`LogReader`].
+  ///
+  /// Unlike consuming the log, the log can still be written after the view is dropped.
+  /// This is useful to call the generic code written against `impl LogReader + Frozen` with a mutable log.
+  ///
+  /// ## Example
+  ///
+  /// ```rust
+  /// use valog::{sync::ValueLog, Builder, Frozen, LogReader, LogWriter};
+  ///
+  /// fn read_frozen<L: LogReader<Id = u32> + Frozen>(log: &L, offset: u32, len: u32) -> Vec<u8> {
+  ///   unsafe { log.read(&0, offset, len).unwrap().to_vec() }
+  /// }
+  ///
+  /// let log = Builder::new().with_capacity(1024).alloc::<ValueLog>(0).unwrap();
+  ///
+  /// let vp = log.insert(b"Hello, valog!").unwrap();
+  /// assert_eq!(read_frozen(&log.as_immutable(), vp.offset(), vp.size()), b"Hello, valog!");
+  ///
+  /// // the log is still writable
+  /// log.insert(b"Hello, again!").unwrap();
+  /// ```
+  #[inline]
+  pub fn as_immutable(&self) -> ImmutableValueLogRef<'_, I, A, C> {
+    ImmutableValueLogRef {
+      fid: &self.fid,
+      allocator: self.allocator.clone(),
+      checksumer: &self.checksumer,
+      options: self.options,
+    }
+  }
+}
+
 /// The immutable value log implementation.
 #[derive(Debug, Clone)]
 pub struct ImmutableValueLog<I, A, C = Crc32> {
@@ -177,6 +541,14 @@ pub struct ImmutableValueLog<I, A, C = Crc32> {
   allocator: A,
   checksumer: C,
   options: Options,
+  /// The readable bounds are fixed after the log is opened, so they are cached for the reads.
+  bounds: (usize, usize),
+  /// The page faults taken by the first scan, shared with the clones of the log.
+  #[cfg(feature = "metrics")]
+  page_faults: std::sync::Arc<std::sync::OnceLock<PageFaultStats>>,
+  /// The path of the file shared by the memory map, used to evict the page cache.
+  #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+  shared_file_path: Option<std::sync::Arc<std::path::PathBuf>>,
 }
 
 // Safety: although the `rarena_allocator::unsync::Arena` is not `Send` and `Sync`,
@@ -208,6 +580,17 @@ where
   fn allocator(&self) -> &Self::Allocator {
     &self.allocator
   }
+
+  #[inline]
+  fn allocator_and_options_mut(&mut self) -> (&mut Self::Allocator, &mut Options) {
+    (&mut self.allocator, &mut self.options)
+  }
+
+  #[cfg(feature = "metrics")]
+  #[inline]
+  fn page_faults(&self) -> Option<&std::sync::OnceLock<PageFaultStats>> {
+    Some(&self.page_faults)
+  }
 }
 
 impl<I, A, C> Log for ImmutableValueLog<I, A, C>
@@ -231,6 +614,11 @@ where
   fn id(&self) -> &Self::Id {
     &self.fid
   }
+
+  #[inline]
+  fn is_readonly(&self) -> bool {
+    true
+  }
 }
 
 impl<I, A, C> LogReader for ImmutableValueLog<I, A, C>
@@ -238,6 +626,20 @@ where
   C: BuildChecksumer,
   A: Allocator,
 {
+  #[inline]
+  unsafe fn read(&self, id: &Self::Id, offset: u32, len: u32) -> Result<&[u8], Error>
+  where
+    Self::Id: Eq,
+  {
+    read_in_bounds(
+      self,
+      id,
+      offset,
+      len,
+      self.options.validate_checksum,
+      self.bounds,
+    )
+  }
 }
 
 impl<I, A, C> sealed::Constructor for ImmutableValueLog<I, A, C>
@@ -256,14 +658,93 @@ where
   ) -> Self {
     Self {
       fid,
+      bounds: readable_bounds(&allocator, &options, None),
       allocator,
       checksumer,
       options,
+      #[cfg(feature = "metrics")]
+      page_faults: std::sync::Arc::new(std::sync::OnceLock::new()),
+      #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+      shared_file_path: None,
     }
   }
+
+  #[inline]
+  fn id_mut(&mut self) -> &mut Self::Id {
+    &mut self.fid
+  }
+
+  #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+  #[inline]
+  fn set_shared_file_path(&mut self, path: std::path::PathBuf) {
+    self.shared_file_path = Some(std::sync::Arc::new(path));
+  }
+}
+
+impl<I, A, C> Frozen for ImmutableValueLog<I, A, C> {}
+
+/// The borrowed read-only view of a [`ValueLog`], created by [`ValueLog::as_immutable`].
+#[derive(Debug, Clone)]
+pub struct ImmutableValueLogRef<'a, I, A, C = Crc32> {
+  fid: &'a I,
+  allocator: A,
+  checksumer: &'a C,
+  options: Options,
+}
+
+impl<I, A, C> sealed::Sealed for ImmutableValueLogRef<'_, I, A, C>
+where
+  A: Allocator,
+{
+  type Allocator = A;
+
+  #[inline]
+  fn allocator(&self) -> &Self::Allocator {
+    &self.allocator
+  }
+
+  #[inline]
+  fn allocator_and_options_mut(&mut self) -> (&mut Self::Allocator, &mut Options) {
+    (&mut self.allocator, &mut self.options)
+  }
 }
 
-impl<I, C> Frozen for ImmutableValueLog<I, C> {}
+impl<I, A, C> Log for ImmutableValueLogRef<'_, I, A, C>
+where
+  C: BuildChecksumer,
+  A: Allocator,
+{
+  type Id = I;
+
+  #[inline]
+  fn checksum(&self, bytes: &[u8]) -> u64 {
+    self.checksumer.checksum_one(bytes)
+  }
+
+  #[inline]
+  fn options(&self) -> &Options {
+    &self.options
+  }
+
+  #[inline]
+  fn id(&self) -> &Self::Id {
+    self.fid
+  }
+
+  #[inline]
+  fn is_readonly(&self) -> bool {
+    true
+  }
+}
+
+impl<I, A, C> LogReader for ImmutableValueLogRef<'_, I, A, C>
+where
+  C: BuildChecksumer,
+  A: Allocator,
+{
+}
+
+impl<I, A, C> Frozen for ImmutableValueLogRef<'_, I, A, C> {}
 
 /// Generic value log.
 pub struct GenericValueLog<T, I, A, C = Crc32> {
@@ -308,6 +789,11 @@ impl<T, I, A, C> common::AsLog for GenericValueLog<T, I, A, C> {
   fn as_log(&self) -> &Self::Log {
     &self.log
   }
+
+  #[inline]
+  fn as_log_mut(&mut self) -> &mut Self::Log {
+    &mut self.log
+  }
 }
 
 /// Immutable generic value log.
@@ -353,6 +839,11 @@ impl<T, I, A, C> common::AsLog for ImmutableGenericValueLog<T, I, A, C> {
   fn as_log(&self) -> &Self::Log {
     &self.log
   }
+
+  #[inline]
+  fn as_log_mut(&mut self) -> &mut Self::Log {
+    &mut self.log
+  }
 }
 
 // Safety: although the `rarena_allocator::unsync::Arena` is not `Send` and `Sync`,
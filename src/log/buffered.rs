@@ -0,0 +1,269 @@
+#[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+use super::writer::flush_entry;
+use super::{
+  writer::{
+    alloc_entry, dealloc_entry, entry_size, reserve_entries, seal_in_place, truncate_oversize,
+  },
+  *,
+};
+
+/// A writer which coalesces the small inserts of a log, created by [`LogWriterExt::buffered`].
+///
+/// The writer reserves [`write_buffer`](crate::options::Options::write_buffer) bytes from the log up front,
+/// and places the values into the reserved space one after another, so that the allocation and the flush
+/// (if [`sync`](crate::options::Options::sync) is enabled) are paid once per reservation instead of once per value.
+/// The returned [`ValuePointer`]s are the final offsets in the log, the values can be read as soon as they are inserted.
+///
+/// The values whose entry does not fit in an empty reservation, or which are inserted when the log has less than
+/// `write_buffer` bytes left, are inserted through [`LogWriter::insert`] directly.
+///
+/// When the writer is dropped, the pending values are flushed (ignoring errors, use [`BufferedWriter::flush`] to handle them),
+/// and the unused space of the reservation is given back to the log.
+///
+/// Each writer owns its reservation, so multiple writers can be used on a concurrent log from different threads.
+#[derive(Debug)]
+pub struct BufferedWriter<'a, L: LogWriter + ?Sized> {
+  log: &'a L,
+  /// The start offset of the reservation.
+  offset: usize,
+  /// The size of the reservation.
+  cap: usize,
+  /// The number of bytes used in the reservation.
+  len: usize,
+  /// The number of bytes in the reservation which have been flushed.
+  #[cfg_attr(
+    not(all(feature = "memmap", not(target_family = "wasm"))),
+    allow(dead_code)
+  )]
+  flushed: usize,
+}
+
+impl<'a, L: LogWriter + ?Sized> BufferedWriter<'a, L> {
+  #[inline]
+  pub(super) const fn new(log: &'a L) -> Self {
+    Self {
+      log,
+      offset: 0,
+      cap: 0,
+      len: 0,
+      flushed: 0,
+    }
+  }
+
+  /// Returns the log of this writer.
+  #[inline]
+  pub const fn log(&self) -> &'a L {
+    self.log
+  }
+
+  /// Returns the number of bytes remaining in the current reservation.
+  ///
+  /// ## Example
+  ///
+  /// ```rust
+  /// use valog::{sync::ValueLog, Builder, LogWriterExt};
+  ///
+  /// let log = Builder::new()
+  ///   .with_capacity(1024)
+  ///   .with_write_buffer(64)
+  ///   .alloc::<ValueLog>(0)
+  ///   .unwrap();
+  ///
+  /// let mut writer = log.buffered();
+  /// assert_eq!(writer.remaining(), 0);
+  ///
+  /// writer.insert(b"foo").unwrap();
+  /// assert_eq!(writer.remaining(), 64 - 3 - 8);
+  /// ```
+  #[inline]
+  pub const fn remaining(&self) -> usize {
+    self.cap - self.len
+  }
+
+  /// Flushes the values inserted since the last flush to the disk in one call,
+  /// if [`sync`](crate::options::Options::sync) is enabled and the log is backed by a file.
+  ///
+  /// ## Example
+  ///
+  /// ```rust
+  /// use valog::{sync::ValueLog, Builder, LogWriterExt};
+  ///
+  /// let log = Builder::new()
+  ///   .with_capacity(1024)
+  ///   .with_write_buffer(256)
+  ///   .alloc::<ValueLog>(0)
+  ///   .unwrap();
+  ///
+  /// let mut writer = log.buffered();
+  /// writer.insert(b"foo").unwrap();
+  /// writer.insert(b"bar").unwrap();
+  /// writer.flush().unwrap();
+  /// ```
+  pub fn flush(&mut self) -> Result<(), Error> {
+    #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+    {
+      let allocator = self.log.allocator();
+      if self.len > self.flushed && self.log.options().sync() && allocator.is_ondisk() {
+        flush_entry(
+          self.log,
+          self.offset + self.flushed,
+          self.len - self.flushed,
+        )?;
+      }
+      self.flushed = self.len;
+    }
+
+    Ok(())
+  }
+
+  /// Flushes the pending values, gives the unused space back to the log, and reserves a new one.
+  fn refill(&mut self, size: usize) -> Result<(), Error> {
+    self.release()?;
+
+    let mut buf = alloc_entry(self.log, size)?;
+    self.offset = buf.offset();
+    self.cap = size;
+    // Safety: the reservation is managed by the writer, no need to drop
+    unsafe {
+      buf.detach();
+    }
+    Ok(())
+  }
+
+  /// Flushes the pending values, and gives the unused space of the reservation back to the log.
+  fn release(&mut self) -> Result<(), Error> {
+    if self.cap == 0 {
+      return Ok(());
+    }
+
+    let res = self.flush();
+    let unused = self.remaining();
+    if unused > 0 {
+      // SAFETY: the unused space of the reservation is never handed out.
+      unsafe {
+        dealloc_entry(
+          self.log.allocator(),
+          self.log.range_cursor(),
+          self.offset + self.len,
+          unused,
+        );
+      }
+    }
+
+    self.offset = 0;
+    self.cap = 0;
+    self.len = 0;
+    self.flushed = 0;
+    res
+  }
+}
+
+impl<L> BufferedWriter<'_, L>
+where
+  L: LogWriter + ?Sized,
+  L::Id: CheapClone + core::fmt::Debug,
+{
+  /// Inserts a value into the log, the value is placed in the reservation of the writer.
+  ///
+  /// ## Example
+  ///
+  /// ```rust
+  /// use valog::{sync::ValueLog, Builder, LogReader, LogWriterExt};
+  ///
+  /// let log = Builder::new()
+  ///   .with_capacity(1024)
+  ///   .with_write_buffer(256)
+  ///   .alloc::<ValueLog>(0)
+  ///   .unwrap();
+  ///
+  /// let mut writer = log.buffered();
+  /// let vp = writer.insert(b"Hello, valog!").unwrap();
+  /// writer.flush().unwrap();
+  ///
+  /// let data = unsafe { log.read(vp.id(), vp.offset(), vp.size()).unwrap() };
+  /// assert_eq!(data, b"Hello, valog!");
+  /// ```
+  pub fn insert(&mut self, value: &[u8]) -> Result<ValuePointer<L::Id>, Error> {
+    let opts = self.log.options();
+    let (value, truncated) = truncate_oversize(opts, value);
+    if value.is_empty() {
+      return Ok(ValuePointer::new(self.log.id().cheap_clone(), 0, 0).with_truncated(truncated));
+    }
+
+    let stored_len = value.len() + opts.seal_overhead();
+    let (_, total) = entry_size(opts, stored_len)?;
+    let size = opts.write_buffer() as usize;
+    if total > size {
+      // give back the unused space first, so that the value follows the buffered ones
+      self.release()?;
+      return self
+        .log
+        .insert(value)
+        .map(|vp| vp.with_truncated(truncated));
+    }
+
+    let entries = reserve_entries(self.log, 1)?;
+    if total > self.remaining() {
+      match self.refill(size) {
+        Ok(()) => {}
+        // the log has less than `write_buffer` bytes left, but the value may still fit
+        Err(Error::InsufficientSpace { .. }) => {
+          drop(entries);
+          return self
+            .log
+            .insert(value)
+            .map(|vp| vp.with_truncated(truncated));
+        }
+        Err(e) => return Err(e),
+      }
+    }
+
+    let cks_len = opts.checksum_len();
+    let align = opts.value_alignment;
+    let pos = self.offset + self.len;
+    let padding = pos.next_multiple_of(align) - pos;
+    let begin_offset = pos + padding;
+
+    // SAFETY: the reservation has at least `total` bytes remaining, which covers the padding,
+    // the sealed value and the checksum.
+    unsafe {
+      let ptr = self.log.allocator().get_pointer_mut(pos);
+      core::ptr::write_bytes(ptr, 0, padding);
+      core::ptr::copy_nonoverlapping(
+        value.as_ptr(),
+        ptr.add(padding + opts.nonce_size()),
+        value.len(),
+      );
+      seal_in_place(opts, ptr.add(padding), begin_offset, stored_len);
+      let checksum = self
+        .log
+        .checksum(core::slice::from_raw_parts(ptr.add(padding), stored_len));
+      core::ptr::copy_nonoverlapping(
+        checksum.to_le_bytes().as_ptr(),
+        ptr.add(padding + stored_len),
+        cks_len.len(),
+      );
+    }
+
+    if padding > 0 {
+      self.log.allocator().increase_discarded(padding as u32);
+    }
+    self.len += padding + stored_len + cks_len.len();
+    entries.commit();
+
+    Ok(
+      ValuePointer::new(
+        self.log.id().cheap_clone(),
+        begin_offset as u32,
+        stored_len as u32,
+      )
+      .with_truncated(truncated),
+    )
+  }
+}
+
+impl<L: LogWriter + ?Sized> Drop for BufferedWriter<'_, L> {
+  fn drop(&mut self) {
+    let _ = self.release();
+  }
+}
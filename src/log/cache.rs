@@ -0,0 +1,309 @@
+use std::{
+  collections::{BTreeMap, HashMap},
+  sync::{Arc, Mutex},
+  vec::Vec,
+};
+
+use super::*;
+
+/// The default number of values can be cached by a [`CachedLog`].
+pub const DEFAULT_CACHE_CAPACITY: usize = 1024;
+
+/// A wrapper of a log which memoizes the decoded values with a bounded LRU cache.
+///
+/// The reads through [`LogReader`] are delegated to the inner log directly, because they are zero-copy
+/// already. The cache is used by [`CachedLog::read_with`], which memoizes the values produced by an
+/// expensive decoding (e.g. decompression or decryption) keyed by `(offset, size)`, so a hot value is only
+/// decoded once until it is evicted.
+///
+/// The cached values are shared as [`Arc<[u8]>`](Arc), so they remain valid after being evicted.
+#[derive(Debug)]
+pub struct CachedLog<L> {
+  log: L,
+  cache: Mutex<Lru>,
+}
+
+impl<L> CachedLog<L> {
+  /// Creates a new cached log which caches at most [`DEFAULT_CACHE_CAPACITY`] values.
+  #[inline]
+  pub fn new(log: L) -> Self {
+    Self {
+      log,
+      cache: Mutex::new(Lru::new(DEFAULT_CACHE_CAPACITY)),
+    }
+  }
+
+  /// Sets the maximum number of values can be cached, the least recently used values are evicted
+  /// when the cache is full. `0` disables the cache.
+  ///
+  /// ## Example
+  ///
+  /// ```rust
+  /// use valog::{sync::ValueLog, Builder, CachedLog};
+  ///
+  /// let log = Builder::new().with_capacity(1024).alloc::<ValueLog>(0).unwrap();
+  /// let log = CachedLog::new(log).with_capacity(16);
+  /// assert_eq!(log.capacity(), 16);
+  /// ```
+  #[inline]
+  pub fn with_capacity(self, capacity: usize) -> Self {
+    let mut cache = self.cache.into_inner().unwrap_or_else(|e| e.into_inner());
+    cache.resize(capacity);
+    Self {
+      log: self.log,
+      cache: Mutex::new(cache),
+    }
+  }
+
+  /// Returns the maximum number of values can be cached.
+  #[inline]
+  pub fn capacity(&self) -> usize {
+    self.lock().capacity
+  }
+
+  /// Returns the number of the cached values.
+  #[inline]
+  pub fn len(&self) -> usize {
+    self.lock().map.len()
+  }
+
+  /// Returns `true` if there is no cached value.
+  #[inline]
+  pub fn is_empty(&self) -> bool {
+    self.len() == 0
+  }
+
+  /// Removes all the cached values.
+  #[inline]
+  pub fn clear(&self) {
+    self.lock().clear();
+  }
+
+  /// Removes the cached value of `offset..offset + len` if any.
+  #[inline]
+  pub fn invalidate(&self, offset: u32, len: u32) {
+    self.lock().remove((offset, len));
+  }
+
+  /// Returns the inner log.
+  #[inline]
+  pub const fn log(&self) -> &L {
+    &self.log
+  }
+
+  /// Consumes the cached log, and returns the inner log.
+  #[inline]
+  pub fn into_inner(self) -> L {
+    self.log
+  }
+
+  #[inline]
+  fn lock(&self) -> std::sync::MutexGuard<'_, Lru> {
+    self.cache.lock().unwrap_or_else(|e| e.into_inner())
+  }
+}
+
+impl<L: LogReader> CachedLog<L> {
+  /// Reads a value from the log, and returns the value decoded by `decode`, the decoded value is cached.
+  ///
+  /// On a cache hit, the cached value is returned without reading the log or calling `decode`.
+  /// The cache is keyed by `(offset, len)` only, so the same `decode` should be used for a log.
+  ///
+  /// ## Safety
+  /// - The buffer `offset..offset + len` must hold a valid bytes sequence.
+  ///
+  /// ## Example
+  ///
+  /// ```rust
+  /// use valog::{sync::ValueLog, Builder, CachedLog, Log, LogWriter};
+  ///
+  /// let log = CachedLog::new(Builder::new().with_capacity(1024).alloc::<ValueLog>(0).unwrap());
+  /// let vp = log.log().insert(b"hello").unwrap();
+  ///
+  /// let upper = |v: &[u8]| v.to_ascii_uppercase();
+  /// let data = unsafe { log.read_with(log.id(), vp.offset(), vp.size(), upper).unwrap() };
+  /// assert_eq!(&*data, b"HELLO");
+  ///
+  /// // the second read is served by the cache
+  /// let data = unsafe { log.read_with(log.id(), vp.offset(), vp.size(), |_| unreachable!()).unwrap() };
+  /// assert_eq!(&*data, b"HELLO");
+  /// ```
+  pub unsafe fn read_with<F>(
+    &self,
+    id: &L::Id,
+    offset: u32,
+    len: u32,
+    decode: F,
+  ) -> Result<Arc<[u8]>, Error>
+  where
+    L::Id: Eq,
+    F: FnOnce(&[u8]) -> Vec<u8>,
+  {
+    if self.log.id().ne(id) {
+      return Err(Error::IdMismatch);
+    }
+
+    if let Some(value) = self.lock().get((offset, len)) {
+      return Ok(value);
+    }
+
+    // decode without holding the lock, so that the other readers are not blocked.
+    let value: Arc<[u8]> = decode(self.log.read(id, offset, len)?).into();
+    self.lock().insert((offset, len), value.clone());
+    Ok(value)
+  }
+}
+
+impl<L: Log> sealed::Sealed for CachedLog<L> {
+  type Allocator = L::Allocator;
+
+  #[inline]
+  fn allocator(&self) -> &Self::Allocator {
+    self.log.allocator()
+  }
+
+  #[inline]
+  fn allocator_and_options_mut(&mut self) -> (&mut Self::Allocator, &mut Options) {
+    self.log.allocator_and_options_mut()
+  }
+
+  #[inline]
+  fn entry_counter(&self) -> Option<&core::sync::atomic::AtomicU32> {
+    self.log.entry_counter()
+  }
+
+  #[inline]
+  fn range_cursor(&self) -> Option<&core::sync::atomic::AtomicU32> {
+    self.log.range_cursor()
+  }
+
+  #[cfg(feature = "metrics")]
+  #[inline]
+  fn page_faults(&self) -> Option<&std::sync::OnceLock<super::PageFaultStats>> {
+    self.log.page_faults()
+  }
+
+  #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+  #[inline]
+  fn sync_file(&self) -> Option<&std::fs::File> {
+    self.log.sync_file()
+  }
+
+  #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+  #[inline]
+  fn shared_file_path(&self) -> Option<&std::path::Path> {
+    self.log.shared_file_path()
+  }
+
+  #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+  #[inline]
+  fn header_lock(&self) -> Option<&std::sync::Mutex<()>> {
+    self.log.header_lock()
+  }
+}
+
+impl<L: Log> Log for CachedLog<L> {
+  type Id = L::Id;
+
+  #[inline]
+  fn id(&self) -> &Self::Id {
+    self.log.id()
+  }
+
+  #[inline]
+  fn checksum(&self, bytes: &[u8]) -> u64 {
+    self.log.checksum(bytes)
+  }
+
+  #[inline]
+  fn options(&self) -> &Options {
+    self.log.options()
+  }
+
+  #[inline]
+  fn is_readonly(&self) -> bool {
+    self.log.is_readonly()
+  }
+}
+
+impl<L: LogReader> LogReader for CachedLog<L> {}
+
+impl<L: Frozen> Frozen for CachedLog<L> {}
+
+type Key = (u32, u32);
+
+/// A bounded LRU map, the recency is tracked by an increasing tick.
+#[derive(Debug)]
+struct Lru {
+  capacity: usize,
+  tick: u64,
+  map: HashMap<Key, (Arc<[u8]>, u64)>,
+  order: BTreeMap<u64, Key>,
+}
+
+impl Lru {
+  #[inline]
+  fn new(capacity: usize) -> Self {
+    Self {
+      capacity,
+      tick: 0,
+      map: HashMap::new(),
+      order: BTreeMap::new(),
+    }
+  }
+
+  fn get(&mut self, key: Key) -> Option<Arc<[u8]>> {
+    let tick = self.next_tick();
+    let (value, last) = self.map.get_mut(&key)?;
+    self.order.remove(last);
+    self.order.insert(tick, key);
+    *last = tick;
+    Some(value.clone())
+  }
+
+  fn insert(&mut self, key: Key, value: Arc<[u8]>) {
+    if self.capacity == 0 {
+      return;
+    }
+
+    self.remove(key);
+    while self.map.len() >= self.capacity {
+      self.evict();
+    }
+
+    let tick = self.next_tick();
+    self.map.insert(key, (value, tick));
+    self.order.insert(tick, key);
+  }
+
+  fn remove(&mut self, key: Key) {
+    if let Some((_, tick)) = self.map.remove(&key) {
+      self.order.remove(&tick);
+    }
+  }
+
+  fn resize(&mut self, capacity: usize) {
+    self.capacity = capacity;
+    while self.map.len() > capacity {
+      self.evict();
+    }
+  }
+
+  fn clear(&mut self) {
+    self.map.clear();
+    self.order.clear();
+  }
+
+  /// Evicts the least recently used value.
+  fn evict(&mut self) {
+    if let Some((_, key)) = self.order.pop_first() {
+      self.map.remove(&key);
+    }
+  }
+
+  #[inline]
+  fn next_tick(&mut self) -> u64 {
+    self.tick += 1;
+    self.tick
+  }
+}
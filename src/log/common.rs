@@ -40,6 +40,39 @@ pub trait Log: sealed::Sealed {
   /// ```
   fn checksum(&self, bytes: &[u8]) -> u64;
 
+  /// Calculates the checksum of a value exactly as the writer stores it.
+  ///
+  /// Only the payload bytes are hashed, the alignment padding before the value and the checksum
+  /// slot after the value are not included. The digest is truncated to the
+  /// [`checksum_len`](crate::options::Options::checksum_len) of the log, so the returned value
+  /// equals the checksum stored after the value, and can be passed to
+  /// [`insert_with_checksum`](crate::LogWriterExt::insert_with_checksum).
+  ///
+  /// ## Example
+  ///
+  /// ```rust
+  /// use valog::{sync::ValueLog, Builder, Log, LogWriterExt, options::ChecksumLen};
+  ///
+  /// let log = Builder::new()
+  ///   .with_capacity(100)
+  ///   .with_checksum_len(ChecksumLen::Four)
+  ///   .alloc::<ValueLog>(1)
+  ///   .unwrap();
+  ///
+  /// let payload = b"Hello, valog!";
+  /// let checksum = log.entry_checksum(payload);
+  /// assert_eq!(checksum, log.checksum(payload) & u32::MAX as u64);
+  ///
+  /// let vp = log.insert_with_checksum(payload, checksum).unwrap();
+  /// ```
+  #[inline]
+  fn entry_checksum(&self, payload: &[u8]) -> u64 {
+    self
+      .options()
+      .checksum_len()
+      .truncate(self.checksum(payload))
+  }
+
   /// Returns the options of the log.
   ///
   /// ## Example
@@ -87,7 +120,7 @@ pub trait Log: sealed::Sealed {
   ///   .alloc::<ValueLog>(1)
   ///   .unwrap();
   ///
-  /// assert_eq!(log.version(), 0);
+  /// assert_eq!(log.version(), 1);
   /// ```
   #[inline]
   fn version(&self) -> u16 {
@@ -117,6 +150,134 @@ pub trait Log: sealed::Sealed {
     self.allocator().discarded()
   }
 
+  /// Returns the statistics of the freelist of the log.
+  ///
+  /// The allocator does not expose the free segments it tracks, so the statistics only contain
+  /// the freelist kind, the discarded bytes and the minimum segment size.
+  ///
+  /// ## Example
+  ///
+  /// ```rust
+  /// use valog::{sync::ValueLog, Builder, Log, LogWriter, options::Freelist};
+  ///
+  /// let log = Builder::new()
+  ///   .with_capacity(100)
+  ///   .with_freelist(Freelist::Optimistic)
+  ///   .alloc::<ValueLog>(1)
+  ///   .unwrap();
+  ///
+  /// log.insert_tombstone(b"Hello, valog!").unwrap();
+  ///
+  /// let stats = log.freelist_stats();
+  /// assert_eq!(stats.kind(), Freelist::Optimistic);
+  /// assert_eq!(stats.discarded(), 13);
+  /// ```
+  #[inline]
+  fn freelist_stats(&self) -> FreelistStats {
+    let allocator = self.allocator();
+    FreelistStats {
+      kind: self.options().freelist(),
+      discarded: allocator.discarded(),
+      minimum_segment_size: allocator.minimum_segment_size(),
+    }
+  }
+
+  /// Returns the page faults taken by the first [`scrub`](crate::LogReader::scrub) of the log,
+  /// `None` if the log has not been scanned yet, or the platform does not account the page faults.
+  ///
+  /// Only the faults of the scanning thread are counted. Comparing the major page faults of a log opened
+  /// with and without [`populate`](crate::Builder::with_populate) tells whether prefaulting helps the workload.
+  ///
+  /// ## Example
+  ///
+  /// ```rust
+  /// use valog::{sync::ValueLog, Builder, Log, LogReader, LogWriter};
+  ///
+  /// let log = Builder::new()
+  ///   .with_capacity(1024)
+  ///   .alloc::<ValueLog>(0)
+  ///   .unwrap();
+  ///
+  /// let ptrs = [log.insert(b"foo").unwrap()];
+  /// assert!(log.page_fault_stats().is_none());
+  ///
+  /// unsafe { log.scrub(&ptrs).unwrap() };
+  /// assert_eq!(log.page_fault_stats().is_some(), cfg!(target_os = "linux"));
+  /// ```
+  #[cfg(feature = "metrics")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "metrics")))]
+  #[inline]
+  fn page_fault_stats(&self) -> Option<PageFaultStats> {
+    self.page_faults()?.get().copied()
+  }
+
+  /// Returns the usage of the log, which is the ratio of the allocated bytes to the capacity, in `0.0..=1.0`.
+  ///
+  /// The allocated bytes include the header and the reserved bytes. For a log created by
+  /// [`Builder::with_external_arena`](crate::Builder::with_external_arena), the ratio is calculated in its range of the arena.
+  ///
+  /// ## Example
+  ///
+  /// ```rust
+  /// use valog::{sync::ValueLog, Builder, Log, LogWriter};
+  ///
+  /// let log = Builder::new()
+  ///   .with_capacity(100)
+  ///   .alloc::<ValueLog>(1)
+  ///   .unwrap();
+  ///
+  /// assert_eq!(log.usage(), 0.36);
+  ///
+  /// log.insert(b"Hello, valog!").unwrap();
+  /// assert_eq!(log.usage(), 0.57);
+  /// ```
+  #[inline]
+  fn usage(&self) -> f64 {
+    let allocator = self.allocator();
+    let allocated = allocator.allocated();
+    let (used, capacity) = match self.options().sub_range() {
+      Some((start, end)) => (
+        self
+          .range_cursor()
+          .map_or(allocated, |cursor| {
+            cursor.load(core::sync::atomic::Ordering::Acquire) as usize
+          })
+          .clamp(start as usize, end as usize)
+          - start as usize,
+        (end - start) as usize,
+      ),
+      None => (allocated, allocator.capacity()),
+    };
+
+    if capacity == 0 {
+      return 1.0;
+    }
+    used as f64 / capacity as f64
+  }
+
+  /// Returns `true` if the [`usage`](Log::usage) of the log exceeds the `threshold`,
+  /// which is useful to decide when to seal the log and rotate to a new one.
+  ///
+  /// ## Example
+  ///
+  /// ```rust
+  /// use valog::{sync::ValueLog, Builder, Log, LogWriter};
+  ///
+  /// let log = Builder::new()
+  ///   .with_capacity(100)
+  ///   .alloc::<ValueLog>(1)
+  ///   .unwrap();
+  ///
+  /// assert!(!log.near_full(0.8));
+  ///
+  /// log.insert(&[0; 50]).unwrap();
+  /// assert!(log.near_full(0.8));
+  /// ```
+  #[inline]
+  fn near_full(&self, threshold: f64) -> bool {
+    self.usage() > threshold
+  }
+
   /// Returns the data offset of the log.
   ///
   /// ## Example
@@ -129,7 +290,7 @@ pub trait Log: sealed::Sealed {
   ///   .alloc::<ValueLog>(1)
   ///   .unwrap();
   ///
-  /// assert_eq!(log.data_offset(), 9); // header size is 8, so data start at 9.
+  /// assert_eq!(log.data_offset(), 36); // header size is 35, so data start at 36.
   ///
   /// let log = Builder::new()
   ///   .with_capacity(100)
@@ -137,12 +298,73 @@ pub trait Log: sealed::Sealed {
   ///   .alloc::<ValueLog>(1)
   ///   .unwrap();
   ///
-  /// assert_eq!(log.data_offset(), 17); // header size is 8, reserved is 8, so data start at 17.
+  /// assert_eq!(log.data_offset(), 44); // header size is 35, reserved is 8, so data start at 44.
   /// ```
   fn data_offset(&self) -> usize {
     Allocator::data_offset(self.allocator())
   }
 
+  /// Returns the real capacity of the log reported by the allocator, including the header and the reserved bytes.
+  ///
+  /// This may differ from [`Options::capacity`], e.g. the capacity of a memory map backed log is rounded
+  /// up to the page size, and the capacity of a reopened log is the size of the file. For a log created by
+  /// [`Builder::with_external_arena`](crate::Builder::with_external_arena), this is the length of its range of the arena.
+  ///
+  /// ## Example
+  ///
+  /// ```rust
+  /// use valog::{sync::ValueLog, Builder, Log};
+  ///
+  /// let log = Builder::new()
+  ///   .with_capacity(100)
+  ///   .alloc::<ValueLog>(1)
+  ///   .unwrap();
+  ///
+  /// assert_eq!(log.capacity(), 100);
+  /// ```
+  #[inline]
+  fn capacity(&self) -> u32 {
+    match self.options().sub_range() {
+      Some((start, end)) => end - start,
+      None => self.allocator().capacity() as u32,
+    }
+  }
+
+  /// Returns the whole allocated memory of the log, including the header, the reserved bytes and all of the values.
+  ///
+  /// This is only available for the frozen logs, as the content cannot be changed anymore,
+  /// which is useful for hashing a sealed log without copying.
+  ///
+  /// ## Example
+  ///
+  /// ```rust
+  /// use valog::{sync::{ValueLog, ImmutableValueLog}, Builder, Log, LogExt, LogWriter};
+  ///
+  /// let log = Builder::new()
+  ///   .with_capacity(100)
+  ///   .with_unify(true)
+  ///   .alloc::<ValueLog>(1)
+  ///   .unwrap();
+  /// log.insert(b"Hello, valog!").unwrap();
+  ///
+  /// let mut buf = Vec::new();
+  /// log.flush_to(&mut buf).unwrap();
+  ///
+  /// let log = Builder::new().from_bytes::<ImmutableValueLog>(&buf, 1).unwrap();
+  /// assert_eq!(log.as_slice().len(), buf.len());
+  ///
+  /// // the allocator metadata before the data offset is not copied by `from_bytes`
+  /// let data_offset = log.data_offset();
+  /// assert_eq!(&log.as_slice()[data_offset..], &buf[data_offset..]);
+  /// ```
+  #[inline]
+  fn as_slice(&self) -> &[u8]
+  where
+    Self: Frozen,
+  {
+    self.allocator().allocated_memory()
+  }
+
   /// Returns the path of the log.
   ///
   /// If the log is in memory, this method will return `None`.
@@ -180,6 +402,96 @@ pub trait Log: sealed::Sealed {
     self.allocator().path()
   }
 
+  /// Advises the OS to page out the mapped pages of the file backed log, so that the pages will not pollute the page cache.
+  ///
+  /// The mapped pages are paged out by `MADV_PAGEOUT`, if it is not supported by the kernel, falls back to
+  /// `posix_fadvise(POSIX_FADV_DONTNEED)` over the file, which drops the clean pages of the file from the page cache.
+  /// The content of the log is not affected, the pages will be loaded from the file again when accessed.
+  ///
+  /// This method is called automatically after bulk scans if [`Options::direct_io`](crate::options::Options::direct_io) is enabled.
+  /// It only works on Linux and Android, and is a no-op for the logs which are not backed by a shared file mapping,
+  /// e.g. the anonymous memory map, the private memory map created by [`Builder::map_cow`](crate::Builder::map_cow),
+  /// and the vec backed log.
+  ///
+  /// ## Example
+  ///
+  /// ```rust
+  /// use valog::{sync::ValueLog, Builder, Log};
+  ///
+  /// # #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+  /// # {
+  /// let log = Builder::new()
+  ///   .with_capacity(100)
+  ///   .alloc::<ValueLog>(1)
+  ///   .unwrap();
+  ///
+  /// log.evict_page_cache().unwrap();
+  /// # }
+  /// ```
+  #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+  #[cfg_attr(docsrs, doc(cfg(all(feature = "memmap", not(target_family = "wasm")))))]
+  #[inline]
+  fn evict_page_cache(&self) -> std::io::Result<()> {
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    {
+      use rustix::mm::{madvise, Advice};
+      use std::os::fd::AsRawFd;
+
+      let allocator = self.allocator();
+      let path = match self.shared_file_path() {
+        Some(path) if allocator.is_map_file() => path,
+        _ => return Ok(()),
+      };
+
+      // madvise requires a page aligned address.
+      let ptr = allocator.raw_ptr() as usize;
+      let aligned = ptr - ptr % allocator.page_size();
+      let len = allocator.capacity() + (ptr - aligned);
+      let ptr = aligned as *mut core::ffi::c_void;
+
+      // Safety: the range is in the shared file backed memory map, the content of the pages is kept by the page cache or the file.
+      match unsafe { madvise(ptr, len, Advice::LinuxPageOut) } {
+        Err(e) if e == rustix::io::Errno::INVAL => {}
+        res => return res.map_err(|e| std::io::Error::from_raw_os_error(e.raw_os_error())),
+      }
+
+      // `MADV_PAGEOUT` is not supported by the kernel, advises on the file instead of the mapping,
+      // so that the mapped pages are never discarded.
+      let file = std::fs::File::open(path)?;
+      // Safety: the file descriptor is valid as long as `file` is alive.
+      match unsafe { libc::posix_fadvise(file.as_raw_fd(), 0, 0, libc::POSIX_FADV_DONTNEED) } {
+        0 => Ok(()),
+        errno => Err(std::io::Error::from_raw_os_error(errno)),
+      }
+    }
+
+    #[cfg(not(any(target_os = "linux", target_os = "android")))]
+    Ok(())
+  }
+
+  /// Returns `true` if the log does not support writes, which is the runtime counterpart of the [`Frozen`] and
+  /// [`Mutable`] markers, so that the generic tooling accepting either can branch on the capability.
+  ///
+  /// The frozen logs are always read-only. Otherwise, it reflects whether the backend was opened writable,
+  /// e.g. a file mapped by [`Builder::map`](crate::Builder::map) is read-only.
+  ///
+  /// ## Example
+  ///
+  /// ```rust
+  /// use valog::{sync::ValueLog, Builder, Log};
+  ///
+  /// let log = Builder::new()
+  ///   .with_capacity(100)
+  ///   .alloc::<ValueLog>(1)
+  ///   .unwrap();
+  /// assert!(!log.is_readonly());
+  /// assert!(log.as_immutable().is_readonly());
+  /// ```
+  #[inline]
+  fn is_readonly(&self) -> bool {
+    self.allocator().read_only()
+  }
+
   /// Returns `true` if the log is in memory.
   ///
   /// ## Example
@@ -251,6 +563,46 @@ pub trait Log: sealed::Sealed {
     self.allocator().is_ondisk()
   }
 
+  /// Returns `true` if the inserts durably flush the written entries to disk.
+  ///
+  /// The entries are flushed only if [`sync`](crate::options::Options::sync) is enabled and the log is
+  /// a writable [on-disk](Log::on_disk) log, the in-memory and the read-only logs never sync.
+  ///
+  /// ## Example
+  ///
+  /// ```rust
+  /// use valog::{sync::ValueLog, Builder, Log};
+  ///
+  /// let log = Builder::new()
+  ///   .with_capacity(100)
+  ///   .alloc::<ValueLog>(1)
+  ///   .unwrap();
+  /// assert!(!log.will_sync());
+  ///
+  /// # #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+  /// # {
+  /// let path = tempfile::NamedTempFile::new().unwrap().into_temp_path();
+  /// # std::fs::remove_file(&path);
+  /// let log = unsafe {
+  ///   Builder::new()
+  ///     .with_capacity(100)
+  ///     .with_create(true)
+  ///     .with_write(true)
+  ///     .with_read(true)
+  ///     .map_mut::<ValueLog, _>(&path, 0)
+  ///     .unwrap()
+  /// };
+  /// assert!(log.will_sync());
+  /// # }
+  /// ```
+  #[inline]
+  fn will_sync(&self) -> bool {
+    cfg!(all(feature = "memmap", not(target_family = "wasm")))
+      && !self.is_readonly()
+      && self.options().sync()
+      && self.on_disk()
+  }
+
   /// Returns `true` if the log is using a memory map backend.
   ///
   /// ## Example
@@ -295,44 +647,374 @@ pub trait Log: sealed::Sealed {
     self.allocator().is_map()
   }
 
-  /// Returns the reserved space in the WAL.
+  /// Returns `true` if the meta page of the log is actually locked in memory by
+  /// [`lock_meta`](crate::options::Options::lock_meta).
+  ///
+  /// The meta page is locked only for the memory map backed logs on the non-Windows platforms,
+  /// so this can be used in a startup check to fail loudly on the platforms where `lock_meta` does not apply.
+  /// A log opened over an external arena reports the configuration of its own options.
+  ///
+  /// ## Example
+  ///
+  /// ```rust
+  /// use valog::{sync::ValueLog, Builder, Log};
+  ///
+  /// let log = Builder::new()
+  ///   .with_capacity(100)
+  ///   .with_lock_meta(true)
+  ///   .alloc::<ValueLog>(1)
+  ///   .unwrap();
+  /// assert!(!log.meta_locked());
+  ///
+  /// # #[cfg(all(feature = "memmap", not(target_family = "wasm"), not(windows)))]
+  /// # {
+  /// let log = Builder::new()
+  ///   .with_capacity(100)
+  ///   .with_lock_meta(true)
+  ///   .map_anon::<ValueLog>(0)
+  ///   .unwrap();
+  /// assert!(log.meta_locked());
+  /// # }
+  /// ```
+  #[inline]
+  fn meta_locked(&self) -> bool {
+    #[cfg(all(feature = "memmap", not(target_family = "wasm"), not(windows)))]
+    {
+      self.options().lock_meta() && self.is_map()
+    }
+
+    #[cfg(not(all(feature = "memmap", not(target_family = "wasm"), not(windows))))]
+    false
+  }
+
+  /// Returns the page size of the OS, which is used to align the capacity of the memory map backed log.
+  ///
+  /// ## Example
+  ///
+  /// ```rust
+  /// use valog::{sync::ValueLog, Builder, Log};
+  ///
+  /// # #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+  /// # {
+  /// let log = Builder::new()
+  ///   .with_capacity(100)
+  ///   .map_anon::<ValueLog>(0)
+  ///   .unwrap();
+  ///
+  /// assert!(log.page_size().is_power_of_two());
+  /// assert_eq!(log.options().capacity() as usize % log.page_size(), 0);
+  /// # }
+  /// ```
+  #[inline]
+  fn page_size(&self) -> usize {
+    self.allocator().page_size()
+  }
+
+  /// Returns a stable fingerprint of the configuration which affects the on-disk layout of the log.
+  ///
+  /// Two logs with the same fingerprint agree on the format, so the fingerprint can be exchanged
+  /// out-of-band (e.g. recorded in a manifest) and compared before trusting a file. The hashed fields are,
+  /// in order:
+  /// - the magic text and the on-disk format version of the header,
+  /// - the [`magic_version`](crate::options::Options::magic_version),
+  /// - the [`checksum_len`](crate::options::Options::checksum_len),
+  /// - the checksum of a fixed probe computed by the checksumer, which identifies the checksum algorithm,
+  /// - the width of the [`Id`](Log::Id) type,
+  /// - the [`reserved`](crate::options::Options::reserved) bytes,
+  /// - the [`value_alignment`](crate::options::Options::value_alignment).
+  ///
+  /// The values are always encoded in little-endian. The fingerprint is reproducible across runs and platforms,
+  /// the in-memory only options, e.g. the capacity and the sync policy, are not hashed.
+  ///
+  /// ## Example
+  ///
+  /// ```rust
+  /// use valog::{sync::ValueLog, Builder, Log};
+  ///
+  /// let a = Builder::new().with_capacity(100).alloc::<ValueLog>(1).unwrap();
+  /// let b = Builder::new().with_capacity(200).alloc::<ValueLog>(2).unwrap();
+  /// assert_eq!(a.config_fingerprint(), b.config_fingerprint());
+  ///
+  /// let c = Builder::new()
+  ///   .with_capacity(100)
+  ///   .with_reserved(8)
+  ///   .alloc::<ValueLog>(1)
+  ///   .unwrap();
+  /// assert_ne!(a.config_fingerprint(), c.config_fingerprint());
+  /// ```
+  #[inline]
+  fn config_fingerprint(&self) -> u64 {
+    const PROBE: &[u8] = b"valog";
+
+    self
+      .options()
+      .fingerprint(self.checksum(PROBE), core::mem::size_of::<Self::Id>())
+  }
+
+  /// Returns the length of the checksum stored after each non-empty value, in bytes.
+  ///
+  /// The on-disk size of a non-empty value is `size + checksum_len()` (plus the alignment padding),
+  /// use this instead of hard-coding the length, which is configured by
+  /// [`Options::with_checksum_len`](crate::options::Options::with_checksum_len).
+  ///
+  /// ## Example
+  ///
+  /// ```rust
+  /// use valog::{options::ChecksumLen, sync::ValueLog, Builder, Log, LogWriter};
+  ///
+  /// let log = Builder::new().with_capacity(100).alloc::<ValueLog>(1).unwrap();
+  /// assert_eq!(log.checksum_len(), 8);
+  ///
+  /// let log = Builder::new()
+  ///   .with_capacity(100)
+  ///   .with_checksum_len(ChecksumLen::Four)
+  ///   .alloc::<ValueLog>(1)
+  ///   .unwrap();
+  /// assert_eq!(log.checksum_len(), 4);
+  ///
+  /// let foo = log.insert(b"foo").unwrap();
+  /// let bar = log.insert(b"bar").unwrap();
+  /// assert_eq!(bar.offset() as usize, foo.offset() as usize + foo.size() as usize + log.checksum_len());
+  /// ```
+  #[inline]
+  fn checksum_len(&self) -> usize {
+    self.options().checksum_len().len()
+  }
+
+  /// Returns the fixed number of bytes each entry takes besides the value, for the current configuration.
+  ///
+  /// The overhead is the length of the stored checksum plus the padding reserved to align the value,
+  /// so inserting a value of `value_len` bytes requires at most `value_len + entry_overhead()` bytes.
+  ///
+  /// ## Example
+  ///
+  /// ```rust
+  /// use valog::{options::ChecksumLen, sync::ValueLog, Builder, Log};
+  ///
+  /// let log = Builder::new().with_capacity(100).alloc::<ValueLog>(1).unwrap();
+  /// assert_eq!(log.entry_overhead(), 8);
+  ///
+  /// let log = Builder::new()
+  ///   .with_capacity(100)
+  ///   .with_checksum_len(ChecksumLen::Four)
+  ///   .with_value_alignment(8)
+  ///   .alloc::<ValueLog>(1)
+  ///   .unwrap();
+  /// assert_eq!(log.entry_overhead(), 4 + 7);
+  /// ```
+  #[inline]
+  fn entry_overhead(&self) -> u32 {
+    (self.checksum_len() + self.options().value_alignment() - 1) as u32
+  }
+
+  /// Returns the length of the reserved space in the WAL, which is the same as the length of
+  /// [`reserved_slice`](Log::reserved_slice).
+  ///
+  /// ## Example
+  ///
+  /// ```rust
+  /// use valog::{sync::ValueLog, Builder, Log};
+  ///
+  /// let log = Builder::new()
+  ///   .with_capacity(100)
+  ///   .alloc::<ValueLog>(1)
+  ///   .unwrap();
+  /// assert_eq!(log.reserved_len(), 0);
+  ///
+  /// let log = Builder::new()
+  ///   .with_capacity(100)
+  ///   .with_reserved(8)
+  ///   .alloc::<ValueLog>(1)
+  ///   .unwrap();
+  /// assert_eq!(log.reserved_len(), 8);
+  /// ```
+  #[inline]
+  fn reserved_len(&self) -> u32 {
+    self.options().reserved()
+  }
+
+  /// Returns the reserved space in the WAL.
+  ///
+  /// ## Safety
+  /// - The writer must ensure that the returned slice is not modified.
+  /// - This method is not thread-safe, so be careful when using it.
+  ///
+  /// ## Example
+  ///
+  /// ```rust
+  /// use valog::{sync::ValueLog, Builder, Log};
+  ///
+  /// let log = Builder::new()
+  ///   .with_capacity(100)
+  ///   .alloc::<ValueLog>(1)
+  ///   .unwrap();
+  ///
+  /// let reserved = unsafe { log.reserved_slice() };
+  /// assert!(reserved.is_empty());
+  ///
+  /// let log = Builder::new()
+  ///   .with_capacity(100)
+  ///   .with_reserved(8)
+  ///   .alloc::<ValueLog>(1)
+  ///   .unwrap();
+  ///
+  /// let reserved = unsafe { log.reserved_slice() };
+  /// assert_eq!(reserved.len(), 8);
+  /// ```
+  #[inline]
+  unsafe fn reserved_slice(&self) -> &[u8] {
+    let reserved = self.options().reserved();
+    if reserved == 0 {
+      return &[];
+    }
+
+    let allocator = self.allocator();
+    let reserved_slice = allocator.reserved_slice();
+    &reserved_slice[self.options().header_size()..]
+  }
+
+  /// Returns the reserved region at `index`, which is configured by [`Builder::with_reserved_regions`](crate::Builder::with_reserved_regions).
+  ///
+  /// ## Safety
+  /// - The caller must ensure that the there is no others accessing reserved region for write.
+  ///
+  /// ## Panics
+  /// - If `index` is out of the bounds of the reserved regions.
+  ///
+  /// ## Example
+  ///
+  /// ```rust
+  /// use valog::{sync::ValueLog, Builder, Log};
+  ///
+  /// let log = Builder::new()
+  ///   .with_capacity(100)
+  ///   .with_reserved_regions(&[4, 8])
+  ///   .alloc::<ValueLog>(1)
+  ///   .unwrap();
+  ///
+  /// let region = unsafe { log.reserved_region(1) };
+  /// assert_eq!(region.len(), 8);
+  /// ```
+  #[inline]
+  unsafe fn reserved_region(&self, index: usize) -> &[u8] {
+    let range = match self.options().reserved_region_range(index) {
+      Some(range) => range,
+      None => panic!("reserved region index {index} out of bounds"),
+    };
+    &self.reserved_slice()[range]
+  }
+
+  /// Returns the raw bytes `offset..offset + len` of the log, e.g. for inspection and debugging tools.
+  ///
+  /// Unlike [`LogReader::read`](crate::LogReader::read), the range is checked against `0..allocated`
+  /// only, so it can span the header and the reserved bytes, and no checksum is validated.
+  ///
+  /// ## Safety
+  /// - The caller must ensure that the range is not being written concurrently.
+  ///
+  /// ## Example
+  ///
+  /// ```rust
+  /// use valog::{error::Error, sync::ValueLog, Builder, Log, LogWriter};
+  ///
+  /// let log = Builder::new()
+  ///   .with_capacity(100)
+  ///   .with_unify(true)
+  ///   .alloc::<ValueLog>(1)
+  ///   .unwrap();
+  /// let vp = log.insert(b"Hello, valog!").unwrap();
+  ///
+  /// let entry = unsafe { log.read_raw_range(vp.offset() as usize, vp.size() as usize + 8).unwrap() };
+  /// assert_eq!(&entry[..13], b"Hello, valog!");
+  ///
+  /// let err = unsafe { log.read_raw_range(0, 1024).unwrap_err() };
+  /// assert!(matches!(err, Error::OutOfBounds { .. }));
+  /// ```
+  unsafe fn read_raw_range(&self, offset: usize, len: usize) -> Result<&[u8], Error> {
+    let allocator = self.allocator();
+    let allocated = allocator.allocated();
+    if offset.checked_add(len).map_or(true, |end| end > allocated) {
+      return Err(Error::out_of_bounds(
+        offset.min(u32::MAX as usize) as u32,
+        len.min(u32::MAX as usize) as u32,
+        0,
+        allocated as u32,
+      ));
+    }
+
+    if len == 0 {
+      return Ok(&[]);
+    }
+
+    // Safety: we have checked the bounds
+    Ok(allocator.get_bytes(offset, len))
+  }
+
+  /// Returns the value pointers recorded in the footer written by [`MutableLog::write_footer`],
+  /// or `None` if the footer has never been written.
   ///
-  /// ## Safety
-  /// - The writer must ensure that the returned slice is not modified.
-  /// - This method is not thread-safe, so be careful when using it.
+  /// The footer is always validated by its checksum, regardless of the
+  /// [`validate_checksum`](crate::options::Options::validate_checksum) configuration.
   ///
   /// ## Example
   ///
   /// ```rust
-  /// use valog::{sync::ValueLog, Builder, Log};
-  ///
-  /// let log = Builder::new()
-  ///   .with_capacity(100)
-  ///   .alloc::<ValueLog>(1)
-  ///   .unwrap();
-  ///
-  /// let reserved = unsafe { log.reserved_slice() };
-  /// assert!(reserved.is_empty());
+  /// use valog::{sync::ValueLog, Builder, Log, LogWriter, MutableLog};
   ///
   /// let log = Builder::new()
-  ///   .with_capacity(100)
-  ///   .with_reserved(8)
+  ///   .with_capacity(1024)
   ///   .alloc::<ValueLog>(1)
   ///   .unwrap();
+  /// assert!(log.footer().unwrap().is_none());
   ///
-  /// let reserved = unsafe { log.reserved_slice() };
-  /// assert_eq!(reserved.len(), 8);
+  /// let ptrs = [log.insert(b"foo").unwrap(), log.insert_tombstone(b"bar").unwrap()];
+  /// log.write_footer(&ptrs).unwrap();
+  /// assert_eq!(log.footer().unwrap().unwrap(), ptrs);
   /// ```
-  #[inline]
-  unsafe fn reserved_slice(&self) -> &[u8] {
-    let reserved = self.options().reserved();
-    if reserved == 0 {
-      return &[];
+  #[cfg(any(feature = "alloc", feature = "std"))]
+  #[cfg_attr(docsrs, doc(cfg(any(feature = "alloc", feature = "std"))))]
+  fn footer(&self) -> Result<Option<std::vec::Vec<ValuePointer<Self::Id>>>, Error>
+  where
+    Self: LogReader,
+    Self::Id: Eq + CheapClone,
+  {
+    let header = self.allocator().reserved_slice();
+    // the header of the version `0` layout does not record a footer.
+    if self.options().is_legacy() || header.len() < HEADER_SIZE {
+      return Ok(None);
+    }
+    let offset = crate::options::footer_offset(header);
+    if offset == 0 {
+      return Ok(None);
     }
 
-    let allocator = self.allocator();
-    let reserved_slice = allocator.reserved_slice();
-    &reserved_slice[HEADER_SIZE..]
+    // a corrupted count is saturated, so that it is reported as out of bounds when reading.
+    let id = self.id();
+    // Safety: the bounds are checked when reading.
+    let count = unsafe { reader::read_in(self, id, offset, 4, false)? };
+    let count = u32::from_le_bytes(count.try_into().unwrap()) as u64;
+    let len = (4 + count * FOOTER_ENTRY_SIZE as u64).min((u32::MAX - offset) as u64) as u32;
+    // Safety: the bounds are checked when reading.
+    let buf = unsafe { reader::read_in(self, id, offset, len, true)? };
+
+    Ok(Some(
+      buf[4..]
+        .chunks_exact(FOOTER_ENTRY_SIZE)
+        .map(|entry| {
+          let vp = ValuePointer::new(
+            id.cheap_clone(),
+            u32::from_le_bytes(entry[..4].try_into().unwrap()),
+            u32::from_le_bytes(entry[4..8].try_into().unwrap()),
+          )
+          .with_truncated(entry[8] & FOOTER_TRUNCATED != 0);
+          if entry[8] & FOOTER_TOMBSTONE != 0 {
+            vp.with_tombstone()
+          } else {
+            vp
+          }
+        })
+        .collect(),
+    ))
   }
 
   /// Locks the underlying file for exclusive access, only works on mmap with a file backend.
@@ -529,16 +1211,215 @@ pub trait LogExt: Log {
   #[cfg_attr(docsrs, doc(cfg(feature = "std")))]
   #[inline]
   fn flush_to(&self, w: &mut impl std::io::Write) -> std::io::Result<()> {
-    w.write_all(self.allocator().allocated_memory())
+    w.write_all(self.allocator().allocated_memory())?;
+
+    #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+    evict_after_scan(self)?;
+
+    Ok(())
+  }
+
+  /// Flushes the whole log to the given writer in `chunk` sized pieces, and flushes the writer after each piece.
+  ///
+  /// Unlike [`flush_to`](LogExt::flush_to), which hands the whole allocated memory to the writer in one call,
+  /// this gives a slow writer (e.g. a network or rate-limited writer) a chance to apply backpressure
+  /// between the pieces, which is useful for streaming a large log.
+  ///
+  /// Returns an error of [`InvalidInput`](std::io::ErrorKind::InvalidInput) if `chunk` is `0`.
+  ///
+  /// ## Example
+  ///
+  /// ```rust
+  /// use valog::{sync::ValueLog, Builder, Log, LogExt, LogWriter};
+  ///
+  /// let log = Builder::new()
+  ///   .with_capacity(100)
+  ///   .alloc::<ValueLog>(1)
+  ///   .unwrap();
+  /// log.insert(b"Hello, valog!").unwrap();
+  ///
+  /// let mut whole = Vec::new();
+  /// log.flush_to(&mut whole).unwrap();
+  ///
+  /// let mut chunked = Vec::new();
+  /// log.flush_to_chunked(&mut chunked, 8).unwrap();
+  /// assert_eq!(chunked, whole);
+  ///
+  /// assert!(log.flush_to_chunked(&mut chunked, 0).is_err());
+  /// ```
+  #[cfg(feature = "std")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+  fn flush_to_chunked(&self, w: &mut impl std::io::Write, chunk: usize) -> std::io::Result<()> {
+    if chunk == 0 {
+      return Err(std::io::Error::new(
+        std::io::ErrorKind::InvalidInput,
+        "chunk size must be greater than 0",
+      ));
+    }
+
+    for piece in self.allocator().allocated_memory().chunks(chunk) {
+      w.write_all(piece)?;
+      w.flush()?;
+    }
+
+    #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+    evict_after_scan(self)?;
+
+    Ok(())
+  }
+
+  /// Flushes the bytes of the log in `from..to` to the given writer, the offsets are relative to the
+  /// start of the log (including the header), same as the offsets of [`ValuePointer`].
+  ///
+  /// This can be used for incremental backups of the append-only log: track the last flushed offset and
+  /// only ship the bytes written since then.
+  ///
+  /// Returns an error of [`InvalidInput`](std::io::ErrorKind::InvalidInput) if `from > to` or `to` is
+  /// greater than the allocated bytes of the log.
+  ///
+  /// ## Example
+  ///
+  /// ```rust
+  /// use valog::{sync::ValueLog, Builder, Log, LogExt, LogWriter};
+  ///
+  /// let log = Builder::new()
+  ///   .with_capacity(100)
+  ///   .alloc::<ValueLog>(1)
+  ///   .unwrap();
+  ///
+  /// let mut backup = Vec::new();
+  /// log.flush_to(&mut backup).unwrap();
+  ///
+  /// let vp = log.insert(b"Hello, valog!").unwrap();
+  /// let end = (vp.offset() + vp.size()) as usize + log.options().checksum_len().len();
+  /// let last = backup.len();
+  /// log.flush_to_range(&mut backup, last, end).unwrap();
+  /// assert_eq!(backup.len(), end);
+  ///
+  /// assert!(log.flush_to_range(&mut backup, end, end + 1).is_err());
+  /// ```
+  #[cfg(feature = "std")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+  fn flush_to_range(
+    &self,
+    w: &mut impl std::io::Write,
+    from: usize,
+    to: usize,
+  ) -> std::io::Result<()> {
+    let memory = self.allocator().allocated_memory();
+    if from > to || to > memory.len() {
+      return Err(std::io::Error::new(
+        std::io::ErrorKind::InvalidInput,
+        Error::out_of_bounds(
+          from as u32,
+          to.saturating_sub(from) as u32,
+          0,
+          memory.len() as u32,
+        ),
+      ));
+    }
+
+    w.write_all(&memory[from..to])
+  }
+}
+
+/// Evicts the page cache of the log after a bulk scan, if [`Options::direct_io`] is enabled.
+#[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+#[inline]
+pub(crate) fn evict_after_scan<L: Log + ?Sized>(l: &L) -> std::io::Result<()> {
+  if l.options().direct_io() {
+    l.evict_page_cache()
+  } else {
+    Ok(())
+  }
+}
+
+/// Runs the scan `f`, and records the page faults it takes if it is the first scan of the log.
+#[cfg(feature = "metrics")]
+pub(crate) fn record_page_faults<L, T, E>(l: &L, f: impl FnOnce() -> Result<T, E>) -> Result<T, E>
+where
+  L: Log + ?Sized,
+{
+  let cell = match l.page_faults() {
+    Some(cell) if cell.get().is_none() => cell,
+    _ => return f(),
+  };
+
+  let before = page_faults_of_thread();
+  let res = f()?;
+  if let (Some(before), Some(after)) = (before, page_faults_of_thread()) {
+    let _ = cell.set(PageFaultStats {
+      major: after.major.saturating_sub(before.major),
+      minor: after.minor.saturating_sub(before.minor),
+    });
+  }
+  Ok(res)
+}
+
+/// Returns the page faults taken by the current thread so far, `None` if the platform does not account them.
+#[cfg(feature = "metrics")]
+fn page_faults_of_thread() -> Option<PageFaultStats> {
+  #[cfg(target_os = "linux")]
+  {
+    let mut usage = core::mem::MaybeUninit::<libc::rusage>::uninit();
+    // Safety: `usage` is a valid pointer to write the resource usage.
+    if unsafe { libc::getrusage(libc::RUSAGE_THREAD, usage.as_mut_ptr()) } != 0 {
+      return None;
+    }
+
+    // Safety: `getrusage` succeeded, so `usage` is initialized.
+    let usage = unsafe { usage.assume_init() };
+    Some(PageFaultStats {
+      major: usage.ru_majflt as u64,
+      minor: usage.ru_minflt as u64,
+    })
   }
+
+  #[cfg(not(target_os = "linux"))]
+  None
 }
 
 impl<L: Log> LogExt for L {}
 
 /// The abstraction for the common mutable methods of log.
 pub trait MutableLog: Log + Mutable {
+  /// Sets the identifier of the log.
+  ///
+  /// The id is not persisted in the log, so this only changes the in-memory identifier, and the
+  /// log must be opened with the new id afterwards. All the [`ValuePointer`]s returned before carry the old id,
+  /// and must be rewritten by [`ValuePointer::new`] to match the new id, otherwise reading by them
+  /// returns [`Error::IdMismatch`](crate::error::Error::IdMismatch).
+  ///
+  /// ## Example
+  ///
+  /// ```rust
+  /// use valog::{sync::ValueLog, Builder, Log, LogReader, LogWriter, MutableLog, ValuePointer};
+  ///
+  /// let mut log = Builder::new()
+  ///   .with_capacity(100)
+  ///   .alloc::<ValueLog>(1)
+  ///   .unwrap();
+  ///
+  /// let vp = log.insert(b"Hello, valog!").unwrap();
+  /// log.set_id(2);
+  /// assert_eq!(log.id(), &2);
+  ///
+  /// let vp = ValuePointer::new(2, vp.offset(), vp.size());
+  /// let data = unsafe { log.read(log.id(), vp.offset(), vp.size()).unwrap() };
+  /// assert_eq!(data, b"Hello, valog!");
+  /// ```
+  #[inline]
+  fn set_id(&mut self, id: <Self as Log>::Id)
+  where
+    Self: sealed::Constructor<Id = <Self as Log>::Id>,
+  {
+    *sealed::Constructor::id_mut(self) = id;
+  }
+
   /// Returns the mutable reference to the reserved slice.
   ///
+  /// If the log has no reserved space, an empty slice is returned, writing to it is a no-op.
+  ///
   /// ## Safety
   /// - The caller must ensure that the there is no others accessing reserved slice for either read or write.
   /// - This method is not thread-safe, so be careful when using it.
@@ -576,16 +1457,248 @@ pub trait MutableLog: Log + Mutable {
   unsafe fn reserved_slice_mut(&self) -> &mut [u8] {
     let reserved = self.options().reserved();
     if reserved == 0 {
+      // a zero-length slice does not point to any memory, so writing to it is a no-op.
       return &mut [];
     }
 
     let allocator = self.allocator();
     let reserved_slice = allocator.reserved_slice_mut();
-    &mut reserved_slice[HEADER_SIZE..]
+    &mut reserved_slice[self.options().header_size()..]
+  }
+
+  /// Returns the mutable reference to the reserved region at `index`, which is configured by [`Builder::with_reserved_regions`](crate::Builder::with_reserved_regions).
+  ///
+  /// ## Safety
+  /// - The caller must ensure that the there is no others accessing reserved region for either read or write.
+  /// - This method is not thread-safe, so be careful when using it.
+  ///
+  /// ## Panics
+  /// - If `index` is out of the bounds of the reserved regions.
+  ///
+  /// ## Example
+  ///
+  /// ```rust
+  /// use valog::{sync::ValueLog, Builder, Log, MutableLog};
+  ///
+  /// let log = Builder::new()
+  ///   .with_capacity(100)
+  ///   .with_reserved_regions(&[4, 8])
+  ///   .alloc::<ValueLog>(1)
+  ///   .unwrap();
+  ///
+  /// unsafe {
+  ///   log.reserved_region_mut(0).copy_from_slice(b"head");
+  ///   log.reserved_region_mut(1).copy_from_slice(b"metadata");
+  ///
+  ///   assert_eq!(log.reserved_region(0), b"head");
+  ///   assert_eq!(log.reserved_region(1), b"metadata");
+  ///   assert_eq!(log.reserved_slice(), b"headmetadata");
+  /// }
+  /// ```
+  #[allow(clippy::mut_from_ref)]
+  #[inline]
+  unsafe fn reserved_region_mut(&self, index: usize) -> &mut [u8] {
+    let range = match self.options().reserved_region_range(index) {
+      Some(range) => range,
+      None => panic!("reserved region index {index} out of bounds"),
+    };
+    &mut self.reserved_slice_mut()[range]
+  }
+
+  /// Drops the values after the committed length of a reopened log, and returns the recovered length of the log.
+  ///
+  /// The entries are not framed with their lengths in the log, so a torn final entry cannot be found by scanning
+  /// backward from the end. Instead, a file backed log records the committed length in its header, which is
+  /// advanced after the values are flushed, by the synced inserts which flush the header (see
+  /// [`HeaderSync`](crate::options::HeaderSync)) and by [`MutableLog::flush`]. The values after the committed
+  /// length may be torn by a crash, so they are reclaimed, and the new values are inserted from the committed length.
+  ///
+  /// The logs which do not record the committed length, e.g. the in-memory logs, the logs over a sub-range of an
+  /// external allocator and the logs in the version `0` layout, are left untouched, and their allocated length
+  /// is returned.
+  ///
+  /// ## Errors
+  /// - [`Error::InvalidLayout`] if the allocated or the committed length is out of the bounds of the log, which
+  ///   means the header is corrupted.
+  /// - [`Error::InvalidOptions`] if the values need to be reclaimed, but the log is shared with its clones.
+  ///
+  /// ## Example
+  ///
+  /// ```rust
+  /// use valog::{sync::ValueLog, Builder, Log, LogWriter, MutableLog};
+  ///
+  /// let dir = tempfile::tempdir().unwrap();
+  /// let path = dir.path().join("repair_example.vlog");
+  ///
+  /// let open = || unsafe {
+  ///   Builder::new()
+  ///     .with_capacity(1024)
+  ///     .with_sync(false)
+  ///     .with_create(true)
+  ///     .with_read(true)
+  ///     .with_write(true)
+  ///     .map_mut::<ValueLog, _>(&path, 1u32)
+  ///     .unwrap()
+  /// };
+  ///
+  /// let log = open();
+  /// let foo = log.insert(b"foo").unwrap();
+  /// log.flush().unwrap();
+  /// // the value inserted after the last flush is not committed.
+  /// log.insert(b"bar").unwrap();
+  /// drop(log);
+  ///
+  /// let mut log = open();
+  /// let committed = log.repair().unwrap();
+  /// assert_eq!(committed, foo.offset() + foo.size() + 8);
+  ///
+  /// // the new values are inserted from the committed length.
+  /// let baz = log.insert(b"baz").unwrap();
+  /// assert_eq!(baz.offset(), committed);
+  /// ```
+  fn repair(&mut self) -> Result<u32, Error> {
+    let allocator = self.allocator();
+    let allocated = allocator.allocated();
+    let data_offset = allocator.data_offset();
+    if allocated < data_offset || allocated > allocator.capacity() {
+      return Err(Error::InvalidLayout);
+    }
+
+    #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+    if writer::committed_header_lock(self).is_some() {
+      let committed = crate::options::committed(allocator.reserved_slice()) as usize;
+      if committed < data_offset || committed > allocated {
+        return Err(Error::InvalidLayout);
+      }
+
+      if committed < allocated {
+        if allocator.refs() > 1 {
+          return Err(Error::invalid_options(
+            "the log to repair is shared with its clones",
+          ));
+        }
+
+        // Safety: the log is not shared, and the values after the committed length are not referenced
+        // by the value pointers returned before reopening.
+        unsafe {
+          allocator.rewind(rarena_allocator::ArenaPosition::Start(committed as u32));
+        }
+        allocator.flush_header()?;
+      }
+      return Ok(committed as u32);
+    }
+
+    Ok(allocated as u32)
+  }
+
+  /// Appends a footer which lists the `(offset, size)` of the given value pointers after the last value,
+  /// and records the offset of the footer in the header of the log, so that a sealed log is self-describing,
+  /// the pointers can be read back by [`Log::footer`] after reopening.
+  ///
+  /// The footer is a `u32` count followed by `offset: u32`, `size: u32` and `flags: u8` (`0b01` for a
+  /// tombstone, `0b10` for a truncated value) of each pointer in little-endian, with a checksum. It is not a
+  /// value of the log, so it is not limited by [`Options::maximum_value_size`](crate::options::Options::maximum_value_size)
+  /// or [`Options::max_entries`](crate::options::Options::max_entries), and it is not counted by
+  /// [`LogWriter::entries`](crate::LogWriter::entries). The log is flushed if it is backed by a file.
+  ///
+  /// Returns the offset of the footer.
+  ///
+  /// ## Errors
+  /// - [`Error::InvalidOptions`] if the log does not own the header, e.g. it is built over a sub-range of an
+  ///   external allocator by [`Builder::with_external_arena`](crate::Builder::with_external_arena), or the log is
+  ///   written in the version `0` layout, whose header cannot record the footer.
+  ///
+  /// ## Example
+  ///
+  /// ```rust
+  /// use valog::{sync::ValueLog, Builder, Log, LogWriter, MutableLog};
+  ///
+  /// let log = Builder::new()
+  ///   .with_capacity(1024)
+  ///   .alloc::<ValueLog>(1)
+  ///   .unwrap();
+  ///
+  /// let ptrs = [log.insert(b"foo").unwrap(), log.insert(b"bar").unwrap()];
+  /// let offset = log.write_footer(&ptrs).unwrap();
+  /// assert!(offset > ptrs[1].offset() as u64);
+  /// assert_eq!(log.footer().unwrap().unwrap(), ptrs);
+  /// ```
+  fn write_footer(&self, ptrs: &[ValuePointer<<Self as Log>::Id>]) -> Result<u64, Error>
+  where
+    Self: LogWriter + Sized,
+    <Self as Log>::Id: CheapClone + core::fmt::Debug,
+  {
+    if self.options().sub_range().is_some()
+      || self.options().is_legacy()
+      || self.allocator().reserved_slice().len() < HEADER_SIZE
+    {
+      return Err(Error::invalid_options(
+        "the footer requires the header of the value log",
+      ));
+    }
+
+    // the footer is not a value of the log, so it is neither limited by `max_value_size` nor by `max_entries`.
+    let cks_len = self.options().checksum_len().len();
+    let len = 4 + ptrs.len() * FOOTER_ENTRY_SIZE;
+    if len + cks_len > u32::MAX as usize {
+      return Err(Error::value_too_large(len + cks_len, u32::MAX as usize));
+    }
+
+    let mut buf = writer::alloc_entry(self, len + cks_len)?;
+    let offset = buf.offset();
+    // SAFETY: `buf` is allocated with `len + cks_len` bytes.
+    let footer = unsafe { core::slice::from_raw_parts_mut(buf.as_mut_ptr(), len + cks_len) };
+    footer[..4].copy_from_slice(&(ptrs.len() as u32).to_le_bytes());
+    for (vp, entry) in ptrs
+      .iter()
+      .zip(footer[4..len].chunks_exact_mut(FOOTER_ENTRY_SIZE))
+    {
+      entry[..4].copy_from_slice(&vp.offset().to_le_bytes());
+      entry[4..8].copy_from_slice(&vp.size().to_le_bytes());
+      let mut flags = 0;
+      if vp.is_tombstone() {
+        flags |= FOOTER_TOMBSTONE;
+      }
+      if vp.is_truncated() {
+        flags |= FOOTER_TRUNCATED;
+      }
+      entry[8] = flags;
+    }
+    let checksum = self.checksum(&footer[..len]).to_le_bytes();
+    footer[len..].copy_from_slice(&checksum[..cks_len]);
+
+    // Safety: the footer is written into the space.
+    unsafe {
+      buf.detach();
+    }
+
+    {
+      #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+      let _guard = self.header_lock().map(|lock| {
+        lock
+          .lock()
+          .unwrap_or_else(std::sync::PoisonError::into_inner)
+      });
+      // Safety: the header is only written under the header lock.
+      unsafe {
+        crate::options::write_footer_offset(self.allocator().reserved_slice_mut(), offset as u32);
+      }
+    }
+
+    #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+    if self.allocator().is_ondisk() {
+      self.flush()?;
+    }
+
+    Ok(offset as u64)
   }
 
   /// Flushes the memory-mapped file to disk.
   ///
+  /// After the values are flushed, the committed length recorded in the header is advanced to the allocated
+  /// length and flushed as well, so that all the values are visible to
+  /// [`Builder::open_pread`](crate::Builder::open_pread) and kept by [`MutableLog::repair`].
+  ///
   /// ## Example
   ///
   /// ```rust
@@ -608,7 +1721,21 @@ pub trait MutableLog: Log + Mutable {
   #[cfg_attr(docsrs, doc(cfg(all(feature = "memmap", not(target_family = "wasm")))))]
   #[inline]
   fn flush(&self) -> std::io::Result<()> {
-    self.allocator().flush()
+    #[cfg(feature = "tracing")]
+    let _span = tracing::trace_span!(
+      "valog.flush",
+      mode = "sync",
+      offset = 0,
+      len = self.allocator().allocated()
+    )
+    .entered();
+
+    self.allocator().flush()?;
+    // the committed length is recorded after the values and the header of the allocator are flushed.
+    if writer::commit(self, self.allocator().allocated()) {
+      self.allocator().flush_range(0, HEADER_SIZE)?;
+    }
+    Ok(())
   }
 
   /// Flushes the memory-mapped file to disk asynchronously.
@@ -636,6 +1763,15 @@ pub trait MutableLog: Log + Mutable {
   #[cfg_attr(docsrs, doc(cfg(all(feature = "memmap", not(target_family = "wasm")))))]
   #[inline]
   fn flush_async(&self) -> std::io::Result<()> {
+    #[cfg(feature = "tracing")]
+    let _span = tracing::trace_span!(
+      "valog.flush",
+      mode = "async",
+      offset = 0,
+      len = self.allocator().allocated()
+    )
+    .entered();
+
     self.allocator().flush_async()
   }
 
@@ -665,6 +1801,9 @@ pub trait MutableLog: Log + Mutable {
   #[cfg_attr(docsrs, doc(cfg(all(feature = "memmap", not(target_family = "wasm")))))]
   #[inline]
   fn flush_range(&self, offset: usize, len: usize) -> std::io::Result<()> {
+    #[cfg(feature = "tracing")]
+    let _span = tracing::trace_span!("valog.flush", mode = "sync", offset, len).entered();
+
     self.allocator().flush_header_and_range(offset, len)
   }
 
@@ -693,17 +1832,67 @@ pub trait MutableLog: Log + Mutable {
   #[cfg_attr(docsrs, doc(cfg(all(feature = "memmap", not(target_family = "wasm")))))]
   #[inline]
   fn flush_async_range(&self, offset: usize, len: usize) -> std::io::Result<()> {
+    #[cfg(feature = "tracing")]
+    let _span = tracing::trace_span!("valog.flush", mode = "async", offset, len).entered();
+
     self.allocator().flush_async_header_and_range(offset, len)
   }
+
+  /// Flushes outstanding memory map modifications in the range to disk, asynchronously if `async_` is `true`.
+  ///
+  /// Dispatches to [`flush_async_range`](MutableLog::flush_async_range) or [`flush_range`](MutableLog::flush_range),
+  /// so that the durability can be configured as data.
+  ///
+  /// ## Example
+  ///
+  /// ```rust
+  /// use valog::{sync::ValueLog, Builder, MutableLog};
+  /// # let path = tempfile::NamedTempFile::new().unwrap().into_temp_path();
+  /// # std::fs::remove_file(&path);
+  /// /// Create a new file without automatic syncing.
+  /// let log = unsafe {
+  ///   Builder::new()
+  ///     .with_sync(false)
+  ///     .with_create(true)
+  ///     .with_read(true)
+  ///     .with_write(true)
+  ///     .with_capacity(100)
+  ///     .map_mut::<ValueLog, _>(&path, 0).unwrap()
+  /// };
+  ///
+  /// log.sync_range(0, 50, true).unwrap();
+  /// log.sync_range(0, 50, false).unwrap();
+  /// ```
+  #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+  #[cfg_attr(docsrs, doc(cfg(all(feature = "memmap", not(target_family = "wasm")))))]
+  #[inline]
+  fn sync_range(&self, offset: usize, len: usize, async_: bool) -> std::io::Result<()> {
+    if async_ {
+      self.flush_async_range(offset, len)
+    } else {
+      self.flush_range(offset, len)
+    }
+  }
 }
 
 impl<L: Log + Mutable> MutableLog for L {}
 
+/// The size of each pointer in the footer, `offset: u32`, `size: u32` and `flags: u8`.
+const FOOTER_ENTRY_SIZE: usize = 9;
+
+/// The flag of a tombstone pointer in the footer.
+const FOOTER_TOMBSTONE: u8 = 0b01;
+
+/// The flag of a truncated pointer in the footer.
+const FOOTER_TRUNCATED: u8 = 0b10;
+
 pub trait AsLog {
   type Log;
   type Type;
 
   fn as_log(&self) -> &Self::Log;
+
+  fn as_log_mut(&mut self) -> &mut Self::Log;
 }
 
 impl<L> sealed::Sealed for L
@@ -717,6 +1906,45 @@ where
   fn allocator(&self) -> &Self::Allocator {
     self.as_log().allocator()
   }
+
+  #[inline]
+  fn allocator_and_options_mut(&mut self) -> (&mut Self::Allocator, &mut Options) {
+    self.as_log_mut().allocator_and_options_mut()
+  }
+
+  #[inline]
+  fn entry_counter(&self) -> Option<&core::sync::atomic::AtomicU32> {
+    self.as_log().entry_counter()
+  }
+
+  #[inline]
+  fn range_cursor(&self) -> Option<&core::sync::atomic::AtomicU32> {
+    self.as_log().range_cursor()
+  }
+
+  #[cfg(feature = "metrics")]
+  #[inline]
+  fn page_faults(&self) -> Option<&std::sync::OnceLock<super::PageFaultStats>> {
+    self.as_log().page_faults()
+  }
+
+  #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+  #[inline]
+  fn sync_file(&self) -> Option<&std::fs::File> {
+    self.as_log().sync_file()
+  }
+
+  #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+  #[inline]
+  fn shared_file_path(&self) -> Option<&std::path::Path> {
+    self.as_log().shared_file_path()
+  }
+
+  #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+  #[inline]
+  fn header_lock(&self) -> Option<&std::sync::Mutex<()>> {
+    self.as_log().header_lock()
+  }
 }
 
 impl<L> sealed::Constructor for L
@@ -737,6 +1965,23 @@ where
   ) -> Self {
     <L::Log as sealed::Constructor>::construct(fid, allocator, checksumer, options).into()
   }
+
+  #[inline]
+  fn id_mut(&mut self) -> &mut Self::Id {
+    self.as_log_mut().id_mut()
+  }
+
+  #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+  #[inline]
+  fn set_sync_file(&mut self, file: std::fs::File) {
+    self.as_log_mut().set_sync_file(file)
+  }
+
+  #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+  #[inline]
+  fn set_shared_file_path(&mut self, path: std::path::PathBuf) {
+    self.as_log_mut().set_shared_file_path(path)
+  }
 }
 
 impl<L> Log for L
@@ -760,4 +2005,9 @@ where
   fn options(&self) -> &Options {
     self.as_log().options()
   }
+
+  #[inline]
+  fn is_readonly(&self) -> bool {
+    self.as_log().is_readonly()
+  }
 }
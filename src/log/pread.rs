@@ -0,0 +1,239 @@
+use std::{fs::File, sync::Mutex, vec::Vec};
+
+use super::{reader::validate_entry, *};
+
+/// The value log which serves reads by positioned reads (`pread`) on the file, instead of memory map.
+///
+/// For huge cold files which only serve scattered reads, memory mapping the whole file wastes
+/// address space and causes TLB pressure. This log keeps the [`File`] and reads each value into
+/// an owned buffer, which trades zero-copy for bounded memory. The checksums are validated in
+/// the same way as the memory map backed logs.
+///
+/// Opened by [`Builder::open_pread`](crate::Builder::open_pread).
+#[derive(Debug)]
+pub struct PreadValueLog<I, C = Crc32> {
+  fid: I,
+  file: File,
+  len: u64,
+  data_offset: u32,
+  committed: u32,
+  checksumer: C,
+  options: Options,
+  window: Mutex<Window>,
+}
+
+impl<I, C> PreadValueLog<I, C> {
+  #[inline]
+  pub(crate) fn construct(
+    fid: I,
+    file: File,
+    checksumer: C,
+    options: Options,
+    data_offset: u32,
+    committed: u32,
+  ) -> std::io::Result<Self> {
+    let len = file.metadata()?.len();
+    Ok(Self {
+      fid,
+      file,
+      len,
+      data_offset,
+      committed,
+      checksumer,
+      options,
+      window: Mutex::new(Window::default()),
+    })
+  }
+
+  /// Returns the identifier of the log.
+  #[inline]
+  pub const fn id(&self) -> &I {
+    &self.fid
+  }
+
+  /// Returns the options of the log.
+  #[inline]
+  pub const fn options(&self) -> &Options {
+    &self.options
+  }
+
+  /// Returns the length of the underlying file when the log was opened.
+  #[inline]
+  pub const fn len(&self) -> u64 {
+    self.len
+  }
+
+  /// Returns `true` if the underlying file was empty when the log was opened.
+  #[inline]
+  pub const fn is_empty(&self) -> bool {
+    self.len == 0
+  }
+
+  /// Returns the minimum offset of the values, which is read from the header of the allocator when
+  /// the log was opened.
+  #[inline]
+  pub const fn data_offset(&self) -> usize {
+    self.data_offset as usize
+  }
+
+  /// Returns the committed length of the log, which is read from the header of the log when the log was opened.
+  ///
+  /// The committed length is advanced by the synced inserts which flush the header and by
+  /// [`MutableLog::flush`](crate::MutableLog::flush), the values after it, including the values inserted
+  /// after the log was opened, are out of bounds.
+  #[inline]
+  pub const fn committed(&self) -> usize {
+    self.committed as usize
+  }
+}
+
+impl<I, C> PreadValueLog<I, C>
+where
+  C: BuildChecksumer,
+{
+  /// Calculates the checksum of the given bytes.
+  #[inline]
+  pub fn checksum(&self, bytes: &[u8]) -> u64 {
+    self.checksumer.checksum_one(bytes)
+  }
+
+  /// Reads a value from the file, the checksum is validated if
+  /// [`validate_checksum`](crate::options::Options::validate_checksum) is enabled.
+  ///
+  /// The value of an encrypted log is decrypted, see `Options::with_cipher`.
+  ///
+  /// ## Example
+  ///
+  /// ```rust
+  /// use valog::{sync, Builder, LogWriter, PreadValueLog};
+  ///
+  /// let dir = tempfile::tempdir().unwrap();
+  /// let path = dir.path().join("pread_example.vlog");
+  ///
+  /// let vp = unsafe {
+  ///   let log = Builder::new()
+  ///     .with_capacity(1024)
+  ///     .with_create_new(true)
+  ///     .with_read(true)
+  ///     .with_write(true)
+  ///     .map_mut::<sync::ValueLog, _>(&path, 1u32)
+  ///     .unwrap();
+  ///   log.insert(b"Hello, valog!").unwrap()
+  /// };
+  ///
+  /// let log: PreadValueLog<u32> = Builder::new().open_pread(&path, 1).unwrap();
+  /// let data = log.read(log.id(), vp.offset(), vp.size()).unwrap();
+  /// assert_eq!(data, b"Hello, valog!");
+  /// ```
+  pub fn read(&self, id: &I, offset: u32, len: u32) -> Result<Vec<u8>, Error>
+  where
+    I: Eq,
+  {
+    #[cfg(feature = "tracing")]
+    let _span = tracing::trace_span!(
+      "valog.read",
+      offset,
+      size = len,
+      validate = self.options.validate_checksum
+    )
+    .entered();
+
+    if self.fid.ne(id) {
+      return Err(Error::IdMismatch);
+    }
+
+    if len == 0 {
+      return Ok(Vec::new());
+    }
+
+    let cks_len = self.options.checksum_len();
+    let total = len as usize + cks_len.len();
+    if offset < self.data_offset || offset as u64 + total as u64 > self.committed as u64 {
+      return Err(Error::out_of_bounds(
+        offset,
+        total as u32,
+        self.data_offset,
+        self.committed,
+      ));
+    }
+
+    let mut buf = std::vec![0; total];
+    self.read_at(&mut buf, self.options.offset + offset as u64)?;
+    validate_entry(
+      &buf,
+      len as usize,
+      cks_len,
+      self.options.validate_checksum,
+      |bytes| self.checksum(bytes),
+    )?;
+
+    buf.truncate(len as usize);
+    #[cfg(feature = "encryption")]
+    if let Some(cipher) = self.options.cipher() {
+      let mut value = Vec::with_capacity(buf.len());
+      return crate::cipher::open(&cipher, &buf, &mut value).map(|_| value);
+    }
+
+    Ok(buf)
+  }
+
+  /// Reads the exact number of bytes to fill `buf` from the `offset` of the file, through the
+  /// read ahead window if [`read_ahead`](Options::read_ahead) is enabled.
+  ///
+  /// The caller must ensure that `offset..offset + buf.len()` is in the bounds of the file.
+  fn read_at(&self, buf: &mut [u8], offset: u64) -> std::io::Result<()> {
+    let read_ahead = self.options.read_ahead() as u64;
+    if read_ahead == 0 {
+      return read_exact_at(&self.file, buf, offset);
+    }
+
+    let mut window = self.window.lock().unwrap_or_else(|e| e.into_inner());
+    let end = offset + buf.len() as u64;
+    if offset < window.offset || end > window.offset + window.buf.len() as u64 {
+      let len = (buf.len() as u64 + read_ahead).min(self.len - offset);
+      window.buf.resize(len as usize, 0);
+      if let Err(e) = read_exact_at(&self.file, &mut window.buf, offset) {
+        window.buf.clear();
+        return Err(e);
+      }
+      window.offset = offset;
+    }
+
+    let start = (offset - window.offset) as usize;
+    buf.copy_from_slice(&window.buf[start..start + buf.len()]);
+    Ok(())
+  }
+}
+
+/// The bytes of the file buffered by the last read which missed, see [`Options::read_ahead`].
+#[derive(Debug, Default)]
+struct Window {
+  offset: u64,
+  buf: Vec<u8>,
+}
+
+/// Reads the exact number of bytes to fill `buf` from the `offset` of the file, without moving the cursor.
+#[cfg(unix)]
+#[inline]
+fn read_exact_at(file: &File, buf: &mut [u8], offset: u64) -> std::io::Result<()> {
+  std::os::unix::fs::FileExt::read_exact_at(file, buf, offset)
+}
+
+/// Reads the exact number of bytes to fill `buf` from the `offset` of the file.
+#[cfg(windows)]
+fn read_exact_at(file: &File, mut buf: &mut [u8], mut offset: u64) -> std::io::Result<()> {
+  use std::os::windows::fs::FileExt;
+
+  while !buf.is_empty() {
+    match file.seek_read(buf, offset) {
+      Ok(0) => return Err(std::io::ErrorKind::UnexpectedEof.into()),
+      Ok(n) => {
+        buf = &mut buf[n..];
+        offset += n as u64;
+      }
+      Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+      Err(e) => return Err(e),
+    }
+  }
+  Ok(())
+}
@@ -0,0 +1,168 @@
+use core::ops::Range;
+
+use memmap2::Mmap;
+
+use super::{reader::validate_entry, *};
+
+/// The read-only value log which only maps a sub-range of the file into memory.
+///
+/// When all the value pointers to read are known to be in a small region of a huge file, mapping
+/// the whole file wastes address space. This log maps only the bytes of the region, and serves zero-copy
+/// reads for the value pointers in the region, reading a value out of the region or after the
+/// [`committed`](RangeValueLog::committed) length returns [`Error::OutOfBounds`].
+/// The offsets of the value pointers are the same as the ones of the whole log.
+///
+/// Opened by [`Builder::open_range`](crate::Builder::open_range).
+#[derive(Debug)]
+pub struct RangeValueLog<I, C = Crc32> {
+  fid: I,
+  map: Mmap,
+  start: u32,
+  data_offset: u32,
+  committed: u32,
+  checksumer: C,
+  options: Options,
+}
+
+impl<I, C> RangeValueLog<I, C> {
+  #[inline]
+  pub(crate) fn construct(
+    fid: I,
+    map: Mmap,
+    start: u32,
+    data_offset: u32,
+    committed: u32,
+    checksumer: C,
+    options: Options,
+  ) -> Self {
+    Self {
+      fid,
+      map,
+      start,
+      data_offset,
+      committed,
+      checksumer,
+      options,
+    }
+  }
+
+  /// Returns the identifier of the log.
+  #[inline]
+  pub const fn id(&self) -> &I {
+    &self.fid
+  }
+
+  /// Returns the options of the log.
+  #[inline]
+  pub const fn options(&self) -> &Options {
+    &self.options
+  }
+
+  /// Returns the range of the log which is mapped, the end is clamped to the length of the file.
+  #[inline]
+  pub fn range(&self) -> Range<u32> {
+    self.start..self.start + self.map.len() as u32
+  }
+
+  /// Returns the minimum offset of the values, which is read from the header of the log when
+  /// the log was opened.
+  #[inline]
+  pub const fn data_offset(&self) -> usize {
+    self.data_offset as usize
+  }
+
+  /// Returns the committed length of the log, which is read from the header of the log when the log was opened.
+  ///
+  /// The bytes of the mapped range after the committed length are out of bounds, see
+  /// [`PreadValueLog::committed`](crate::PreadValueLog::committed).
+  #[inline]
+  pub const fn committed(&self) -> usize {
+    self.committed as usize
+  }
+}
+
+impl<I, C> RangeValueLog<I, C>
+where
+  C: BuildChecksumer,
+{
+  /// Calculates the checksum of the given bytes.
+  #[inline]
+  pub fn checksum(&self, bytes: &[u8]) -> u64 {
+    self.checksumer.checksum_one(bytes)
+  }
+
+  /// Reads a value from the mapped range, the checksum is validated if
+  /// [`validate_checksum`](crate::options::Options::validate_checksum) is enabled.
+  ///
+  /// ## Example
+  ///
+  /// ```rust
+  /// use valog::{error::Error, sync, Builder, LogWriter, RangeValueLog};
+  ///
+  /// let dir = tempfile::tempdir().unwrap();
+  /// let path = dir.path().join("range_example.vlog");
+  ///
+  /// let (foo, bar) = unsafe {
+  ///   let log = Builder::new()
+  ///     .with_capacity(1024)
+  ///     .with_create_new(true)
+  ///     .with_read(true)
+  ///     .with_write(true)
+  ///     .map_mut::<sync::ValueLog, _>(&path, 1u32)
+  ///     .unwrap();
+  ///   (log.insert(b"foo").unwrap(), log.insert(b"bar").unwrap())
+  /// };
+  ///
+  /// let log: RangeValueLog<u32> = unsafe {
+  ///   Builder::new().open_range(&path, bar.offset()..bar.offset() + 64, 1).unwrap()
+  /// };
+  /// let data = log.read(log.id(), bar.offset(), bar.size()).unwrap();
+  /// assert_eq!(data, b"bar");
+  ///
+  /// let err = log.read(log.id(), foo.offset(), foo.size()).unwrap_err();
+  /// assert!(matches!(err, Error::OutOfBounds { .. }));
+  /// ```
+  pub fn read(&self, id: &I, offset: u32, len: u32) -> Result<&[u8], Error>
+  where
+    I: Eq,
+  {
+    #[cfg(feature = "tracing")]
+    let _span = tracing::trace_span!(
+      "valog.read",
+      offset,
+      size = len,
+      validate = self.options.validate_checksum
+    )
+    .entered();
+
+    if self.fid.ne(id) {
+      return Err(Error::IdMismatch);
+    }
+
+    if len == 0 {
+      return Ok(&[]);
+    }
+
+    let cks_len = self.options.checksum_len();
+    let start = (self.data_offset() as u64).max(self.start as u64);
+    let end = (self.start as u64 + self.map.len() as u64).min(self.committed as u64);
+    let total = len as u64 + cks_len.len() as u64;
+    if (offset as u64) < start || offset as u64 + total > end {
+      return Err(Error::out_of_bounds(
+        offset,
+        total as u32,
+        start as u32,
+        end as u32,
+      ));
+    }
+
+    let pos = (offset - self.start) as usize;
+    validate_entry(
+      &self.map[pos..pos + total as usize],
+      len as usize,
+      cks_len,
+      self.options.validate_checksum,
+      |bytes| self.checksum(bytes),
+    )
+  }
+}
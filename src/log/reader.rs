@@ -1,5 +1,43 @@
+#![allow(clippy::type_complexity)]
+
 use super::*;
 
+macro_rules! read_scalar {
+  ($($name:ident: $ty:ident), +$(,)?) => {
+    $(
+      #[doc = concat!("Reads a little-endian `", stringify!($ty), "` value from the log, the size of the value must be `size_of::<", stringify!($ty), ">()`.")]
+      ///
+      /// The bounds and the checksum are validated in the same way as [`LogReader::read`].
+      ///
+      /// ## Safety
+      #[doc = concat!("- The buffer `offset..offset + size_of::<", stringify!($ty), ">()` must hold a valid bytes sequence.")]
+      ///
+      /// ## Example
+      ///
+      /// ```rust
+      /// use valog::{Builder, sync::ValueLog, LogWriter, LogReader, Log};
+      ///
+      /// let log = Builder::new().with_capacity(1024).alloc::<ValueLog>(0).unwrap();
+      ///
+      #[doc = concat!("let vp = log.insert(&42", stringify!($ty), ".to_le_bytes()).unwrap();")]
+      #[doc = concat!("let val = unsafe { log.", stringify!($name), "(log.id(), vp.offset()).unwrap() };")]
+      /// assert_eq!(val, 42);
+      /// ```
+      #[inline]
+      unsafe fn $name(&self, id: &Self::Id, offset: u32) -> Result<$ty, Error>
+      where
+        Self::Id: Eq,
+      {
+        const SIZE: usize = core::mem::size_of::<$ty>();
+
+        self
+          .read(id, offset, SIZE as u32)
+          .map(|buf| $ty::from_le_bytes(buf.try_into().unwrap()))
+      }
+    )*
+  };
+}
+
 /// The value log reader abstraction.
 pub trait LogReader: Log {
   /// Reads a value from the log.
@@ -22,55 +60,475 @@ pub trait LogReader: Log {
   where
     Self::Id: Eq,
   {
-    if self.id().ne(id) {
+    read_in(self, id, offset, len, self.options().validate_checksum)
+  }
+
+  /// Returns the size of the value pointed by `ptr` after confirming the pointer is valid for this log,
+  /// which means the id is matched and the value with its checksum is in the bounds of the log.
+  ///
+  /// The value is neither read nor validated by the checksum, so it is a cheap check to reject a stale
+  /// pointer before a full [`read`](LogReader::read). Empty values are always valid.
+  ///
+  /// ## Example
+  ///
+  /// ```rust
+  /// use valog::{error::Error, Builder, sync::ValueLog, LogWriter, LogReader, ValuePointer};
+  ///
+  /// let log = Builder::new().with_capacity(1024).alloc::<ValueLog>(0).unwrap();
+  ///
+  /// let vp = log.insert(b"Hello, valog!").unwrap();
+  /// assert_eq!(log.peek(&vp).unwrap(), 13);
+  ///
+  /// let stale = ValuePointer::new(0, vp.offset() + 512, 13);
+  /// assert!(matches!(log.peek(&stale), Err(Error::OutOfBounds { .. })));
+  ///
+  /// let other = ValuePointer::new(1, vp.offset(), vp.size());
+  /// assert!(matches!(log.peek(&other), Err(Error::IdMismatch)));
+  /// ```
+  fn peek(&self, ptr: &ValuePointer<Self::Id>) -> Result<u32, Error>
+  where
+    Self::Id: Eq,
+  {
+    if self.id().ne(ptr.id()) {
       return Err(Error::IdMismatch);
     }
 
-    if len == 0 {
-      return Ok(&[]);
+    if ptr.size() == 0 {
+      return Ok(0);
     }
 
-    let offset = offset as usize;
-    let len = len as usize;
+    check_bounds(
+      ptr.offset(),
+      ptr.size(),
+      self.options().checksum_len().len(),
+      readable_bounds(self.allocator(), self.options(), self.range_cursor()),
+    )
+    .map(|_| ptr.size())
+  }
 
-    let allocator = self.allocator();
-    let allocated = allocator.allocated();
-    let data_offset = allocator.data_offset();
-    let opts = self.options();
+  /// Reads a value from the log, and returns the value with the length of the whole entry in the log,
+  /// which includes the value and the checksum.
+  ///
+  /// The entries are not framed with their lengths in the log, so the length of the value must be
+  /// given by the value pointer. `offset + entry_len` is the end of the entry, which is the start of the next
+  /// entry if [`value_alignment`](crate::options::Options::value_alignment) is `1`, otherwise padding bytes may
+  /// be placed between the entries. Empty values are not stored, so their entry length is `0`.
+  ///
+  /// ## Safety
+  /// - The buffer `offset..offset + len` must hold a valid bytes sequence.
+  ///
+  /// ## Example
+  ///
+  /// ```rust
+  /// use valog::{Builder, sync::ValueLog, LogWriter, LogReader, Log};
+  ///
+  /// let log = Builder::new().with_capacity(1024).alloc::<ValueLog>(0).unwrap();
+  ///
+  /// let vp = log.insert(b"foo").unwrap();
+  /// let next = log.insert(b"bar").unwrap();
+  ///
+  /// let (data, entry_len) = unsafe { log.read_entry(log.id(), vp.offset(), vp.size()).unwrap() };
+  /// assert_eq!(data, b"foo");
+  /// assert_eq!(vp.offset() + entry_len, next.offset());
+  /// ```
+  unsafe fn read_entry(&self, id: &Self::Id, offset: u32, len: u32) -> Result<(&[u8], u32), Error>
+  where
+    Self::Id: Eq,
+  {
+    let cks_len = self.options().checksum_len().len() as u32;
+    self
+      .read(id, offset, len)
+      .map(|buf| (buf, if len == 0 { 0 } else { len + cks_len }))
+  }
+
+  /// Reads a value inserted by [`LogWriter::insert_with_flags`](crate::LogWriter::insert_with_flags),
+  /// and returns the value with its flags byte.
+  ///
+  /// `len` is the size of the value pointer, which includes the flags byte. Empty value pointers
+  /// do not carry flags, so `(&[], 0)` is returned for them.
+  ///
+  /// ## Safety
+  /// - The buffer `offset..offset + len` must hold a valid bytes sequence which is inserted with flags.
+  ///
+  /// ## Example
+  ///
+  /// ```rust
+  /// use valog::{Builder, sync::ValueLog, LogWriter, LogReader, Log};
+  ///
+  /// let log = Builder::new().with_capacity(1024).alloc::<ValueLog>(0).unwrap();
+  ///
+  /// let vp = log.insert_with_flags(b"Hello, valog!", 1).unwrap();
+  /// let (data, flags) = unsafe { log.read_with_flags(log.id(), vp.offset(), vp.size()).unwrap() };
+  /// assert_eq!(data, b"Hello, valog!");
+  /// assert_eq!(flags, 1);
+  /// ```
+  unsafe fn read_with_flags(
+    &self,
+    id: &Self::Id,
+    offset: u32,
+    len: u32,
+  ) -> Result<(&[u8], u8), Error>
+  where
+    Self::Id: Eq,
+  {
+    self
+      .read(id, offset, len)
+      .map(|buf| match buf.split_last() {
+        Some((flags, value)) => (value, *flags),
+        None => (buf, 0),
+      })
+  }
 
-    if offset < data_offset {
-      return Err(Error::out_of_bounds(
-        offset as u32,
-        (len + CHECKSUM_LEN) as u32,
-        data_offset as u32,
-        allocated as u32,
-      ));
+  read_scalar!(
+    read_u16: u16,
+    read_u32: u32,
+    read_u64: u64,
+    read_i16: i16,
+    read_i32: i32,
+    read_i64: i64,
+  );
+
+  /// Reads a value from the log, and copies it into a new [`Vec`](std::vec::Vec).
+  ///
+  /// The value of an encrypted log is decrypted, see `Options::with_cipher`.
+  ///
+  /// ## Safety
+  /// - The buffer `offset..offset + len` must hold a valid bytes sequence.
+  ///
+  /// ## Example
+  ///
+  /// ```rust
+  /// use valog::{Builder, sync::ValueLog, LogWriter, LogReader, Log};
+  ///
+  /// let log = Builder::new().with_capacity(1024).alloc::<ValueLog>(0).unwrap();
+  ///
+  /// let vp = log.insert(b"Hello, valog!").unwrap();
+  /// let data = unsafe { log.read_owned(log.id(), vp.offset(), vp.size()).unwrap() };
+  /// assert_eq!(data, b"Hello, valog!");
+  /// ```
+  #[cfg(any(feature = "alloc", feature = "std"))]
+  #[cfg_attr(docsrs, doc(cfg(any(feature = "alloc", feature = "std"))))]
+  unsafe fn read_owned(
+    &self,
+    id: &Self::Id,
+    offset: u32,
+    len: u32,
+  ) -> Result<std::vec::Vec<u8>, Error>
+  where
+    Self::Id: Eq,
+  {
+    let value = self.read(id, offset, len)?;
+    #[cfg(feature = "encryption")]
+    if let Some(cipher) = self.options().cipher() {
+      let mut buf = std::vec::Vec::with_capacity(value.len());
+      return crate::cipher::open(&cipher, value, &mut buf).map(|_| buf);
     }
 
-    if (offset + len + CHECKSUM_LEN) > allocated {
-      return Err(Error::out_of_bounds(
-        offset as u32,
-        (len + CHECKSUM_LEN) as u32,
-        data_offset as u32,
-        allocated as u32,
-      ));
+    Ok(value.to_vec())
+  }
+
+  /// Reads a value from the log, and copies it into a new reference-counted [`Arc<[u8]>`](std::sync::Arc),
+  /// which can be cheaply shared by the concurrent consumers, e.g. in a cache.
+  ///
+  /// The value of an encrypted log is decrypted, see `Options::with_cipher`.
+  ///
+  /// ## Safety
+  /// - The buffer `offset..offset + len` must hold a valid bytes sequence.
+  ///
+  /// ## Example
+  ///
+  /// ```rust
+  /// use valog::{Builder, sync::ValueLog, LogWriter, LogReader, Log};
+  ///
+  /// let log = Builder::new().with_capacity(1024).alloc::<ValueLog>(0).unwrap();
+  ///
+  /// let vp = log.insert(b"Hello, valog!").unwrap();
+  /// let data = unsafe { log.read_shared(log.id(), vp.offset(), vp.size()).unwrap() };
+  /// let shared = data.clone();
+  /// assert_eq!(&*shared, b"Hello, valog!");
+  /// ```
+  #[cfg(any(feature = "alloc", feature = "std"))]
+  #[cfg_attr(docsrs, doc(cfg(any(feature = "alloc", feature = "std"))))]
+  unsafe fn read_shared(
+    &self,
+    id: &Self::Id,
+    offset: u32,
+    len: u32,
+  ) -> Result<std::sync::Arc<[u8]>, Error>
+  where
+    Self::Id: Eq,
+  {
+    #[cfg(feature = "encryption")]
+    if self.options().cipher().is_some() {
+      return self.read_owned(id, offset, len).map(std::sync::Arc::from);
     }
 
-    // Safety: we have checked the bounds
-    let buf = unsafe { allocator.get_bytes(offset, len + CHECKSUM_LEN) };
+    self.read(id, offset, len).map(std::sync::Arc::from)
+  }
 
-    if opts.validate_checksum {
-      let checksum = u64::from_le_bytes((&buf[len..len + CHECKSUM_LEN]).try_into().unwrap());
-      let digest = self.checksum(&buf[..len]);
-      if checksum != digest {
-        return Err(Error::checksum_mismatch());
-      }
+  /// Reads a value from the log, and appends it to the end of `buf`.
+  ///
+  /// `buf` is left unchanged if the read fails. The value of an encrypted log is decrypted,
+  /// see `Options::with_cipher`.
+  ///
+  /// ## Safety
+  /// - The buffer `offset..offset + len` must hold a valid bytes sequence.
+  ///
+  /// ## Example
+  ///
+  /// ```rust
+  /// use valog::{Builder, sync::ValueLog, LogWriter, LogReader, Log};
+  ///
+  /// let log = Builder::new().with_capacity(1024).alloc::<ValueLog>(0).unwrap();
+  ///
+  /// let vp = log.insert(b"valog!").unwrap();
+  /// let mut buf = b"Hello, ".to_vec();
+  /// unsafe { log.read_to_owned(log.id(), vp.offset(), vp.size(), &mut buf).unwrap() };
+  /// assert_eq!(buf, b"Hello, valog!");
+  /// ```
+  #[cfg(any(feature = "alloc", feature = "std"))]
+  #[cfg_attr(docsrs, doc(cfg(any(feature = "alloc", feature = "std"))))]
+  unsafe fn read_to_owned(
+    &self,
+    id: &Self::Id,
+    offset: u32,
+    len: u32,
+    buf: &mut std::vec::Vec<u8>,
+  ) -> Result<(), Error>
+  where
+    Self::Id: Eq,
+  {
+    let value = self.read(id, offset, len)?;
+    #[cfg(feature = "encryption")]
+    if let Some(cipher) = self.options().cipher() {
+      return crate::cipher::open(&cipher, value, buf);
     }
 
-    Ok(&buf[..len])
+    buf.extend_from_slice(value);
+    Ok(())
+  }
+
+  /// Verifies the checksums of the values pointed by `ptrs`, regardless of the
+  /// [`validate_checksum`](crate::options::Options::validate_checksum) configuration.
+  ///
+  /// This is useful when the checksum validation is disabled for the read path,
+  /// and the values are verified out-of-band periodically. Returns the error of the first bad value.
+  ///
+  /// ## Safety
+  /// - The `ptrs` must point to valid bytes sequences.
+  ///
+  /// ## Example
+  ///
+  /// ```rust
+  /// use valog::{Builder, sync::ValueLog, LogWriter, LogReader};
+  ///
+  /// let log = Builder::new()
+  ///   .with_capacity(1024)
+  ///   .with_validate_checksum(false)
+  ///   .alloc::<ValueLog>(0)
+  ///   .unwrap();
+  ///
+  /// let ptrs = [log.insert(b"foo").unwrap(), log.insert(b"bar").unwrap()];
+  /// unsafe { log.scrub(&ptrs).unwrap() };
+  /// ```
+  unsafe fn scrub(&self, ptrs: &[ValuePointer<Self::Id>]) -> Result<(), Error>
+  where
+    Self::Id: Eq,
+  {
+    let scan = || {
+      ptrs
+        .iter()
+        .try_for_each(|vp| read_in(self, vp.id(), vp.offset(), vp.size(), true).map(|_| ()))
+    };
+
+    #[cfg(feature = "metrics")]
+    common::record_page_faults(self, scan)?;
+    #[cfg(not(feature = "metrics"))]
+    scan()?;
+
+    #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+    common::evict_after_scan(self)?;
+
+    Ok(())
+  }
+
+  /// Returns the total bytes occupied by the values pointed by `ptrs` in the log,
+  /// which is the sum of the value size and the checksum length of each non-empty value.
+  ///
+  /// If `include_padding` is `true`, the `value_alignment - 1` bytes reserved for aligning
+  /// each value are also counted, which matches what the writer actually consumed.
+  ///
+  /// ## Example
+  ///
+  /// ```rust
+  /// use valog::{Builder, sync::ValueLog, LogWriter, LogReader};
+  ///
+  /// let log = Builder::new()
+  ///   .with_capacity(1024)
+  ///   .with_value_alignment(8)
+  ///   .alloc::<ValueLog>(0)
+  ///   .unwrap();
+  ///
+  /// let ptrs = [log.insert(b"foo").unwrap(), log.insert(b"").unwrap()];
+  /// assert_eq!(log.total_size(&ptrs, false), 3 + 8);
+  /// assert_eq!(log.total_size(&ptrs, true), 3 + 8 + 7);
+  /// ```
+  fn total_size(&self, ptrs: &[ValuePointer<Self::Id>], include_padding: bool) -> u64 {
+    let opts = self.options();
+    let overhead = opts.checksum_len().len() as u64
+      + if include_padding {
+        opts.value_alignment() as u64 - 1
+      } else {
+        0
+      };
+
+    ptrs
+      .iter()
+      .filter(|vp| vp.size() != 0)
+      .fold(0, |acc, vp| acc + vp.size() as u64 + overhead)
+  }
+}
+
+/// Reads a value from the log, validates the checksum if `validate` is `true`.
+///
+/// ## Safety
+/// - The buffer `offset..offset + len` must hold a valid bytes sequence.
+pub(super) unsafe fn read_in<'a, L: LogReader + ?Sized>(
+  l: &'a L,
+  id: &L::Id,
+  offset: u32,
+  len: u32,
+  validate: bool,
+) -> Result<&'a [u8], Error>
+where
+  L::Id: Eq,
+{
+  let bounds = readable_bounds(l.allocator(), l.options(), l.range_cursor());
+  read_in_bounds(l, id, offset, len, validate, bounds)
+}
+
+/// Returns the `(data_offset, allocated)` bounds of the readable values of a log, the values in a sub-range
+/// end at the `cursor` of the log if any.
+#[inline]
+pub(super) fn readable_bounds<A: Allocator>(
+  allocator: &A,
+  opts: &Options,
+  cursor: Option<&AtomicU32>,
+) -> (usize, usize) {
+  match opts.sub_range() {
+    Some((start, end)) => (
+      allocator.data_offset().max(start as usize),
+      cursor
+        .map_or(allocator.allocated(), |cursor| {
+          cursor.load(core::sync::atomic::Ordering::Acquire) as usize
+        })
+        .min(end as usize),
+    ),
+    None => (allocator.data_offset(), allocator.allocated()),
   }
 }
 
+/// Same as [`read_in`], but the bounds are given by the caller, e.g. cached by a frozen log.
+///
+/// ## Safety
+/// - The buffer `offset..offset + len` must hold a valid bytes sequence.
+/// - `bounds` must be the [`readable_bounds`] of the log.
+pub(super) unsafe fn read_in_bounds<'a, L: LogReader + ?Sized>(
+  l: &'a L,
+  id: &L::Id,
+  offset: u32,
+  len: u32,
+  validate: bool,
+  (data_offset, allocated): (usize, usize),
+) -> Result<&'a [u8], Error>
+where
+  L::Id: Eq,
+{
+  #[cfg(feature = "tracing")]
+  let _span = tracing::trace_span!("valog.read", offset, size = len, validate).entered();
+
+  if l.id().ne(id) {
+    return Err(Error::IdMismatch);
+  }
+
+  if len == 0 {
+    return Ok(&[]);
+  }
+
+  let allocator = l.allocator();
+  let opts = l.options();
+  let cks_len = opts.checksum_len();
+  check_bounds(offset, len, cks_len.len(), (data_offset, allocated))?;
+
+  let offset = offset as usize;
+  let len = len as usize;
+
+  // the bounds may be cached by the caller, double-checks them against the allocator.
+  #[cfg(feature = "paranoid")]
+  {
+    assert!(
+      l.id().eq(id),
+      "paranoid: reading a value with a mismatched id"
+    );
+    assert!(
+      offset >= allocator.data_offset() && offset + len + cks_len.len() <= allocator.allocated(),
+      "paranoid: the value at {offset} with {len} bytes is out of the allocated memory {}..{}",
+      allocator.data_offset(),
+      allocator.allocated(),
+    );
+  }
+
+  // Safety: we have checked the bounds
+  let buf = unsafe { allocator.get_bytes(offset, len + cks_len.len()) };
+  let align = opts.value_alignment;
+  debug_assert!(
+    opts.sub_range().is_some() || offset % align != 0 || buf.as_ptr() as usize % align == 0,
+    "the value at an aligned offset is not aligned in memory"
+  );
+
+  validate_entry(buf, len, cks_len, validate, |bytes| l.checksum(bytes))
+}
+
+/// Splits the value off the entry in `buf`, which is the value of `len` bytes followed by the
+/// checksum of `cks_len` bytes, the checksum is validated by `checksum` if `validate` is `true`.
+#[inline]
+pub(super) fn validate_entry(
+  buf: &[u8],
+  len: usize,
+  cks_len: ChecksumLen,
+  validate: bool,
+  checksum: impl FnOnce(&[u8]) -> u64,
+) -> Result<&[u8], Error> {
+  let (value, cks) = buf.split_at(len);
+  if validate && cks_len.decode(cks) != cks_len.truncate(checksum(value)) {
+    return Err(Error::checksum_mismatch());
+  }
+
+  Ok(value)
+}
+
+/// Checks the entry of `len` bytes value and `cks_len` bytes checksum at `offset` is in the bounds.
+#[inline]
+fn check_bounds(
+  offset: u32,
+  len: u32,
+  cks_len: usize,
+  (data_offset, allocated): (usize, usize),
+) -> Result<(), Error> {
+  let offset = offset as usize;
+  let total = len as usize + cks_len;
+  if offset < data_offset || offset + total > allocated {
+    return Err(Error::out_of_bounds(
+      offset as u32,
+      total as u32,
+      data_offset as u32,
+      allocated as u32,
+    ));
+  }
+
+  Ok(())
+}
+
 /// The extension trait for the [`LogReader`] trait.
 ///
 /// The reason having a `LogReaderExt` is that to make [`LogReader`] object-safe.
@@ -106,6 +564,338 @@ pub trait LogReaderExt: LogReader {
       .read(id, offset, len)
       .map(|buf| <T::Ref<'_> as TypeRef>::from_slice(buf))
   }
+
+  /// Reads a generic value from the log at the given offset, and returns the value
+  /// with the number of payload bytes consumed.
+  ///
+  /// ## Safety
+  /// - The buffer `offset..offset + len` must hold a valid bytes sequence which created by encoding a value of type `T` through [`Type::encode`](Type::encode).
+  ///
+  /// ## Example
+  ///
+  /// ```rust
+  /// use valog::{Builder, sync::ValueLog, LogWriterExt, LogReaderExt, Log};
+  ///
+  /// let log = Builder::new().with_capacity(1024).alloc::<ValueLog>(0).unwrap();
+  ///
+  /// let vp = log.insert_generic(&"Hello, valog!".to_string()).unwrap();
+  ///
+  /// let (data, len) = unsafe { log.read_generic_with_len::<String>(log.id(), vp.offset(), vp.size()).unwrap() };
+  ///
+  /// assert_eq!(data, "Hello, valog!");
+  /// assert_eq!(len, vp.size() as usize);
+  /// ```
+  unsafe fn read_generic_with_len<T: Type>(
+    &self,
+    id: &Self::Id,
+    offset: u32,
+    len: u32,
+  ) -> Result<(T::Ref<'_>, usize), Error>
+  where
+    Self::Id: Eq,
+  {
+    self
+      .read(id, offset, len)
+      .map(|buf| (<T::Ref<'_> as TypeRef>::from_slice(buf), buf.len()))
+  }
+
+  /// Reads a pair of generic values inserted by [`insert_pair`](crate::LogWriterExt::insert_pair) from the log.
+  ///
+  /// Returns [`Error::InvalidLayout`] if the length prefix of the first value exceeds the entry.
+  ///
+  /// ## Safety
+  /// - The buffer `offset..offset + len` must hold a valid bytes sequence which created by
+  ///   [`insert_pair`](crate::LogWriterExt::insert_pair) with the values of type `K` and `V`.
+  ///
+  /// ## Example
+  ///
+  /// ```rust
+  /// use valog::{Builder, sync::ValueLog, Log, LogReaderExt, LogWriterExt};
+  ///
+  /// let log = Builder::new().with_capacity(1024).alloc::<ValueLog>(0).unwrap();
+  /// let vp = log.insert_pair(&"meta".to_string(), &[1u8, 2, 3]).unwrap();
+  ///
+  /// let (meta, payload) = unsafe {
+  ///   log.read_pair::<String, [u8; 3]>(log.id(), vp.offset(), vp.size()).unwrap()
+  /// };
+  /// assert_eq!(meta, "meta");
+  /// assert_eq!(payload, [1, 2, 3]);
+  /// ```
+  unsafe fn read_pair<K: Type, V: Type>(
+    &self,
+    id: &Self::Id,
+    offset: u32,
+    len: u32,
+  ) -> Result<(K::Ref<'_>, V::Ref<'_>), Error>
+  where
+    Self::Id: Eq,
+  {
+    let buf = self.read(id, offset, len)?;
+    if buf.len() < PAIR_PREFIX_SIZE {
+      return Err(Error::InvalidLayout);
+    }
+
+    let (prefix, rest) = buf.split_at(PAIR_PREFIX_SIZE);
+    let first_len = u32::from_le_bytes(prefix.try_into().unwrap()) as usize;
+    if first_len > rest.len() {
+      return Err(Error::InvalidLayout);
+    }
+
+    let (first, second) = rest.split_at(first_len);
+    Ok((
+      <K::Ref<'_> as TypeRef>::from_slice(first),
+      <V::Ref<'_> as TypeRef>::from_slice(second),
+    ))
+  }
+
+  /// Reads a value from the log for recovery, and returns the value with whether the checksum matches.
+  ///
+  /// Unlike [`LogReader::read`], a checksum mismatch is not an error, so that the possibly-corrupt bytes
+  /// can still be inspected or salvaged. The checksum is always calculated, regardless of the
+  /// [`validate_checksum`](crate::options::Options::validate_checksum) configuration.
+  /// Errors are only returned when the id does not match or the value is out of bounds.
+  ///
+  /// ## Safety
+  /// - The buffer `offset..offset + len` must hold a valid bytes sequence.
+  ///
+  /// ## Example
+  ///
+  /// ```rust
+  /// use valog::{Builder, sync::ValueLog, LogWriter, LogReaderExt, Log};
+  ///
+  /// let log = Builder::new().with_capacity(1024).alloc::<ValueLog>(0).unwrap();
+  ///
+  /// let vp = log.insert(b"Hello, valog!").unwrap();
+  ///
+  /// let (data, intact) = unsafe { log.read_lossy(log.id(), vp.offset(), vp.size()).unwrap() };
+  /// assert_eq!(data, b"Hello, valog!");
+  /// assert!(intact);
+  /// ```
+  unsafe fn read_lossy(&self, id: &Self::Id, offset: u32, len: u32) -> Result<(&[u8], bool), Error>
+  where
+    Self::Id: Eq,
+  {
+    let buf = read_in(self, id, offset, len, false)?;
+    if buf.is_empty() {
+      return Ok((buf, true));
+    }
+
+    let cks_len = self.options().checksum_len();
+    // Safety: the bounds of the value and its checksum have been checked by `read_in`
+    let checksum = cks_len.decode(unsafe {
+      self
+        .allocator()
+        .get_bytes(offset as usize + buf.len(), cks_len.len())
+    });
+    Ok((buf, checksum == cks_len.truncate(self.checksum(buf))))
+  }
+
+  /// Verifies the checksums of all the values pointed by `ptrs`, regardless of the
+  /// [`validate_checksum`](crate::options::Options::validate_checksum) configuration.
+  ///
+  /// Unlike [`LogReader::scrub`], this method does not return on the first bad value, but collects
+  /// every failure with its pointer, which gives a complete damage report of the log.
+  /// An empty vec means all the values are intact.
+  ///
+  /// ## Safety
+  /// - The `ptrs` must point to valid bytes sequences.
+  ///
+  /// ## Example
+  ///
+  /// ```rust
+  /// use valog::{Builder, sync::ValueLog, LogWriter, LogReaderExt, ValuePointer};
+  ///
+  /// let log = Builder::new().with_capacity(1024).alloc::<ValueLog>(0).unwrap();
+  ///
+  /// let vp = log.insert(b"foo").unwrap();
+  /// let ptrs = [vp, ValuePointer::new(1, vp.offset(), vp.size()), ValuePointer::new(0, 1024, 3)];
+  /// let report = unsafe { log.verify_all(&ptrs) };
+  /// assert_eq!(report.len(), 2);
+  /// assert_eq!(report[0].0, ptrs[1]);
+  /// assert_eq!(report[1].0, ptrs[2]);
+  /// ```
+  #[cfg(any(feature = "alloc", feature = "std"))]
+  #[cfg_attr(docsrs, doc(cfg(any(feature = "alloc", feature = "std"))))]
+  unsafe fn verify_all(
+    &self,
+    ptrs: &[ValuePointer<Self::Id>],
+  ) -> std::vec::Vec<(ValuePointer<Self::Id>, Error)>
+  where
+    Self::Id: Eq + CheapClone,
+  {
+    let report = ptrs
+      .iter()
+      .filter_map(|vp| {
+        read_in(self, vp.id(), vp.offset(), vp.size(), true)
+          .err()
+          .map(|e| (vp.cheap_clone(), e))
+      })
+      .collect();
+
+    // evicting the page cache is a best-effort hint, which should not hide the report
+    #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+    let _ = common::evict_after_scan(self);
+
+    report
+  }
+
+  /// Copies the live values pointed by `ptrs` into `dst`, the tombstones are dropped,
+  /// and returns the mappings from the old value pointers to the new ones, which can be used to rewrite the index.
+  ///
+  /// The values are copied through [`LogWriterExt::merge_from`](crate::LogWriterExt::merge_from), so the checksums
+  /// are reused when possible, and the copied values are flushed once at the end.
+  ///
+  /// ## Safety
+  /// - The `ptrs` must point to valid values in this log.
+  ///
+  /// ## Example
+  ///
+  /// ```rust
+  /// use valog::{Builder, sync::ValueLog, LogReader, LogReaderExt, LogWriter};
+  ///
+  /// let src = Builder::new().with_capacity(1024).alloc::<ValueLog>(0).unwrap();
+  /// let ptrs = [
+  ///   src.insert(b"foo").unwrap(),
+  ///   src.insert_tombstone(b"bar").unwrap(),
+  ///   src.insert(b"baz").unwrap(),
+  /// ];
+  ///
+  /// let dst = Builder::new().with_capacity(1024).alloc::<ValueLog>(1).unwrap();
+  /// let mappings = unsafe { src.compact_into(&dst, &ptrs).unwrap() };
+  /// assert_eq!(mappings.len(), 2);
+  /// assert_eq!(mappings[1].0, ptrs[2]);
+  ///
+  /// let new = mappings[1].1;
+  /// let data = unsafe { dst.read(new.id(), new.offset(), new.size()).unwrap() };
+  /// assert_eq!(data, b"baz");
+  /// ```
+  #[cfg(any(feature = "alloc", feature = "std"))]
+  #[cfg_attr(docsrs, doc(cfg(any(feature = "alloc", feature = "std"))))]
+  unsafe fn compact_into<L>(
+    &self,
+    dst: &L,
+    ptrs: &[ValuePointer<Self::Id>],
+  ) -> Result<std::vec::Vec<(ValuePointer<Self::Id>, ValuePointer<L::Id>)>, Error>
+  where
+    L: LogWriter,
+    L::Id: CheapClone + core::fmt::Debug,
+    Self: Sized,
+    Self::Id: Eq + CheapClone,
+  {
+    let live = ptrs
+      .iter()
+      .filter(|vp| !vp.is_tombstone())
+      .map(CheapClone::cheap_clone)
+      .collect::<std::vec::Vec<_>>();
+    let new_ptrs = dst.merge_from(self, &live)?;
+    Ok(live.into_iter().zip(new_ptrs).collect())
+  }
+
+  /// Returns `true` if the values pointed by `self_ptrs` in this log are the same as the values pointed by
+  /// `other_ptrs` in `other` pairwise, which is useful to check the result of a compaction.
+  ///
+  /// The ids and the offsets of the pointers are ignored, only the lengths, the tombstone marks and the payloads
+  /// are compared. If any value cannot be read (e.g. out of bounds or checksum mismatch), `false` is returned.
+  ///
+  /// ## Safety
+  /// - The `self_ptrs` must point to valid values in this log, and the `other_ptrs` must point to valid values in `other`.
+  ///
+  /// ## Example
+  ///
+  /// ```rust
+  /// use valog::{Builder, sync::ValueLog, LogReaderExt, LogWriter};
+  ///
+  /// let src = Builder::new().with_capacity(1024).alloc::<ValueLog>(0).unwrap();
+  /// let ptrs = [
+  ///   src.insert(b"foo").unwrap(),
+  ///   src.insert_tombstone(b"bar").unwrap(),
+  ///   src.insert(b"baz").unwrap(),
+  /// ];
+  ///
+  /// let dst = Builder::new().with_capacity(1024).alloc::<ValueLog>(1).unwrap();
+  /// let mappings = unsafe { src.compact_into(&dst, &ptrs).unwrap() };
+  /// let (old, new): (Vec<_>, Vec<_>) = mappings.into_iter().unzip();
+  ///
+  /// assert!(unsafe { src.contents_eq(&old, &dst, &new) });
+  /// assert!(!unsafe { src.contents_eq(&ptrs, &dst, &new) });
+  /// ```
+  unsafe fn contents_eq<L>(
+    &self,
+    self_ptrs: &[ValuePointer<Self::Id>],
+    other: &L,
+    other_ptrs: &[ValuePointer<L::Id>],
+  ) -> bool
+  where
+    L: LogReader,
+    L::Id: Eq,
+    Self::Id: Eq,
+  {
+    if self_ptrs.len() != other_ptrs.len() {
+      return false;
+    }
+
+    self_ptrs.iter().zip(other_ptrs).all(|(a, b)| {
+      if a.size() != b.size() || a.is_tombstone() != b.is_tombstone() {
+        return false;
+      }
+
+      match (
+        self.read(a.id(), a.offset(), a.size()),
+        other.read(b.id(), b.offset(), b.size()),
+      ) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+      }
+    })
+  }
+
+  /// Reads a value from the log, the checksum is validated by the given `checksumer` instead of the one of the log,
+  /// e.g. to read the entries written by another checksum algorithm during a migration.
+  ///
+  /// The checksum is always validated, regardless of the
+  /// [`validate_checksum`](crate::options::Options::validate_checksum) configuration.
+  ///
+  /// ## Safety
+  /// - The buffer `offset..offset + len` must hold a valid bytes sequence.
+  ///
+  /// ## Example
+  ///
+  /// ```rust
+  /// use valog::{checksum::Crc32, Builder, sync::ValueLog, LogWriter, LogReaderExt, Log};
+  ///
+  /// let log = Builder::new().with_capacity(1024).alloc::<ValueLog>(0).unwrap();
+  /// let vp = log.insert(b"Hello, valog!").unwrap();
+  ///
+  /// let data = unsafe { log.read_with_checksumer(log.id(), vp.offset(), vp.size(), &Crc32::new()).unwrap() };
+  /// assert_eq!(data, b"Hello, valog!");
+  /// ```
+  unsafe fn read_with_checksumer<C>(
+    &self,
+    id: &Self::Id,
+    offset: u32,
+    len: u32,
+    checksumer: &C,
+  ) -> Result<&[u8], Error>
+  where
+    Self::Id: Eq,
+    C: BuildChecksumer,
+  {
+    // the bounds of the value and the checksum are checked here.
+    let value = read_in(self, id, offset, len, false)?;
+    if len == 0 {
+      return Ok(value);
+    }
+
+    let cks_len = self.options().checksum_len();
+    let cks = self
+      .allocator()
+      .get_bytes((offset + len) as usize, cks_len.len());
+    if cks_len.decode(cks) != cks_len.truncate(checksumer.checksum_one(value)) {
+      return Err(Error::checksum_mismatch());
+    }
+
+    Ok(value)
+  }
 }
 
 impl<L: LogReader> LogReaderExt for L {}
@@ -142,6 +932,74 @@ pub trait GenericLogReader: Log {
   where
     Self::Type: Type,
     Self::Id: Eq;
+
+  /// Reads a generic value from the log at the given offset, and returns the value
+  /// with the number of payload bytes consumed.
+  ///
+  /// ## Safety
+  /// - The buffer `offset..offset + len` must hold a valid bytes sequence which created by encoding a value of type `T` through [`Type::encode`](Type::encode).
+  ///
+  /// ## Example
+  ///
+  /// ```rust
+  /// use valog::{Builder, sync::GenericValueLog, GenericLogWriter, GenericLogReader, Log};
+  ///
+  /// let log = Builder::new().with_capacity(1024).alloc::<GenericValueLog<String>>(0).unwrap();
+  ///
+  /// let vp = log.insert(&"Hello, valog!".to_string()).unwrap();
+  ///
+  /// let (data, len) = unsafe { log.read_with_len(log.id(), vp.offset(), vp.size()).unwrap() };
+  ///
+  /// assert_eq!(data, "Hello, valog!");
+  /// assert_eq!(len, vp.size() as usize);
+  /// ```
+  unsafe fn read_with_len(
+    &self,
+    id: &Self::Id,
+    offset: u32,
+    len: u32,
+  ) -> Result<(<Self::Type as Type>::Ref<'_>, usize), Error>
+  where
+    Self::Type: Type,
+    Self::Id: Eq,
+  {
+    self.read(id, offset, len).map(|r| (r, len as usize))
+  }
+
+  /// Returns an iterator which reads the generic values pointed by `ptrs` lazily, in the order of `ptrs`.
+  ///
+  /// Each item is the result of [`read`](GenericLogReader::read) of the pointer, so an error does not stop the iteration.
+  ///
+  /// ## Safety
+  /// - The `ptrs` must point to valid bytes sequences which created by encoding values of type `T` through [`Type::encode`](Type::encode).
+  ///
+  /// ## Example
+  ///
+  /// ```rust
+  /// use valog::{Builder, sync::GenericValueLog, GenericLogWriter, GenericLogReader};
+  ///
+  /// let log = Builder::new().with_capacity(1024).alloc::<GenericValueLog<String>>(0).unwrap();
+  ///
+  /// let ptrs = ["foo", "bar", "baz"].map(|v| log.insert(&v.to_string()).unwrap());
+  ///
+  /// let values = unsafe { log.read_generic_many(&ptrs) }
+  ///   .collect::<Result<Vec<_>, _>>()
+  ///   .unwrap();
+  /// assert_eq!(values, ["foo", "bar", "baz"]);
+  /// ```
+  unsafe fn read_generic_many<'a>(
+    &'a self,
+    ptrs: &'a [ValuePointer<Self::Id>],
+  ) -> impl Iterator<Item = Result<<Self::Type as Type>::Ref<'a>, Error>> + 'a
+  where
+    Self: Sized,
+    Self::Type: Type,
+    Self::Id: Eq,
+  {
+    ptrs
+      .iter()
+      .map(move |vp| self.read(vp.id(), vp.offset(), vp.size()))
+  }
 }
 
 impl<L> GenericLogReader for L
@@ -1,3 +1,5 @@
+use core::sync::atomic::AtomicU32;
+
 use crate::options::Options;
 
 use super::Allocator;
@@ -8,6 +10,51 @@ pub trait Sealed {
 
   /// Returns the allocator used by the log.
   fn allocator(&self) -> &Self::Allocator;
+
+  /// Returns the mutable reference to the allocator and the options of the log.
+  fn allocator_and_options_mut(&mut self) -> (&mut Self::Allocator, &mut Options);
+
+  /// Returns the counter of the non-empty values inserted into the log, `None` if the log cannot be written.
+  #[inline]
+  fn entry_counter(&self) -> Option<&AtomicU32> {
+    None
+  }
+
+  /// Returns the allocation cursor of the log in its sub-range, `None` if the log is not restricted to a
+  /// sub-range of the allocator, see [`Builder::with_external_arena`](crate::Builder::with_external_arena).
+  #[inline]
+  fn range_cursor(&self) -> Option<&AtomicU32> {
+    None
+  }
+
+  /// Returns the cell recording the page faults taken by the first scan, `None` if the log does not track them.
+  #[cfg(feature = "metrics")]
+  #[inline]
+  fn page_faults(&self) -> Option<&std::sync::OnceLock<super::PageFaultStats>> {
+    None
+  }
+
+  /// Returns the file used to flush the synced values, `None` if the values are flushed through the allocator.
+  #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+  #[inline]
+  fn sync_file(&self) -> Option<&std::fs::File> {
+    None
+  }
+
+  /// Returns the path of the file which is shared by the memory map of the log, `None` if the log is not
+  /// backed by a shared file mapping, e.g. an anonymous or a private (copy-on-write) memory map.
+  #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+  #[inline]
+  fn shared_file_path(&self) -> Option<&std::path::Path> {
+    None
+  }
+
+  /// Returns the lock which serializes the updates of the header of the log, `None` if the log cannot be written.
+  #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+  #[inline]
+  fn header_lock(&self) -> Option<&std::sync::Mutex<()>> {
+    None
+  }
 }
 
 /// A trait that means can be constructed to a value log.
@@ -26,4 +73,17 @@ pub trait Constructor: Sealed {
     checksumer: Self::Checksumer,
     options: Options,
   ) -> Self;
+
+  /// Returns the mutable reference to the file id.
+  fn id_mut(&mut self) -> &mut Self::Id;
+
+  /// Sets the file used to flush the synced values, which is ignored by the logs cannot be written.
+  #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+  #[inline]
+  fn set_sync_file(&mut self, _file: std::fs::File) {}
+
+  /// Sets the path of the file which is shared by the memory map of the log.
+  #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+  #[inline]
+  fn set_shared_file_path(&mut self, _path: std::path::PathBuf) {}
 }
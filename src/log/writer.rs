@@ -6,6 +6,9 @@ use super::*;
 pub trait LogWriter: Log {
   /// Inserts a value into the log.
   ///
+  /// An empty value is not stored, the returned value pointer has offset `0` and size `0`, and reading it
+  /// returns an empty slice. Use [`insert_empty`](LogWriter::insert_empty) to store an empty value explicitly.
+  ///
   /// ## Example
   ///
   /// ```rust
@@ -20,11 +23,330 @@ pub trait LogWriter: Log {
   where
     Self::Id: CheapClone + core::fmt::Debug,
   {
-    let vb = ValueBuilder::new(value.len(), |buf: &mut VacantBuffer<'_>| {
-      buf.put_slice_unchecked(value);
-      Ok(())
-    });
-    insert_in::<_, ()>(self, vb).map_err(|e| e.unwrap_right())
+    insert_slice_in(self, value)
+  }
+
+  /// Inserts an empty value into the log, which is stored as an entry holding only the checksum.
+  ///
+  /// [`insert`](LogWriter::insert) with an empty value does not allocate, and returns a value pointer with
+  /// offset `0` and size `0`, which does not refer to anything in the log. The value pointer returned by this method
+  /// refers to a real entry, so [`ValuePointer::is_stored`] tells an explicitly stored empty value apart from
+  /// an unstored one or a [`ValuePointer::null`]. Both of them are read as an empty slice.
+  ///
+  /// ## Example
+  ///
+  /// ```rust
+  /// use valog::{Builder, sync::ValueLog, Log, LogReader, LogWriter};
+  ///
+  /// let log = Builder::new().with_capacity(1024).alloc::<ValueLog>(0).unwrap();
+  ///
+  /// let stored = log.insert_empty().unwrap();
+  /// assert!(stored.is_stored());
+  /// assert_eq!(stored.size(), 0);
+  /// assert_eq!(unsafe { log.read(log.id(), stored.offset(), stored.size()).unwrap() }, b"");
+  ///
+  /// assert!(!log.insert(b"").unwrap().is_stored());
+  /// ```
+  #[inline]
+  fn insert_empty(&self) -> Result<ValuePointer<Self::Id>, Error>
+  where
+    Self::Id: CheapClone + core::fmt::Debug,
+  {
+    store_entry_in(self, &[], None, false)
+  }
+
+  /// Inserts a value into the log if there is enough space for it.
+  ///
+  /// This method is almost the same as the [`insert`](LogWriter::insert) method, the only difference is that
+  /// running out of space is reported as [`InsufficientSpaceError`] in [`Either::Left`], so that the caller
+  /// can write the value to another log without checking the remaining space first. Other errors
+  /// (e.g. [`Error::ValueTooLarge`]) are returned in [`Either::Right`].
+  ///
+  /// ## Example
+  ///
+  /// ```rust
+  /// use valog::{Builder, sync::ValueLog, LogWriter};
+  ///
+  /// let log = Builder::new().with_capacity(57).alloc::<ValueLog>(0).unwrap();
+  ///
+  /// let vp = log.insert_if_fits(b"Hello, valog!").unwrap();
+  /// let err = log.insert_if_fits(b"Hello, valog!").unwrap_err().unwrap_left();
+  /// assert!(err.requested() > err.available());
+  /// ```
+  #[inline]
+  fn insert_if_fits(
+    &self,
+    value: &[u8],
+  ) -> Result<ValuePointer<Self::Id>, Either<InsufficientSpaceError, Error>>
+  where
+    Self::Id: CheapClone + core::fmt::Debug,
+  {
+    self.insert(value).map_err(|e| match e {
+      Error::InsufficientSpace {
+        requested,
+        available,
+      } => Either::Left(InsufficientSpaceError::new(requested, available)),
+      e => Either::Right(e),
+    })
+  }
+
+  /// Inserts a value into the log, grows the log if there is not enough space and
+  /// [`anon_grow`](crate::options::Options::anon_grow) is enabled for an anonymous memory map backed log.
+  ///
+  /// Growing creates a new larger anonymous memory map, copies the old contents to it and swaps it in.
+  /// The existing value pointers remain valid, because they are offset-relative, but the slices
+  /// returned by the previous reads are invalidated, which is why this method takes `&mut self`.
+  /// The log is not grown if its memory map is shared with the clones of the log (or of the allocator),
+  /// the original [`Error::InsufficientSpace`] is returned instead, because the values inserted through
+  /// the clones would not be visible in the new memory map.
+  ///
+  /// ## Example
+  ///
+  /// ```rust
+  /// use valog::{Builder, sync::ValueLog, LogReader, LogWriter, Log};
+  ///
+  /// let mut log = Builder::new()
+  ///   .with_capacity(64)
+  ///   .with_anon_grow(true)
+  ///   .map_anon::<ValueLog>(0)
+  ///   .unwrap();
+  ///
+  /// let ptrs = (0..1000u32)
+  ///   .map(|i| log.insert_or_grow(&i.to_le_bytes()).unwrap())
+  ///   .collect::<Vec<_>>();
+  ///
+  /// let data = unsafe { log.read(log.id(), ptrs[0].offset(), ptrs[0].size()).unwrap() };
+  /// assert_eq!(data, 0u32.to_le_bytes());
+  /// ```
+  #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+  #[cfg_attr(docsrs, doc(cfg(all(feature = "memmap", not(target_family = "wasm")))))]
+  fn insert_or_grow(&mut self, value: &[u8]) -> Result<ValuePointer<Self::Id>, Error>
+  where
+    Self::Id: CheapClone + core::fmt::Debug,
+  {
+    match self.insert(value) {
+      Err(Error::InsufficientSpace { .. })
+        if self.options().anon_grow()
+          && self.allocator().is_map_anon()
+          && self.allocator().refs() == 1 =>
+      {
+        let (value, _) = truncate_oversize(self.options(), value);
+        let (_, total) = entry_size(self.options(), value.len() + self.options().seal_overhead())?;
+        grow_anon(self, total)?;
+        self.insert(value)
+      }
+      res => res,
+    }
+  }
+
+  /// Checks if a value with the given length can be inserted into the log, without allocating.
+  ///
+  /// Returns [`Error::ReadOnly`] if the log is read-only, [`Error::ValueTooLarge`] if the value (including
+  /// the checksum) exceeds the [`maximum_value_size`](crate::options::Options::maximum_value_size) and is not
+  /// truncated by [`OversizePolicy::Truncate`](crate::options::OversizePolicy::Truncate),
+  /// [`Error::MaxEntriesReached`] if the log already holds [`max_entries`](crate::options::Options::max_entries)
+  /// entries, or [`Error::InsufficientSpace`] if there is not enough remaining space in the log (or in its
+  /// sub-range, see [`Builder::with_external_arena`](crate::Builder::with_external_arena)). Only the
+  /// unallocated tail of the log is considered, the space which may be reused by the freelist is not counted.
+  ///
+  /// ## Example
+  ///
+  /// ```rust
+  /// use valog::{Builder, sync::ValueLog, LogWriter};
+  ///
+  /// let log = Builder::new().with_capacity(1024).alloc::<ValueLog>(0).unwrap();
+  ///
+  /// assert!(log.can_insert(13).is_ok());
+  /// assert!(log.can_insert(1024).is_err());
+  /// ```
+  #[inline]
+  fn can_insert(&self, value_len: u32) -> Result<(), Error> {
+    let opts = self.options();
+    let value_len = truncated_len(opts, value_len as usize);
+    if value_len == 0 {
+      return Ok(());
+    }
+
+    let allocator = self.allocator();
+    if allocator.read_only() {
+      return Err(Error::read_only());
+    }
+
+    let (_, total) = entry_size(opts, value_len + opts.seal_overhead())?;
+    if let Some(counter) = self.entry_counter() {
+      let entries = counter.load(core::sync::atomic::Ordering::Acquire);
+      if entries >= opts.max_entries {
+        return Err(Error::max_entries_reached(opts.max_entries));
+      }
+    }
+
+    let available = available_space(self);
+    if total > available {
+      return Err(Error::insufficient_space(total as u32, available as u32));
+    }
+
+    Ok(())
+  }
+
+  /// Returns the number of the non-empty values (including tombstones) and the empty values stored by
+  /// [`insert_empty`](LogWriter::insert_empty) inserted through the log and its clones, which is capped by [`max_entries`](crate::options::Options::max_entries).
+  ///
+  /// The entries are not framed in the log, so the counter starts from `0` when a log is opened,
+  /// the values inserted before reopening are not counted.
+  ///
+  /// ## Example
+  ///
+  /// ```rust
+  /// use valog::{error::Error, Builder, sync::ValueLog, LogWriter};
+  ///
+  /// let log = Builder::new()
+  ///   .with_capacity(1024)
+  ///   .with_max_entries(2)
+  ///   .alloc::<ValueLog>(0)
+  ///   .unwrap();
+  ///
+  /// log.insert(b"foo").unwrap();
+  /// log.insert(b"").unwrap();
+  /// log.insert_tombstone(b"bar").unwrap();
+  /// assert_eq!(log.entries(), 2);
+  ///
+  /// let err = log.insert(b"baz").unwrap_err();
+  /// assert!(matches!(err, Error::MaxEntriesReached { maximum: 2 }));
+  /// ```
+  #[inline]
+  fn entries(&self) -> u32 {
+    self.entry_counter().map_or(0, |counter| {
+      counter.load(core::sync::atomic::Ordering::Acquire)
+    })
+  }
+
+  /// Inserts all the values into the log with a single allocation, and returns the value pointers
+  /// in the same order of `values`.
+  ///
+  /// Each value is stored with its own checksum, so the values can be read independently, and the
+  /// inserted values are flushed once at the end if [`sync`](crate::options::Options::sync) is enabled.
+  /// If there is not enough space for all the values, none of them is inserted.
+  ///
+  /// ## Example
+  ///
+  /// ```rust
+  /// use valog::{Builder, sync::ValueLog, LogReader, LogWriter, Log};
+  ///
+  /// let log = Builder::new().with_capacity(1024).alloc::<ValueLog>(0).unwrap();
+  /// let ptrs = log.insert_all(&[b"foo", b"", b"bar"]).unwrap();
+  ///
+  /// let data = unsafe { log.read(log.id(), ptrs[2].offset(), ptrs[2].size()).unwrap() };
+  /// assert_eq!(data, b"bar");
+  /// ```
+  #[cfg(any(feature = "alloc", feature = "std"))]
+  #[cfg_attr(docsrs, doc(cfg(any(feature = "alloc", feature = "std"))))]
+  fn insert_all(&self, values: &[&[u8]]) -> Result<std::vec::Vec<ValuePointer<Self::Id>>, Error>
+  where
+    Self::Id: CheapClone + core::fmt::Debug,
+  {
+    let opts = self.options();
+    let cks_len = opts.checksum_len();
+    let align = opts.value_alignment;
+
+    let mut total = 0usize;
+    let mut non_empty = 0usize;
+    for value in values
+      .iter()
+      .map(|v| truncate_oversize(opts, v).0)
+      .filter(|v| !v.is_empty())
+    {
+      let (_, size) = entry_size(opts, value.len() + opts.seal_overhead())?;
+      total += size;
+      non_empty += 1;
+    }
+
+    if total > u32::MAX as usize {
+      return Err(Error::value_too_large(total, u32::MAX as usize));
+    }
+
+    let mut ptrs = std::vec::Vec::with_capacity(values.len());
+    if total == 0 {
+      ptrs.extend(values.iter().map(|v| {
+        ValuePointer::new(self.id().cheap_clone(), 0, 0)
+          .with_truncated(truncate_oversize(opts, v).1)
+      }));
+      return Ok(ptrs);
+    }
+
+    let entries = reserve_entries(self, non_empty as u32)?;
+    let allocator = self.allocator();
+    let mut buf = alloc_entry(self, total)?;
+    let buf_offset = buf.offset();
+    let ptr = buf.as_mut_ptr();
+    let mut cursor = 0;
+
+    for value in values {
+      let (value, truncated) = truncate_oversize(opts, value);
+      if value.is_empty() {
+        ptrs.push(ValuePointer::new(self.id().cheap_clone(), 0, 0).with_truncated(truncated));
+        continue;
+      }
+
+      let offset = buf_offset + cursor;
+      let padding = offset.next_multiple_of(align) - offset;
+      let start = cursor + padding;
+      let stored_len = value.len() + opts.seal_overhead();
+
+      // SAFETY: `buf` is allocated with the size of all the values, checksums and paddings.
+      unsafe {
+        core::ptr::write_bytes(ptr.add(cursor), 0, padding);
+        core::ptr::copy_nonoverlapping(
+          value.as_ptr(),
+          ptr.add(start + opts.nonce_size()),
+          value.len(),
+        );
+        seal_in_place(opts, ptr.add(start), buf_offset + start, stored_len);
+        let checksum = self.checksum(core::slice::from_raw_parts(ptr.add(start), stored_len));
+        core::ptr::copy_nonoverlapping(
+          checksum.to_le_bytes().as_ptr(),
+          ptr.add(start + stored_len),
+          cks_len.len(),
+        );
+      }
+
+      ptrs.push(
+        ValuePointer::new(
+          self.id().cheap_clone(),
+          (buf_offset + start) as u32,
+          stored_len as u32,
+        )
+        .with_truncated(truncated),
+      );
+      cursor = start + stored_len + cks_len.len();
+    }
+
+    // SAFETY: `cursor` is in the bounds of `buf`.
+    unsafe {
+      core::ptr::write_bytes(ptr.add(cursor), 0, total - cursor);
+    }
+
+    #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+    if opts.sync && allocator.is_ondisk() {
+      flush_entry(self, buf_offset, total)?;
+    }
+
+    if align > 1 {
+      allocator.increase_discarded((non_empty * (align - 1)) as u32);
+    }
+
+    // Safety: no need to drop
+    unsafe {
+      buf.detach();
+    }
+    entries.commit();
+
+    #[cfg(feature = "paranoid")]
+    ptrs
+      .iter()
+      .filter(|vp| vp.size() != 0)
+      .for_each(|vp| paranoid_check(self, vp.offset() as usize, vp.size() as usize, true));
+
+    Ok(ptrs)
   }
 
   /// Inserts a tombstone value into the log.
@@ -46,18 +368,144 @@ pub trait LogWriter: Log {
     Self::Id: CheapClone + core::fmt::Debug,
   {
     self.insert(value).map(|vp| {
-      self.allocator().increase_discarded(value.len() as u32);
+      self.allocator().increase_discarded(vp.size());
       vp.with_tombstone()
     })
   }
+
+  /// Inserts a value with an application-defined flags byte, e.g. "compressed", "encrypted" or "pinned".
+  ///
+  /// The flags byte is stored right after the value, so it is covered by the checksum of the entry,
+  /// and the size of the returned value pointer includes the flags byte.
+  /// Use [`LogReader::read_with_flags`](crate::LogReader::read_with_flags) to read the value and the flags back.
+  ///
+  /// ## Example
+  ///
+  /// ```rust
+  /// use valog::{Builder, sync::ValueLog, LogReader, LogWriter, Log};
+  ///
+  /// let log = Builder::new().with_capacity(1024).alloc::<ValueLog>(0).unwrap();
+  /// let vp = log.insert_with_flags(b"Hello, valog!", 0b101).unwrap();
+  /// assert_eq!(vp.size(), 14);
+  ///
+  /// let (data, flags) = unsafe { log.read_with_flags(log.id(), vp.offset(), vp.size()).unwrap() };
+  /// assert_eq!(data, b"Hello, valog!");
+  /// assert_eq!(flags, 0b101);
+  /// ```
+  #[inline]
+  fn insert_with_flags(&self, value: &[u8], flags: u8) -> Result<ValuePointer<Self::Id>, Error>
+  where
+    Self::Id: CheapClone + core::fmt::Debug,
+  {
+    let vb = ValueBuilder::new(value.len() + 1, |buf: &mut VacantBuffer<'_>| {
+      buf.put_slice_unchecked(value);
+      buf.put_u8_unchecked(flags);
+      Ok(())
+    });
+    insert_in::<_, ()>(self, vb, None, true).map_err(|e| e.unwrap_right())
+  }
+
+  /// Inserts a value at the caller-chosen `offset`, e.g. to build a fixed-slot log whose slot positions
+  /// encode information.
+  ///
+  /// The log is append-only, so `offset` must not be less than the end of the written entries, otherwise
+  /// [`Error::Overlap`] is returned. The bytes between the end of the written entries and `offset` are
+  /// claimed as discarded bytes, and the append cursor is moved to the end of the new entry, so the following
+  /// inserts are placed after it. The value is placed at `offset` exactly, without the
+  /// [`value_alignment`](crate::options::Options::value_alignment) padding.
+  ///
+  /// ## Example
+  ///
+  /// ```rust
+  /// use valog::{error::Error, Builder, sync::ValueLog, Log, LogReader, LogWriter};
+  ///
+  /// let log = Builder::new().with_capacity(1024).alloc::<ValueLog>(0).unwrap();
+  /// let vp = log.insert_at(512, b"Hello, valog!").unwrap();
+  /// assert_eq!(vp.offset(), 512);
+  ///
+  /// let data = unsafe { log.read(log.id(), vp.offset(), vp.size()).unwrap() };
+  /// assert_eq!(data, b"Hello, valog!");
+  ///
+  /// let err = log.insert_at(256, b"Hello, valog!").unwrap_err();
+  /// assert!(matches!(err, Error::Overlap { .. }));
+  /// ```
+  fn insert_at(&self, offset: u32, value: &[u8]) -> Result<ValuePointer<Self::Id>, Error>
+  where
+    Self::Id: CheapClone + core::fmt::Debug,
+  {
+    insert_at_in(self, offset, value)
+  }
 }
 
 /// The extension trait for the [`LogWriter`] trait.
 ///
 /// The reason having a `LogWriterExt` is that to make [`LogWriter`] object-safe.
 pub trait LogWriterExt: LogWriter {
+  /// Returns a [`BufferedWriter`] which coalesces the small inserts in a reservation of
+  /// [`write_buffer`](crate::options::Options::write_buffer) bytes.
+  ///
+  /// ## Example
+  ///
+  /// ```rust
+  /// use valog::{Builder, sync::ValueLog, LogReader, LogWriterExt};
+  ///
+  /// let log = Builder::new()
+  ///   .with_capacity(1024)
+  ///   .with_write_buffer(256)
+  ///   .alloc::<ValueLog>(0)
+  ///   .unwrap();
+  ///
+  /// let mut writer = log.buffered();
+  /// let ptrs = [writer.insert(b"foo").unwrap(), writer.insert(b"bar").unwrap()];
+  /// writer.flush().unwrap();
+  /// drop(writer);
+  ///
+  /// let data = unsafe { log.read(ptrs[1].id(), ptrs[1].offset(), ptrs[1].size()).unwrap() };
+  /// assert_eq!(data, b"bar");
+  /// ```
+  #[inline]
+  fn buffered(&self) -> BufferedWriter<'_, Self> {
+    BufferedWriter::new(self)
+  }
+
+  /// Inserts a fixed-size value into the log, e.g. an ID, a hash or a timestamp.
+  ///
+  /// This method is the same as the [`insert`](LogWriter::insert) method, but the length of the value
+  /// is known at compile time. The [`maximum_value_size`](crate::options::Options::maximum_value_size)
+  /// is configured at runtime, so it is still checked.
+  ///
+  /// ## Example
+  ///
+  /// ```rust
+  /// use valog::{Builder, sync::ValueLog, Log, LogReader, LogWriterExt};
+  ///
+  /// let log = Builder::new().with_capacity(1024).alloc::<ValueLog>(0).unwrap();
+  /// let vp = log.insert_array(&[0xAB; 32]).unwrap();
+  /// assert_eq!(vp.size(), 32);
+  ///
+  /// let data = unsafe { log.read(log.id(), vp.offset(), vp.size()).unwrap() };
+  /// assert_eq!(data, &[0xAB; 32]);
+  /// ```
+  #[inline]
+  fn insert_array<const N: usize>(&self, value: &[u8; N]) -> Result<ValuePointer<Self::Id>, Error>
+  where
+    Self::Id: CheapClone + core::fmt::Debug,
+  {
+    const {
+      assert!(
+        N <= u32::MAX as usize,
+        "the array is too large to be a value"
+      );
+    }
+
+    insert_slice_in(self, value)
+  }
+
   /// Inserts a generic value into the log.
   ///
+  /// Returns [`Error::EncodeLengthMismatch`] if the number of bytes written by the value
+  /// does not equal its `encoded_len`, the space allocated for the value is released.
+  ///
   /// ## Example
   ///
   /// ```rust
@@ -73,10 +521,69 @@ pub trait LogWriterExt: LogWriter {
     Self::Id: CheapClone + core::fmt::Debug,
   {
     let encoded_len = value.encoded_len();
-    self.insert_with(ValueBuilder::new(
-      encoded_len,
-      |buf: &mut VacantBuffer<'_>| value.encode_to_buffer(buf).map(|_| ()),
-    ))
+    self
+      .insert_with(ValueBuilder::new(
+        encoded_len,
+        |buf: &mut VacantBuffer<'_>| encode_generic(value, encoded_len, buf),
+      ))
+      .map_err(flatten_encode_err)
+  }
+
+  /// Inserts a pair of generic values into the log as one entry, which can be read back by
+  /// [`read_pair`](crate::LogReaderExt::read_pair).
+  ///
+  /// The entry is the little-endian `u32` length of the encoded `first`, followed by the encoded
+  /// `first` and `second`, the checksum covers the whole entry. So the two values are always stored
+  /// contiguously without defining a wrapper [`Type`] for the pair.
+  ///
+  /// ## Example
+  ///
+  /// ```rust
+  /// use valog::{Builder, sync::ValueLog, Log, LogReaderExt, LogWriterExt};
+  ///
+  /// let log = Builder::new().with_capacity(1024).alloc::<ValueLog>(0).unwrap();
+  /// let vp = log.insert_pair(&"meta".to_string(), &"payload".to_string()).unwrap();
+  ///
+  /// let (meta, payload) = unsafe {
+  ///   log.read_pair::<String, String>(log.id(), vp.offset(), vp.size()).unwrap()
+  /// };
+  /// assert_eq!(meta, "meta");
+  /// assert_eq!(payload, "payload");
+  /// ```
+  #[inline]
+  fn insert_pair<K, V>(
+    &self,
+    first: &K,
+    second: &V,
+  ) -> Result<ValuePointer<Self::Id>, Either<Either<K::Error, V::Error>, Error>>
+  where
+    K: Type,
+    V: Type,
+    Self::Id: CheapClone + core::fmt::Debug,
+  {
+    let first_len = first.encoded_len();
+    let second_len = second.encoded_len();
+    let first_len_u32 = u32::try_from(first_len)
+      .map_err(|_| Either::Right(Error::value_too_large(first_len, u32::MAX as usize)))?;
+
+    self
+      .insert_with(ValueBuilder::new(
+        PAIR_PREFIX_SIZE + first_len + second_len,
+        |buf: &mut VacantBuffer<'_>| {
+          let mut first_buf = buf.split_off(PAIR_PREFIX_SIZE);
+          buf.put_u32_le_unchecked(first_len_u32);
+          let mut second_buf = first_buf.split_off(first_len);
+          encode_generic(first, first_len, &mut first_buf).map_err(|e| match e {
+            Either::Left(e) => Either::Left(Either::Left(e)),
+            Either::Right(e) => Either::Right(e),
+          })?;
+          encode_generic(second, second_len, &mut second_buf).map_err(|e| match e {
+            Either::Left(e) => Either::Left(Either::Right(e)),
+            Either::Right(e) => Either::Right(e),
+          })
+        },
+      ))
+      .map_err(flatten_encode_err)
   }
 
   /// Inserts a value into the log with a builder, the value is built in place.
@@ -101,7 +608,291 @@ pub trait LogWriterExt: LogWriter {
   where
     Self::Id: CheapClone + core::fmt::Debug,
   {
-    insert_in(self, vb)
+    insert_in(self, vb, None, true)
+  }
+
+  /// Inserts a value into the log with a precomputed checksum.
+  ///
+  /// This method is almost the same as the [`insert`](LogWriter::insert) method, the only difference is that
+  /// the checksum of the value will not be recomputed, which is useful when copying values between logs
+  /// using the same checksumer.
+  ///
+  /// The caller must ensure that the `checksum` is computed by the same checksumer of the log,
+  /// otherwise, the value cannot be read back if [`validate_checksum`](crate::options::Options::validate_checksum) is enabled.
+  /// In debug builds, the `checksum` is validated.
+  ///
+  /// The checksum of an encrypted log covers the sealed value, which cannot be computed by the caller,
+  /// so [`Error::InvalidOptions`] is returned if the log is encrypted,
+  /// see `Options::with_cipher`.
+  ///
+  /// ## Example
+  ///
+  /// ```rust
+  /// use valog::{Builder, sync::ValueLog, Log, LogWriterExt};
+  ///
+  /// let log = Builder::new().with_capacity(1024).alloc::<ValueLog>(0).unwrap();
+  /// let checksum = log.checksum(b"Hello, valog!");
+  /// let vp = log.insert_with_checksum(b"Hello, valog!", checksum).unwrap();
+  /// ```
+  #[inline]
+  fn insert_with_checksum(
+    &self,
+    value: &[u8],
+    checksum: u64,
+  ) -> Result<ValuePointer<Self::Id>, Error>
+  where
+    Self::Id: CheapClone + core::fmt::Debug,
+  {
+    if self.options().seal_overhead() != 0 {
+      return Err(Error::invalid_options(
+        "the precomputed checksum cannot cover the encrypted value",
+      ));
+    }
+
+    let vb = ValueBuilder::new(value.len(), |buf: &mut VacantBuffer<'_>| {
+      buf.put_slice_unchecked(value);
+      Ok(())
+    });
+    insert_in::<_, ()>(self, vb, Some(checksum), true).map_err(|e| e.unwrap_right())
+  }
+
+  /// Inserts the values from an iterator into the log, and reports the progress to `on_progress`.
+  ///
+  /// `on_progress` is called after each value is inserted with `(done, total_bytes_written)`, where
+  /// `total_bytes_written` only counts the payloads. Returning [`ControlFlow::Break`](core::ops::ControlFlow::Break)
+  /// stops loading, and the value pointers of the already inserted values are returned.
+  ///
+  /// If inserting a value fails, the value pointers of the already inserted values are returned with the error.
+  ///
+  /// ## Example
+  ///
+  /// ```rust
+  /// use core::ops::ControlFlow;
+  /// use valog::{Builder, sync::ValueLog, LogWriterExt};
+  ///
+  /// let log = Builder::new().with_capacity(1024).alloc::<ValueLog>(0).unwrap();
+  ///
+  /// let ptrs = log
+  ///   .insert_stream([b"foo", b"bar", b"baz"], |done, _| {
+  ///     if done == 2 {
+  ///       ControlFlow::Break(())
+  ///     } else {
+  ///       ControlFlow::Continue(())
+  ///     }
+  ///   })
+  ///   .unwrap();
+  /// assert_eq!(ptrs.len(), 2);
+  /// ```
+  #[cfg(any(feature = "alloc", feature = "std"))]
+  #[cfg_attr(docsrs, doc(cfg(any(feature = "alloc", feature = "std"))))]
+  fn insert_stream<I, F>(
+    &self,
+    values: I,
+    mut on_progress: F,
+  ) -> Result<std::vec::Vec<ValuePointer<Self::Id>>, (std::vec::Vec<ValuePointer<Self::Id>>, Error)>
+  where
+    I: IntoIterator,
+    I::Item: AsRef<[u8]>,
+    F: FnMut(usize, usize) -> core::ops::ControlFlow<()>,
+    Self::Id: CheapClone + core::fmt::Debug,
+  {
+    let values = values.into_iter();
+    let mut ptrs = std::vec::Vec::with_capacity(values.size_hint().0);
+    let mut written = 0;
+
+    for value in values {
+      let value = value.as_ref();
+      match self.insert(value) {
+        Ok(vp) => ptrs.push(vp),
+        Err(e) => return Err((ptrs, e)),
+      }
+
+      written += value.len();
+      if on_progress(ptrs.len(), written).is_break() {
+        break;
+      }
+    }
+
+    Ok(ptrs)
+  }
+
+  /// Copies the values pointed by `ptrs` from `src` into this log, and returns the new value pointers
+  /// in the same order of `ptrs`.
+  ///
+  /// The values are read with [`LogReader::read`] from `src`, so the checksums will be validated
+  /// if `src` enables [`validate_checksum`](crate::options::Options::validate_checksum).
+  /// If `src` and this log use the same checksumer, the stored checksums are reused without recomputing.
+  /// Tombstones are kept as tombstones.
+  ///
+  /// If `src` and this log use the same cipher (see `Options::with_cipher`), the sealed values are
+  /// copied as is, otherwise the values of `src` are decrypted and sealed again by this log.
+  ///
+  /// The inserted values are flushed once at the end if [`sync`](crate::options::Options::sync) is enabled.
+  ///
+  /// ## Safety
+  /// - The `ptrs` must point to valid values in `src`.
+  ///
+  /// ## Example
+  ///
+  /// ```rust
+  /// use valog::{Builder, sync::ValueLog, LogReader, LogWriter, LogWriterExt, Log};
+  ///
+  /// let src = Builder::new().with_capacity(1024).alloc::<ValueLog>(0).unwrap();
+  /// let ptrs = vec![src.insert(b"foo").unwrap(), src.insert(b"bar").unwrap()];
+  ///
+  /// let dst = Builder::new().with_capacity(1024).alloc::<ValueLog>(1).unwrap();
+  /// let new_ptrs = unsafe { dst.merge_from(&src, &ptrs).unwrap() };
+  ///
+  /// let data = unsafe { dst.read(dst.id(), new_ptrs[1].offset(), new_ptrs[1].size()).unwrap() };
+  /// assert_eq!(data, b"bar");
+  /// ```
+  #[cfg(any(feature = "alloc", feature = "std"))]
+  #[cfg_attr(docsrs, doc(cfg(any(feature = "alloc", feature = "std"))))]
+  unsafe fn merge_from<L>(
+    &self,
+    src: &L,
+    ptrs: &[ValuePointer<L::Id>],
+  ) -> Result<std::vec::Vec<ValuePointer<Self::Id>>, Error>
+  where
+    L: LogReader,
+    L::Id: Eq,
+    Self::Id: CheapClone + core::fmt::Debug,
+  {
+    const PROBE: &[u8] = b"valog";
+
+    let src_cks_len = src.options().checksum_len();
+    let cks_len = self.options().checksum_len();
+    let same_cipher = src.options().same_cipher(self.options());
+    // the sealed values are copied as is, which must be written with the checksums of the sealed values.
+    let sealed = same_cipher && self.options().seal_overhead() != 0;
+    // the stored checksums can only be reused if they are not shorter than the checksums of this log.
+    let reuse_checksum = same_cipher
+      && src_cks_len.len() >= cks_len.len()
+      && src.checksum(PROBE) == self.checksum(PROBE);
+    let src_allocator = src.allocator();
+    let mut new_ptrs = std::vec::Vec::with_capacity(ptrs.len());
+    let mut range: Option<(usize, usize)> = None;
+    let mut opened = std::vec::Vec::new();
+
+    for ptr in ptrs {
+      let value = if same_cipher {
+        src.read(ptr.id(), ptr.offset(), ptr.size())?
+      } else {
+        opened.clear();
+        src.read_to_owned(ptr.id(), ptr.offset(), ptr.size(), &mut opened)?;
+        opened.as_slice()
+      };
+      let checksum = if value.is_empty() {
+        None
+      } else if reuse_checksum {
+        let offset = ptr.offset() as usize + value.len();
+        Some(src_cks_len.decode(src_allocator.get_bytes(offset, src_cks_len.len())))
+      } else if sealed {
+        Some(self.checksum(value))
+      } else {
+        None
+      };
+
+      let vb = ValueBuilder::new(value.len(), |buf: &mut VacantBuffer<'_>| {
+        buf.put_slice_unchecked(value);
+        Ok(())
+      });
+      let mut vp = insert_in::<_, ()>(self, vb, checksum, false).map_err(|e| e.unwrap_right())?;
+      if ptr.is_tombstone() {
+        self.allocator().increase_discarded(vp.size());
+        vp = vp.with_tombstone();
+      }
+
+      if vp.size() != 0 {
+        let entry = vp.range(cks_len);
+        let (start, end) = (entry.start as usize, entry.end as usize);
+        range = Some(match range {
+          Some((s, e)) => (s.min(start), e.max(end)),
+          None => (start, end),
+        });
+      }
+      new_ptrs.push(vp);
+    }
+
+    #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+    common::evict_after_scan(src)?;
+
+    #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+    if let Some((start, end)) = range {
+      let allocator = self.allocator();
+      if self.options().sync && allocator.is_ondisk() {
+        flush_entry(self, start, end - start)?;
+      }
+    }
+
+    Ok(new_ptrs)
+  }
+
+  /// Copies the raw entry `offset..offset + entry_len` (the value and its stored checksum) from `src`
+  /// into this log, and returns the new value pointer.
+  ///
+  /// The bytes are copied as is, without recomputing or validating the checksum, so this is the fastest
+  /// way to move the values between the logs when compacting. The checksum length, the checksumer and the
+  /// cipher (see `Options::with_cipher`) of `src` must be the same as the ones of this log,
+  /// otherwise [`Error::IncompatibleLayout`] is returned.
+  /// The entries are not framed, so the tombstone mark is not copied, the returned pointer is a plain value.
+  ///
+  /// ## Example
+  ///
+  /// ```rust
+  /// use valog::{Builder, sync::ValueLog, LogReader, LogWriter, LogWriterExt, Log};
+  ///
+  /// let src = Builder::new().with_capacity(1024).alloc::<ValueLog>(0).unwrap();
+  /// let vp = src.insert(b"Hello, valog!").unwrap();
+  ///
+  /// let dst = Builder::new().with_capacity(1024).alloc::<ValueLog>(1).unwrap();
+  /// let entry_len = vp.size() + src.options().checksum_len().len() as u32;
+  /// let new_vp = dst.copy_raw_from(&src, vp.offset(), entry_len).unwrap();
+  ///
+  /// let data = unsafe { dst.read(dst.id(), new_vp.offset(), new_vp.size()).unwrap() };
+  /// assert_eq!(data, b"Hello, valog!");
+  /// ```
+  fn copy_raw_from<L>(
+    &self,
+    src: &L,
+    offset: u32,
+    entry_len: u32,
+  ) -> Result<ValuePointer<Self::Id>, Error>
+  where
+    L: LogReader,
+    Self::Id: CheapClone + core::fmt::Debug,
+  {
+    const PROBE: &[u8] = b"valog";
+
+    let cks_len = self.options().checksum_len();
+    if src.options().checksum_len() != cks_len
+      || src.checksum(PROBE) != self.checksum(PROBE)
+      || !src.options().same_cipher(self.options())
+    {
+      return Err(Error::IncompatibleLayout);
+    }
+
+    if entry_len == 0 {
+      return Ok(ValuePointer::new(self.id().cheap_clone(), 0, 0));
+    }
+
+    let (data_offset, allocated) =
+      readable_bounds(src.allocator(), src.options(), src.range_cursor());
+    let start = offset as usize;
+    let end = start + entry_len as usize;
+    if entry_len < cks_len.len() as u32 || start < data_offset || end > allocated {
+      return Err(Error::out_of_bounds(
+        offset,
+        entry_len,
+        data_offset as u32,
+        allocated as u32,
+      ));
+    }
+
+    // Safety: we have checked the bounds
+    let entry = unsafe { src.allocator().get_bytes(start, entry_len as usize) };
+    let (value, checksum) = entry.split_at(entry.len() - cks_len.len());
+    insert_entry_in(self, value, Some(checksum))
   }
 
   /// Inserts a generic value into the log.
@@ -127,10 +918,12 @@ pub trait LogWriterExt: LogWriter {
     Self::Id: CheapClone + core::fmt::Debug,
   {
     let encoded_len = value.encoded_len();
-    self.insert_tombstone_with(ValueBuilder::new(
-      encoded_len,
-      |buf: &mut VacantBuffer<'_>| value.encode_to_buffer(buf).map(|_| ()),
-    ))
+    self
+      .insert_tombstone_with(ValueBuilder::new(
+        encoded_len,
+        |buf: &mut VacantBuffer<'_>| encode_generic(value, encoded_len, buf),
+      ))
+      .map_err(flatten_encode_err)
   }
 
   /// Inserts a value into the log with a builder, the value is built in place.
@@ -158,9 +951,8 @@ pub trait LogWriterExt: LogWriter {
   where
     Self::Id: CheapClone + core::fmt::Debug,
   {
-    let encoded_len = vb.size;
-    insert_in(self, vb).map(|vp| {
-      self.allocator().increase_discarded(encoded_len as u32);
+    insert_in(self, vb, None, true).map(|vp| {
+      self.allocator().increase_discarded(vp.size());
       vp.with_tombstone()
     })
   }
@@ -168,10 +960,678 @@ pub trait LogWriterExt: LogWriter {
 
 impl<L> LogWriterExt for L where L: LogWriter {}
 
+/// Returns the size of the entry (value and checksum) and the size needs to be allocated for the entry.
+#[inline]
+pub(super) fn entry_size(opts: &Options, value_len: usize) -> Result<(usize, usize), Error> {
+  let maximum = opts.max_value_size;
+  let len = value_len + opts.checksum_len().len();
+
+  if len > maximum as usize {
+    return Err(Error::value_too_large(len, maximum as usize));
+  }
+
+  // reserve extra `align - 1` bytes, so that the value can always start at an aligned offset.
+  let total = len + opts.value_alignment - 1;
+  if total > u32::MAX as usize {
+    return Err(Error::value_too_large(total, u32::MAX as usize));
+  }
+
+  Ok((len, total))
+}
+
+/// Flushes the synced entry at `offset..offset + len` to the disk by the
+/// [`SyncStrategy`](crate::options::SyncStrategy) of the log, the header is flushed
+/// only if required by [`HeaderSync`](crate::options::HeaderSync), together with the
+/// committed length recorded up to the end of the entry.
+#[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+pub(super) fn flush_entry<L: Log + ?Sized>(
+  l: &L,
+  offset: usize,
+  len: usize,
+) -> std::io::Result<()> {
+  use crate::options::SyncStrategy;
+
+  let allocator = l.allocator();
+  let opts = l.options();
+  let flush_header = opts.header_sync.should_flush(offset, len);
+  let committed = flush_header && commit(l, offset + len);
+  let file = match (opts.sync_strategy, l.sync_file()) {
+    (SyncStrategy::SyncFileRange | SyncStrategy::Fdatasync, Some(file)) => file,
+    _ if flush_header => {
+      allocator.flush_header_and_range(offset, len)?;
+      // the header of the value log is at the start of the memory map, it is flushed after the header of
+      // the allocator if they are not on the same page, so that the committed length on the disk never
+      // exceeds the allocated length.
+      if committed && allocator.data_offset() > allocator.page_size() {
+        allocator.flush_range(0, crate::options::HEADER_SIZE)?;
+      }
+      return Ok(());
+    }
+    _ => return allocator.flush_range(offset, len),
+  };
+
+  #[cfg(any(target_os = "linux", target_os = "android"))]
+  if opts.sync_strategy == SyncStrategy::SyncFileRange {
+    // the memory map starts at `opts.offset` of the file, and the headers of the value log and
+    // the allocator are placed before the data offset.
+    if flush_header {
+      sync_file_range(file, opts.offset, allocator.data_offset())?;
+    }
+    return sync_file_range(file, opts.offset + offset as u64, len);
+  }
+
+  file.sync_data()
+}
+
+/// Returns the lock of the header if the log records the committed length, which is only recorded by the
+/// writable file backed logs which own the header of the layout version `1`.
+#[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+pub(super) fn committed_header_lock<L: Log + ?Sized>(l: &L) -> Option<&std::sync::Mutex<()>> {
+  let allocator = l.allocator();
+  let opts = l.options();
+  l.header_lock().filter(|_| {
+    allocator.is_ondisk()
+      && !allocator.read_only()
+      && opts.sub_range().is_none()
+      && !opts.is_legacy()
+  })
+}
+
+/// Records `end` as the committed length in the header of the log, if it is beyond the recorded one.
+///
+/// Returns `false` if the log does not record the committed length.
+#[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+pub(super) fn commit<L: Log + ?Sized>(l: &L, end: usize) -> bool {
+  let Some(lock) = committed_header_lock(l) else {
+    return false;
+  };
+
+  let _guard = lock
+    .lock()
+    .unwrap_or_else(std::sync::PoisonError::into_inner);
+  let allocator = l.allocator();
+  // Safety: the header is only written under the header lock.
+  let header = unsafe { allocator.reserved_slice_mut() };
+  if end as u32 > crate::options::committed(header) {
+    crate::options::write_committed(header, end as u32);
+  }
+  true
+}
+
+/// Writes the dirty pages of the file in `offset..offset + len` to the disk, and waits for the completion.
+#[cfg(all(
+  feature = "memmap",
+  not(target_family = "wasm"),
+  any(target_os = "linux", target_os = "android")
+))]
+fn sync_file_range(file: &std::fs::File, offset: u64, len: usize) -> std::io::Result<()> {
+  use std::os::fd::AsRawFd;
+
+  // waits before writing as well, so that the pages under writeback before this write are not skipped.
+  let flags = libc::SYNC_FILE_RANGE_WAIT_BEFORE
+    | libc::SYNC_FILE_RANGE_WRITE
+    | libc::SYNC_FILE_RANGE_WAIT_AFTER;
+  // SAFETY: the file descriptor is valid as long as `file` is alive.
+  let ret = unsafe { libc::sync_file_range(file.as_raw_fd(), offset as _, len as _, flags) };
+  if ret == 0 {
+    Ok(())
+  } else {
+    Err(std::io::Error::last_os_error())
+  }
+}
+
+/// Replaces the anonymous memory map of the log with a larger one, which has at least
+/// `additional` bytes remaining, and copies the old contents to it.
+#[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+fn grow_anon<L: LogWriter + ?Sized>(l: &mut L, additional: usize) -> Result<(), Error> {
+  let old = l.allocator();
+  let cap = old.capacity() as u64;
+  let new_cap = (cap * 2).max(cap + additional as u64);
+  if new_cap > u32::MAX as u64 {
+    return Err(Error::insufficient_space(
+      additional as u32,
+      old.remaining() as u32,
+    ));
+  }
+
+  let opts = l
+    .options()
+    .with_capacity(new_cap as u32)
+    .with_page_aligned_capacity();
+  let arena = opts.to_arena_options().map_anon::<L::Allocator>()?;
+
+  // SAFETY: the new arena is not shared yet, and has the same layout of the reserved slice.
+  unsafe {
+    arena
+      .reserved_slice_mut()
+      .copy_from_slice(old.reserved_slice());
+  }
+
+  let data_offset = old.data_offset();
+  let data_len = old.allocated() - data_offset;
+  if data_len > 0 {
+    let mut buf = arena.alloc_bytes(data_len as u32).map_err(Error::from)?;
+    debug_assert_eq!(buf.offset(), data_offset);
+    // SAFETY: `buf` is allocated with `data_len` bytes.
+    unsafe {
+      buf.put_slice_unchecked(&old.allocated_memory()[data_offset..]);
+      buf.detach();
+    }
+  }
+  arena.increase_discarded(old.discarded());
+  crate::options::lock_all(&arena, &opts)?;
+
+  let (allocator, options) = l.allocator_and_options_mut();
+  *allocator = arena;
+  *options = opts;
+  Ok(())
+}
+
+/// Allocates `size` bytes for the entries of the log.
+///
+/// A log over a sub-range of the allocator has claimed its whole range from the allocator when it is built,
+/// see [`Builder::with_external_arena`](crate::Builder::with_external_arena), so its entries are allocated
+/// by its own cursor in the range, which does not affect the logs over the other sub-ranges.
+pub(super) fn alloc_entry<L: Log + ?Sized>(
+  l: &L,
+  size: usize,
+) -> Result<EntryBuf<'_, L::Allocator>, Error> {
+  use core::sync::atomic::Ordering;
+
+  let allocator = l.allocator();
+  if allocator.read_only() {
+    return Err(Error::read_only());
+  }
+
+  let (Some((_, end)), Some(cursor)) = (l.options().sub_range(), l.range_cursor()) else {
+    let mut buf = allocator.alloc_bytes(size as u32).map_err(Error::from)?;
+    // Safety: the space is given back by the `EntryBuf` if it is not used.
+    unsafe {
+      buf.detach();
+    }
+    return Ok(EntryBuf {
+      allocator,
+      cursor: None,
+      offset: buf.offset(),
+      memory_offset: buf.buffer_offset(),
+      memory_size: buf.buffer_capacity(),
+      detached: false,
+    });
+  };
+
+  // the range is not claimed if the options are copied from a log over a sub-range to a new allocator.
+  let claimed = (allocator.allocated() as u32).min(end);
+  cursor
+    .fetch_update(Ordering::AcqRel, Ordering::Acquire, |cur| {
+      (cur as u64 + size as u64 <= claimed as u64).then_some(cur + size as u32)
+    })
+    .map(|offset| EntryBuf {
+      allocator,
+      cursor: Some(cursor),
+      offset: offset as usize,
+      memory_offset: offset as usize,
+      memory_size: size,
+      detached: false,
+    })
+    .map_err(|cur| Error::insufficient_space(size as u32, claimed.saturating_sub(cur)))
+}
+
+/// The space allocated by [`alloc_entry`], which is given back to the log if it is dropped without being detached.
+pub(super) struct EntryBuf<'a, A: Allocator> {
+  allocator: &'a A,
+  cursor: Option<&'a AtomicU32>,
+  offset: usize,
+  memory_offset: usize,
+  memory_size: usize,
+  detached: bool,
+}
+
+impl<A: Allocator> EntryBuf<'_, A> {
+  /// Returns the offset of the buffer in the allocator.
+  #[inline]
+  pub(super) const fn offset(&self) -> usize {
+    self.offset
+  }
+
+  /// Returns the mutable pointer to the buffer.
+  #[inline]
+  pub(super) fn as_mut_ptr(&mut self) -> *mut u8 {
+    // SAFETY: the buffer is allocated in the bounds of the writable allocator.
+    unsafe { self.allocator.get_pointer_mut(self.offset) }
+  }
+
+  /// Keeps the space of the buffer in the log.
+  ///
+  /// ## Safety
+  /// - The space must be used, or given back by [`dealloc_entry`].
+  #[inline]
+  pub(super) unsafe fn detach(&mut self) {
+    self.detached = true;
+  }
+}
+
+impl<A: Allocator> Drop for EntryBuf<'_, A> {
+  #[inline]
+  fn drop(&mut self) {
+    if !self.detached {
+      // SAFETY: the space is not detached, so it is never used.
+      unsafe {
+        dealloc_entry(
+          self.allocator,
+          self.cursor,
+          self.memory_offset,
+          self.memory_size,
+        );
+      }
+    }
+  }
+}
+
+/// Gives the space `offset..offset + size` allocated by [`alloc_entry`] back to the log.
+///
+/// The space in a sub-range can only be given back if nothing is allocated after it, otherwise it is discarded.
+///
+/// ## Safety
+/// - The space must not be used.
+pub(super) unsafe fn dealloc_entry<A: Allocator>(
+  allocator: &A,
+  cursor: Option<&AtomicU32>,
+  offset: usize,
+  size: usize,
+) {
+  use core::sync::atomic::Ordering;
+
+  match cursor {
+    Some(cursor) => {
+      let end = (offset + size) as u32;
+      if cursor
+        .compare_exchange(end, offset as u32, Ordering::AcqRel, Ordering::Acquire)
+        .is_err()
+      {
+        allocator.increase_discarded(size as u32);
+      }
+    }
+    None => {
+      allocator.dealloc(offset as u32, size as u32);
+    }
+  }
+}
+
+/// Returns the number of bytes which can still be allocated by [`alloc_entry`], without the freelist.
+pub(super) fn available_space<L: Log + ?Sized>(l: &L) -> usize {
+  let allocator = l.allocator();
+  match (l.options().sub_range(), l.range_cursor()) {
+    (Some((_, end)), Some(cursor)) => {
+      let claimed = allocator.allocated().min(end as usize);
+      claimed.saturating_sub(cursor.load(core::sync::atomic::Ordering::Acquire) as usize)
+    }
+    _ => allocator.remaining(),
+  }
+}
+
+/// Reserves `n` entries from the entry counter of the log, which are given back if the guard is dropped
+/// without being committed.
+pub(super) fn reserve_entries<L: Log + ?Sized>(l: &L, n: u32) -> Result<EntryGuard<'_>, Error> {
+  use core::sync::atomic::Ordering;
+
+  let Some(counter) = l.entry_counter() else {
+    return Ok(EntryGuard { counter: None, n });
+  };
+
+  let maximum = l.options().max_entries;
+  counter
+    .fetch_update(Ordering::AcqRel, Ordering::Acquire, |cur| {
+      cur.checked_add(n).filter(|total| *total <= maximum)
+    })
+    .map_err(|_| Error::max_entries_reached(maximum))?;
+  Ok(EntryGuard {
+    counter: Some(counter),
+    n,
+  })
+}
+
+/// The entries reserved by [`reserve_entries`].
+pub(super) struct EntryGuard<'a> {
+  counter: Option<&'a core::sync::atomic::AtomicU32>,
+  n: u32,
+}
+
+impl EntryGuard<'_> {
+  /// Keeps the reserved entries.
+  #[inline]
+  pub(super) fn commit(mut self) {
+    self.counter = None;
+  }
+}
+
+impl Drop for EntryGuard<'_> {
+  #[inline]
+  fn drop(&mut self) {
+    if let Some(counter) = self.counter {
+      counter.fetch_sub(self.n, core::sync::atomic::Ordering::AcqRel);
+    }
+  }
+}
+
+/// Encodes a generic value into the buffer, and checks the number of bytes written against `encoded_len`.
+#[inline]
+fn encode_generic<T: Type + ?Sized>(
+  value: &T,
+  encoded_len: usize,
+  buf: &mut VacantBuffer<'_>,
+) -> Result<(), Either<T::Error, Error>> {
+  let written = value.encode_to_buffer(buf).map_err(Either::Left)?;
+  if written != encoded_len {
+    return Err(Either::Right(Error::encode_length_mismatch(
+      encoded_len,
+      written,
+    )));
+  }
+  Ok(())
+}
+
+/// The size of the length prefix of the first value in an entry inserted by [`LogWriterExt::insert_pair`].
+pub(super) const PAIR_PREFIX_SIZE: usize = core::mem::size_of::<u32>();
+
+#[inline]
+fn flatten_encode_err<E>(e: Either<Either<E, Error>, Error>) -> Either<E, Error> {
+  match e {
+    Either::Left(e) => e,
+    Either::Right(e) => Either::Right(e),
+  }
+}
+
+/// Inserts a plain slice into the log, which is the same as [`insert_in`] with a builder copying the slice,
+/// but copies the slice directly without the [`VacantBuffer`] setup.
+#[inline]
+fn insert_slice_in<L: LogWriter + ?Sized>(l: &L, value: &[u8]) -> Result<ValuePointer<L::Id>, Error>
+where
+  L::Id: CheapClone + core::fmt::Debug,
+{
+  insert_entry_in(l, value, None)
+}
+
+/// Inserts a value with the stored checksum bytes into the log, the checksum is calculated if `checksum` is `None`.
+fn insert_entry_in<L: LogWriter + ?Sized>(
+  l: &L,
+  value: &[u8],
+  checksum: Option<&[u8]>,
+) -> Result<ValuePointer<L::Id>, Error>
+where
+  L::Id: CheapClone + core::fmt::Debug,
+{
+  let opts = l.options();
+  // the raw entries with the given checksum are copied as is, so they cannot be truncated.
+  let (value, truncated) = match checksum {
+    Some(_) => (value, false),
+    None => truncate_oversize(opts, value),
+  };
+
+  if value.is_empty() {
+    return Ok(ValuePointer::new(l.id().cheap_clone(), 0, 0).with_truncated(truncated));
+  }
+
+  store_entry_in(l, value, checksum, truncated)
+}
+
+/// Stores the value with the checksum bytes into a new entry, even if the value is empty.
+fn store_entry_in<L: LogWriter + ?Sized>(
+  l: &L,
+  value: &[u8],
+  checksum: Option<&[u8]>,
+  truncated: bool,
+) -> Result<ValuePointer<L::Id>, Error>
+where
+  L::Id: CheapClone + core::fmt::Debug,
+{
+  let opts = l.options();
+
+  #[cfg(feature = "tracing")]
+  let _span = tracing::trace_span!(
+    "valog.insert",
+    size = value.len(),
+    offset = tracing::field::Empty,
+    sync = false
+  )
+  .entered();
+
+  let cks_len = opts.checksum_len();
+  let align = opts.value_alignment;
+  // the raw entries are copied as is, the other values are sealed if the log is encrypted.
+  let (stored_len, nonce_size) = match checksum {
+    Some(_) => (value.len(), 0),
+    None => (value.len() + opts.seal_overhead(), opts.nonce_size()),
+  };
+  #[cfg_attr(
+    not(all(feature = "memmap", not(target_family = "wasm"))),
+    allow(unused_variables)
+  )]
+  let (len, total) = entry_size(opts, stored_len)?;
+
+  let entries = reserve_entries(l, 1)?;
+  let allocator = l.allocator();
+  let mut buf = alloc_entry(l, total)?;
+
+  let buf_offset = buf.offset();
+  let padding = buf_offset.next_multiple_of(align) - buf_offset;
+  let begin_offset = buf_offset + padding;
+  // the raw entries are copied as is, so only the computed checksum can be re-validated.
+  #[cfg(feature = "paranoid")]
+  let validate = checksum.is_none();
+
+  // SAFETY: `buf` is allocated with the exact size of `padding + stored_len + checksum length` at least.
+  unsafe {
+    let ptr = buf.as_mut_ptr();
+    core::ptr::write_bytes(ptr, 0, padding);
+    core::ptr::copy_nonoverlapping(value.as_ptr(), ptr.add(padding + nonce_size), value.len());
+    let digest;
+    let checksum = match checksum {
+      Some(checksum) => checksum,
+      None => {
+        seal_in_place(opts, ptr.add(padding), begin_offset, stored_len);
+        digest = l
+          .checksum(core::slice::from_raw_parts(ptr.add(padding), stored_len))
+          .to_le_bytes();
+        &digest[..cks_len.len()]
+      }
+    };
+    debug_assert_eq!(checksum.len(), cks_len.len());
+    core::ptr::copy_nonoverlapping(
+      checksum.as_ptr(),
+      ptr.add(padding + stored_len),
+      cks_len.len(),
+    );
+  }
+
+  #[cfg(feature = "tracing")]
+  _span.record("offset", begin_offset);
+
+  #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+  if opts.sync && allocator.is_ondisk() {
+    flush_entry(l, begin_offset, len)?;
+
+    #[cfg(feature = "tracing")]
+    _span.record("sync", true);
+  }
+
+  if align > 1 {
+    allocator.increase_discarded((align - 1) as u32);
+  }
+
+  // Safety: no need to drop
+  unsafe {
+    buf.detach();
+  }
+  entries.commit();
+
+  #[cfg(feature = "paranoid")]
+  paranoid_check(l, begin_offset, stored_len, validate);
+
+  Ok(
+    ValuePointer::new(l.id().cheap_clone(), begin_offset as u32, stored_len as u32)
+      .with_truncated(truncated),
+  )
+}
+
+/// Inserts a value at `offset`, the bytes between the end of the written entries and `offset` are discarded.
+fn insert_at_in<L: LogWriter + ?Sized>(
+  l: &L,
+  offset: u32,
+  value: &[u8],
+) -> Result<ValuePointer<L::Id>, Error>
+where
+  L::Id: CheapClone + core::fmt::Debug,
+{
+  let opts = l.options();
+  let (value, truncated) = truncate_oversize(opts, value);
+  if value.is_empty() {
+    return Ok(ValuePointer::new(l.id().cheap_clone(), 0, 0).with_truncated(truncated));
+  }
+
+  let cks_len = opts.checksum_len();
+  let stored_len = value.len() + opts.seal_overhead();
+  #[cfg_attr(
+    not(all(feature = "memmap", not(target_family = "wasm"))),
+    allow(unused_variables)
+  )]
+  let (len, _) = entry_size(opts, stored_len)?;
+
+  let allocator = l.allocator();
+  if allocator.read_only() {
+    return Err(Error::read_only());
+  }
+
+  let (start, end) = match opts.sub_range() {
+    Some((start, end)) => (start.max(allocator.data_offset() as u32), end),
+    None => (allocator.data_offset() as u32, allocator.capacity() as u32),
+  };
+  if offset < start {
+    return Err(Error::out_of_bounds(offset, len as u32, start, end));
+  }
+  if offset as u64 + len as u64 > end as u64 {
+    return Err(Error::insufficient_space(
+      len as u32,
+      end.saturating_sub(offset),
+    ));
+  }
+
+  let allocated = match l.range_cursor() {
+    Some(cursor) => cursor.load(core::sync::atomic::Ordering::Acquire),
+    None => allocator.allocated() as u32,
+  };
+  if offset < allocated {
+    return Err(Error::overlap(offset, allocated));
+  }
+
+  let entries = reserve_entries(l, 1)?;
+  let size = (offset - allocated) as usize + len;
+  let mut buf = alloc_entry(l, size)?;
+
+  // the buffer may not start at `allocated`, e.g. the cursor is moved by a concurrent insert.
+  let buf_offset = buf.offset();
+  let offset = offset as usize;
+  if offset < buf_offset || offset + len > buf_offset + size {
+    // dropping the buffer gives the space back to the allocator
+    return Err(Error::overlap(offset as u32, buf_offset as u32));
+  }
+
+  let gap = offset - buf_offset;
+  // SAFETY: `offset..offset + len` is in the bounds of `buf`, which is checked above.
+  unsafe {
+    let ptr = buf.as_mut_ptr();
+    core::ptr::write_bytes(ptr, 0, gap);
+    core::ptr::copy_nonoverlapping(
+      value.as_ptr(),
+      ptr.add(gap + opts.nonce_size()),
+      value.len(),
+    );
+    seal_in_place(opts, ptr.add(gap), offset, stored_len);
+    let checksum = l
+      .checksum(core::slice::from_raw_parts(ptr.add(gap), stored_len))
+      .to_le_bytes();
+    core::ptr::copy_nonoverlapping(checksum.as_ptr(), ptr.add(gap + stored_len), cks_len.len());
+  }
+
+  #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+  if opts.sync && allocator.is_ondisk() {
+    flush_entry(l, offset, len)?;
+  }
+
+  let discarded = size - len;
+  if discarded > 0 {
+    allocator.increase_discarded(discarded as u32);
+  }
+
+  // Safety: no need to drop
+  unsafe {
+    buf.detach();
+  }
+  entries.commit();
+
+  #[cfg(feature = "paranoid")]
+  paranoid_check(l, offset, stored_len, true);
+
+  Ok(
+    ValuePointer::new(l.id().cheap_clone(), offset as u32, stored_len as u32)
+      .with_truncated(truncated),
+  )
+}
+
+/// Seals the value of `stored_len` bytes at `ptr`, which is placed at `offset` of the log, if the log is encrypted.
+///
+/// The value must be written after the nonce, see `Options::with_cipher`.
+///
+/// ## Safety
+/// - `ptr..ptr + stored_len` must be valid for writes.
+#[inline]
+pub(super) unsafe fn seal_in_place(opts: &Options, ptr: *mut u8, offset: usize, stored_len: usize) {
+  #[cfg(feature = "encryption")]
+  if let Some(cipher) = opts.cipher() {
+    crate::cipher::seal(
+      &cipher,
+      opts.salt(),
+      offset as u32,
+      core::slice::from_raw_parts_mut(ptr, stored_len),
+    );
+  }
+
+  #[cfg(not(feature = "encryption"))]
+  let _ = (opts, ptr, offset, stored_len);
+}
+
+/// Truncates the value to the maximum value size if the log is configured with [`OversizePolicy::Truncate`],
+/// returns the value and whether it is truncated.
+#[inline]
+pub(super) fn truncate_oversize<'a>(opts: &Options, value: &'a [u8]) -> (&'a [u8], bool) {
+  let len = truncated_len(opts, value.len());
+  (&value[..len], len < value.len())
+}
+
+/// Returns the length of a value of `len` bytes after it is truncated by [`truncate_oversize`].
+#[inline]
+fn truncated_len(opts: &Options, len: usize) -> usize {
+  // the maximum value size includes the checksum of the entry, and the nonce and the tag of an encrypted log.
+  let maximum =
+    (opts.max_value_size as usize).saturating_sub(opts.checksum_len().len() + opts.seal_overhead());
+  match opts.oversize_policy {
+    OversizePolicy::Truncate => len.min(maximum),
+    OversizePolicy::Error => len,
+  }
+}
+
 /// Inserts a value into the log with a builder, the value is built in place.
+///
+/// If `checksum` is `Some`, the given checksum will be written without recomputing.
+/// If `flush` is `false`, the caller is responsible for flushing the inserted value.
 fn insert_in<L: LogWriter + ?Sized, E>(
   l: &L,
   vb: ValueBuilder<impl FnOnce(&mut VacantBuffer<'_>) -> Result<(), E>>,
+  checksum: Option<u64>,
+  #[cfg_attr(
+    not(all(feature = "memmap", not(target_family = "wasm"))),
+    allow(unused_variables)
+  )]
+  flush: bool,
 ) -> Result<ValuePointer<L::Id>, Either<E, Error>>
 where
   L::Id: CheapClone + core::fmt::Debug,
@@ -180,51 +1640,126 @@ where
     return Ok(ValuePointer::new(l.id().cheap_clone(), 0, 0));
   }
 
+  #[cfg(feature = "tracing")]
+  let _span = tracing::trace_span!(
+    "valog.insert",
+    size = vb.size,
+    offset = tracing::field::Empty,
+    sync = false
+  )
+  .entered();
+
   let opts = l.options();
-  let maximum = opts.max_value_size;
   let (value_len, builder) = vb.into_components();
-  let len = value_len + CHECKSUM_LEN;
-
-  if len > maximum as usize {
-    return Err(Either::Right(Error::value_too_large(len, maximum as usize)));
-  }
+  let cks_len = opts.checksum_len();
+  let align = opts.value_alignment;
+  // the values with the precomputed checksums are copied as is, the other values are sealed if the log is encrypted.
+  let (stored_len, nonce_size) = match checksum {
+    Some(_) => (value_len, 0),
+    None => (value_len + opts.seal_overhead(), opts.nonce_size()),
+  };
+  #[cfg_attr(
+    not(all(feature = "memmap", not(target_family = "wasm"))),
+    allow(unused_variables)
+  )]
+  let (len, total) = entry_size(opts, stored_len).map_err(Either::Right)?;
 
+  let entries = reserve_entries(l, 1).map_err(Either::Right)?;
   let allocator = l.allocator();
-  let mut buf = allocator
-    .alloc_bytes(len as u32)
-    .map_err(|e| Either::Right(Error::from_insufficient_space(e)))?;
+  let mut buf = alloc_entry(l, total).map_err(Either::Right)?;
 
-  let begin_offset = buf.offset();
-  buf.set_len(value_len);
+  let buf_offset = buf.offset();
+  let padding = buf_offset.next_multiple_of(align) - buf_offset;
+  let begin_offset = buf_offset + padding;
 
-  // SAFETY: `buf` is allocated with the exact size of `value.len() + CHECKSUM_LEN`.
+  // SAFETY: `buf` is allocated with the exact size of `padding + stored_len + checksum length` at least.
   unsafe {
-    let ptr = NonNull::new_unchecked(buf.as_mut_ptr());
-    let mut vacant_buf = VacantBuffer::new(value_len, ptr);
+    let ptr = buf.as_mut_ptr();
+    core::ptr::write_bytes(ptr, 0, padding);
+    let mut vacant_buf = VacantBuffer::new(
+      value_len,
+      NonNull::new_unchecked(ptr.add(padding + nonce_size)),
+    );
     builder(&mut vacant_buf).map_err(Either::Left)?;
-    let checksum = l.checksum(&buf);
-    buf.put_u64_le_unchecked(checksum);
+    let value = core::slice::from_raw_parts(ptr.add(padding), stored_len);
+    let checksum = match checksum {
+      Some(checksum) => {
+        debug_assert_eq!(
+          cks_len.truncate(checksum),
+          cks_len.truncate(l.checksum(value)),
+          "the precomputed checksum does not match the value"
+        );
+        checksum
+      }
+      None => {
+        seal_in_place(opts, ptr.add(padding), begin_offset, stored_len);
+        l.checksum(core::slice::from_raw_parts(ptr.add(padding), stored_len))
+      }
+    };
+    core::ptr::copy_nonoverlapping(
+      checksum.to_le_bytes().as_ptr(),
+      ptr.add(padding + stored_len),
+      cks_len.len(),
+    );
   }
 
+  #[cfg(feature = "tracing")]
+  _span.record("offset", begin_offset);
+
   #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
-  if opts.sync && allocator.is_ondisk() {
-    allocator
-      .flush_header_and_range(begin_offset, len)
-      .map_err(|e| Either::Right(e.into()))?;
+  if flush && opts.sync && allocator.is_ondisk() {
+    flush_entry(l, begin_offset, len).map_err(|e| Either::Right(e.into()))?;
+
+    #[cfg(feature = "tracing")]
+    _span.record("sync", true);
+  }
+
+  if align > 1 {
+    allocator.increase_discarded((align - 1) as u32);
   }
 
   // Safety: no need to drop
   unsafe {
     buf.detach();
   }
+  entries.commit();
+
+  #[cfg(feature = "paranoid")]
+  paranoid_check(l, begin_offset, stored_len, true);
 
   Ok(ValuePointer::new(
     l.id().cheap_clone(),
     begin_offset as u32,
-    value_len as u32,
+    stored_len as u32,
   ))
 }
 
+/// Re-reads the value just inserted at `offset..offset + len`, and panics if it is out of the allocated
+/// memory or its checksum does not match when `validate` is `true`, so that a layout bug fails loudly
+/// at the insertion.
+#[cfg(feature = "paranoid")]
+fn paranoid_check<L: Log + ?Sized>(l: &L, offset: usize, len: usize, validate: bool) {
+  let allocator = l.allocator();
+  let cks_len = l.options().checksum_len();
+  let (data_offset, allocated) = (allocator.data_offset(), allocator.allocated());
+  assert!(
+    offset >= data_offset && offset + len + cks_len.len() <= allocated,
+    "paranoid: the inserted value at {offset} with {len} bytes is out of the allocated memory {data_offset}..{allocated}",
+  );
+
+  if !validate {
+    return;
+  }
+
+  // SAFETY: the bounds are checked above.
+  let buf = unsafe { allocator.get_bytes(offset, len + cks_len.len()) };
+  assert_eq!(
+    cks_len.decode(&buf[len..]),
+    cks_len.truncate(l.checksum(&buf[..len])),
+    "paranoid: the inserted value at {offset} with {len} bytes cannot be validated by its checksum",
+  );
+}
+
 /// Generic log writer abstraction.
 pub trait GenericLogWriter: Log {
   /// The generic type stored in the log.
@@ -1,39 +1,337 @@
 pub use rarena_allocator::Freelist;
 use rarena_allocator::Options as ArenaOptions;
 
-pub(super) const CURRENT_VERSION: u16 = 0;
+pub(super) const CURRENT_VERSION: u16 = 1;
 
 pub(super) const MAGIC_TEXT: [u8; 6] = *b"valog!";
 pub(super) const MAGIC_TEXT_SIZE: usize = MAGIC_TEXT.len();
 pub(super) const MAGIC_VERSION_SIZE: usize = core::mem::size_of::<u16>();
-pub(super) const HEADER_SIZE: usize = MAGIC_TEXT_SIZE + MAGIC_VERSION_SIZE;
+pub(super) const VERSION_OFFSET: usize = MAGIC_TEXT_SIZE + MAGIC_VERSION_SIZE;
+pub(super) const VERSION_SIZE: usize = core::mem::size_of::<u16>();
+pub(super) const CHECKSUM_LEN_OFFSET: usize = VERSION_OFFSET + VERSION_SIZE;
+pub(super) const CHECKSUM_LEN_SIZE: usize = 1;
+pub(super) const CHECKSUM_ALGO_OFFSET: usize = CHECKSUM_LEN_OFFSET + CHECKSUM_LEN_SIZE;
+pub(super) const CHECKSUM_ALGO_SIZE: usize = 1;
+pub(super) const ID_WIDTH_OFFSET: usize = CHECKSUM_ALGO_OFFSET + CHECKSUM_ALGO_SIZE;
+pub(super) const ID_WIDTH_SIZE: usize = 1;
+pub(super) const FREELIST_OFFSET: usize = ID_WIDTH_OFFSET + ID_WIDTH_SIZE;
+pub(super) const FREELIST_SIZE: usize = 1;
+pub(super) const CIPHER_OFFSET: usize = FREELIST_OFFSET + FREELIST_SIZE;
+pub(super) const CIPHER_SIZE: usize = 1;
+pub(super) const RESERVED_OFFSET: usize = CIPHER_OFFSET + CIPHER_SIZE;
+pub(super) const RESERVED_SIZE: usize = core::mem::size_of::<u32>();
+pub(super) const DATA_OFFSET_OFFSET: usize = RESERVED_OFFSET + RESERVED_SIZE;
+pub(super) const DATA_OFFSET_SIZE: usize = core::mem::size_of::<u32>();
+pub(super) const COMMITTED_OFFSET: usize = DATA_OFFSET_OFFSET + DATA_OFFSET_SIZE;
+pub(super) const COMMITTED_SIZE: usize = core::mem::size_of::<u32>();
+pub(super) const FOOTER_OFFSET_OFFSET: usize = COMMITTED_OFFSET + COMMITTED_SIZE;
+pub(super) const FOOTER_OFFSET_SIZE: usize = core::mem::size_of::<u32>();
+pub(super) const HEADER_CHECKSUM_OFFSET: usize = FOOTER_OFFSET_OFFSET + FOOTER_OFFSET_SIZE;
+pub(super) const HEADER_CHECKSUM_SIZE: usize = core::mem::size_of::<u32>();
+pub(super) const HEADER_SIZE: usize = HEADER_CHECKSUM_OFFSET + HEADER_CHECKSUM_SIZE;
+/// The size of the header of the version `0` layout, which only holds the magic text and the magic version.
+pub(super) const LEGACY_HEADER_SIZE: usize = MAGIC_TEXT_SIZE + MAGIC_VERSION_SIZE;
+/// The salt of the nonces and the key check of an encrypted log follow the header.
+#[cfg(feature = "encryption")]
+pub(super) const SALT_OFFSET: usize = HEADER_SIZE;
+#[cfg(feature = "encryption")]
+pub(super) const SALT_SIZE: usize = core::mem::size_of::<u64>();
+#[cfg(feature = "encryption")]
+pub(super) const KEY_CHECK_OFFSET: usize = SALT_OFFSET + SALT_SIZE;
+#[cfg(feature = "encryption")]
+pub(super) const CIPHER_HEADER_SIZE: usize = SALT_SIZE + crate::cipher::KEY_CHECK_SIZE;
+
+/// The maximum number of reserved regions can be configured by [`Options::with_reserved_regions`].
+pub const MAX_RESERVED_REGIONS: usize = 8;
+
+/// The maximum alignment of values can be configured by [`Options::with_value_alignment`].
+pub const MAX_VALUE_ALIGNMENT: usize = 64;
 
 #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
 #[cfg_attr(docsrs, doc(cfg(all(feature = "memmap", not(target_family = "wasm")))))]
 mod open_options;
+#[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+pub(crate) use open_options::lock_all;
 
 mod builder;
 pub use builder::*;
 
+/// The checksum algorithm of a checksumer, which is recorded in the header of the value log,
+/// so that reopening a value log with a different checksumer can be detected.
+///
+/// ## Example
+///
+/// ```rust
+/// use valog::options::ChecksumAlgo;
+///
+/// #[derive(Default)]
+/// struct MyChecksumer;
+///
+/// impl ChecksumAlgo for MyChecksumer {
+///   fn algorithm() -> u8 {
+///     128
+///   }
+/// }
+/// ```
+pub trait ChecksumAlgo {
+  /// Returns the identifier of the checksum algorithm.
+  ///
+  /// The identifiers `1..=127` are reserved for the checksumers provided by this crate,
+  /// `0` means unknown algorithm, which skips the check when reopening.
+  fn algorithm() -> u8;
+}
+
+impl ChecksumAlgo for crate::checksum::Crc32 {
+  #[inline]
+  fn algorithm() -> u8 {
+    1
+  }
+}
+
+#[cfg(feature = "xxhash64")]
+#[cfg_attr(docsrs, doc(cfg(feature = "xxhash64")))]
+impl ChecksumAlgo for crate::checksum::XxHash64 {
+  #[inline]
+  fn algorithm() -> u8 {
+    2
+  }
+}
+
+#[cfg(feature = "xxhash3")]
+#[cfg_attr(docsrs, doc(cfg(feature = "xxhash3")))]
+impl ChecksumAlgo for crate::checksum::XxHash3 {
+  #[inline]
+  fn algorithm() -> u8 {
+    3
+  }
+}
+
+/// The length of the checksum digest stored after each value.
+///
+/// The checksum is always stored in little-endian (the lowest `len` bytes of the `u64` digest),
+/// regardless of the endianness of the host, so the value log files can be shipped between
+/// architectures. The magic version in the header is stored in little-endian as well.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+#[repr(u8)]
+pub enum ChecksumLen {
+  /// The checksum is truncated to the lower 4 bytes, which is enough for 32-bit checksumers, e.g. [`Crc32`](crate::checksum::Crc32).
+  Four = 4,
+  /// The full 8 bytes checksum.
+  #[default]
+  Eight = 8,
+}
+
+impl ChecksumLen {
+  /// Returns the number of bytes of the checksum digest.
+  ///
+  /// ## Example
+  ///
+  /// ```rust
+  /// use valog::options::ChecksumLen;
+  ///
+  /// assert_eq!(ChecksumLen::Four.len(), 4);
+  /// assert_eq!(ChecksumLen::Eight.len(), 8);
+  /// ```
+  #[allow(clippy::len_without_is_empty)]
+  #[inline]
+  pub const fn len(&self) -> usize {
+    *self as usize
+  }
+
+  /// Truncates the checksum to the length.
+  #[inline]
+  pub(crate) const fn truncate(&self, checksum: u64) -> u64 {
+    match self {
+      Self::Four => checksum & (u32::MAX as u64),
+      Self::Eight => checksum,
+    }
+  }
+
+  /// Decodes the checksum from the buffer, the buffer must have enough bytes.
+  #[inline]
+  pub(crate) fn decode(&self, buf: &[u8]) -> u64 {
+    let mut bytes = [0; 8];
+    let len = self.len();
+    bytes[..len].copy_from_slice(&buf[..len]);
+    u64::from_le_bytes(bytes)
+  }
+
+  #[inline]
+  const fn from_u8(val: u8) -> Option<Self> {
+    match val {
+      4 => Some(Self::Four),
+      8 => Some(Self::Eight),
+      _ => None,
+    }
+  }
+}
+
+/// The cipher which encrypts the values of the log at rest, see [`Options::with_cipher`].
+///
+/// The key is never written to the log, only a tag derived from it is recorded in the header, so that
+/// reopening a log with a wrong key is detected. The [`Debug`](core::fmt::Debug) output does not show the key.
+#[cfg(feature = "encryption")]
+#[cfg_attr(docsrs, doc(cfg(feature = "encryption")))]
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Cipher {
+  /// AES-256 in Galois/Counter Mode.
+  Aes256Gcm {
+    /// The 256-bit key.
+    key: [u8; 32],
+  },
+}
+
+#[cfg(feature = "encryption")]
+impl core::fmt::Debug for Cipher {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    match self {
+      Self::Aes256Gcm { .. } => f
+        .debug_struct("Aes256Gcm")
+        .field("key", &"<redacted>")
+        .finish(),
+    }
+  }
+}
+
+#[cfg(feature = "encryption")]
+impl Cipher {
+  /// Returns the identifier of the cipher recorded in the header, `0` means the log is not encrypted.
+  #[inline]
+  const fn id(&self) -> u8 {
+    match self {
+      Self::Aes256Gcm { .. } => 1,
+    }
+  }
+}
+
+/// Controls when the header of a file backed log is flushed together with the synced values,
+/// only takes effect when [`sync`](Options::sync) is enabled.
+///
+/// The header records the committed length of the log, which is advanced to the end of the synced value when
+/// the header is flushed. After a crash, [`MutableLog::repair`](crate::MutableLog::repair) recovers the log to the
+/// committed length of the last flushed header, the values inserted after that are lost, but the recovered log is
+/// never corrupted, as the values before it are always flushed.
+///
+/// The default is [`Always`](HeaderSync::Always), the other modes trade the durability of the latest
+/// synced values for less flushes of the header page, and must be opted in.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HeaderSync {
+  /// Flushes the header with every synced value, the inserted values always survive a crash,
+  /// at the cost of flushing the header page on every insert.
+  #[default]
+  Always,
+  /// Flushes the header when a synced value crosses a multiple of the given number of bytes of the log,
+  /// so that at most the given number of bytes of values are lost after a crash.
+  Periodic(u32),
+  /// Never flushes the header on inserts, the header is only flushed by
+  /// [`MutableLog::flush`](crate::MutableLog::flush).
+  OnClose,
+}
+
+impl HeaderSync {
+  /// Returns `true` if the header should be flushed with the entry at `offset..offset + len`.
+  #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+  #[inline]
+  pub(crate) const fn should_flush(&self, offset: usize, len: usize) -> bool {
+    match self {
+      Self::Always => true,
+      Self::Periodic(interval) => {
+        let interval = if *interval == 0 {
+          1
+        } else {
+          *interval as usize
+        };
+        offset / interval != (offset + len) / interval
+      }
+      Self::OnClose => false,
+    }
+  }
+}
+
+/// Controls how the synced values of a file backed log are flushed to the disk,
+/// only takes effect when [`sync`](Options::sync) is enabled.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SyncStrategy {
+  /// Flushes the memory map with `msync`, which is portable.
+  #[default]
+  Msync,
+  /// Flushes the written range of the file with `sync_file_range(WAIT_BEFORE | WRITE | WAIT_AFTER)`,
+  /// which is usually cheaper than `msync` for sequential appends.
+  ///
+  /// `sync_file_range` neither flushes the file metadata nor the volatile write cache of the device,
+  /// so it does not guarantee the durability after a power loss on such devices.
+  /// Falls back to [`Fdatasync`](SyncStrategy::Fdatasync) on the platforms other than Linux and Android.
+  SyncFileRange,
+  /// Flushes the whole file with `fdatasync`.
+  Fdatasync,
+}
+
+/// Controls what happens when inserting a value larger than [`maximum_value_size`](Options::maximum_value_size).
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OversizePolicy {
+  /// Rejects the value with [`Error::ValueTooLarge`](crate::error::Error::ValueTooLarge).
+  #[default]
+  Error,
+  /// Stores the longest prefix of the value which fits in `maximum_value_size` (the checksum is counted in),
+  /// and marks the returned pointer as
+  /// [`truncated`](crate::ValuePointer::is_truncated).
+  ///
+  /// Only the values inserted from slices are truncated, which are the values inserted by
+  /// [`LogWriter::insert`](crate::LogWriter::insert), [`LogWriter::insert_if_fits`](crate::LogWriter::insert_if_fits),
+  /// [`LogWriter::insert_or_grow`](crate::LogWriter::insert_or_grow),
+  /// [`LogWriter::insert_tombstone`](crate::LogWriter::insert_tombstone),
+  /// [`LogWriter::insert_all`](crate::LogWriter::insert_all), [`LogWriter::insert_at`](crate::LogWriter::insert_at),
+  /// [`LogWriterExt::insert_stream`](crate::LogWriterExt::insert_stream),
+  /// [`LogWriterExt::insert_array`](crate::LogWriterExt::insert_array) and
+  /// [`BufferedWriter::insert`](crate::BufferedWriter::insert), and [`LogWriter::can_insert`](crate::LogWriter::can_insert)
+  /// checks the truncated length.
+  ///
+  /// The values built in place (e.g. by [`LogWriterExt::insert_with`](crate::LogWriterExt::insert_with),
+  /// [`LogWriter::insert_with_flags`](crate::LogWriter::insert_with_flags) and
+  /// [`LogWriterExt::insert_generic`](crate::LogWriterExt::insert_generic)), the values inserted with a given checksum
+  /// by [`LogWriterExt::insert_with_checksum`](crate::LogWriterExt::insert_with_checksum), and the entries copied from
+  /// other logs are still rejected. The entries are not framed, so the truncation is only recorded on the returned pointer.
+  Truncate,
+}
+
 /// Options for configuring the value log.
 #[viewit::viewit(vis_all = "pub(super)", getters(skip), setters(skip))]
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct Options {
   max_value_size: u32,
+  oversize_policy: OversizePolicy,
+  max_entries: u32,
   magic_version: u16,
+  /// The layout version of the log, `0` for the logs written before the header has a checksum.
+  version: u16,
   capacity: Option<u32>,
   unify: bool,
   freelist: Freelist,
   reserved: u32,
+  reserved_regions: [u32; MAX_RESERVED_REGIONS],
+  num_reserved_regions: u8,
   lock_meta: bool,
   sync: bool,
+  header_sync: HeaderSync,
+  sync_strategy: SyncStrategy,
   validate_checksum: bool,
+  /// The checksum length, `None` adopts the one recorded in an existing log, or defaults to [`ChecksumLen::Eight`].
+  checksum_len: Option<ChecksumLen>,
+  value_alignment: usize,
+  write_buffer: u32,
+  #[cfg(feature = "encryption")]
+  cipher: Option<Cipher>,
+  /// The salt of the nonces of the values written by the log, which is renewed whenever a writable log is built.
+  #[cfg(feature = "encryption")]
+  salt: u64,
 
   #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
   create_new: bool,
   #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
   create: bool,
   #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+  create_dir: bool,
+  #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
   read: bool,
   #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
   write: bool,
@@ -49,6 +347,16 @@ pub struct Options {
   huge: Option<u8>,
   #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
   populate: bool,
+  #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+  direct_io: bool,
+  #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+  anon_grow: bool,
+  #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+  lock_all: bool,
+  #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+  read_ahead: u32,
+  /// The sub-range of the shared allocator, see [`Builder::with_external_arena`].
+  sub_range: Option<(u32, u32)>,
 }
 
 impl Default for Options {
@@ -64,20 +372,36 @@ impl Options {
   pub const fn new() -> Self {
     Self {
       max_value_size: u32::MAX,
+      oversize_policy: OversizePolicy::Error,
+      max_entries: u32::MAX,
       capacity: None,
       unify: false,
       magic_version: 0,
+      version: CURRENT_VERSION,
       freelist: Freelist::None,
       reserved: 0,
+      reserved_regions: [0; MAX_RESERVED_REGIONS],
+      num_reserved_regions: 0,
       lock_meta: false,
       sync: true,
+      header_sync: HeaderSync::Always,
+      sync_strategy: SyncStrategy::Msync,
       validate_checksum: true,
+      checksum_len: None,
+      value_alignment: 1,
+      write_buffer: 0,
+      #[cfg(feature = "encryption")]
+      cipher: None,
+      #[cfg(feature = "encryption")]
+      salt: 0,
 
       #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
       create_new: false,
       #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
       create: false,
       #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+      create_dir: false,
+      #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
       read: false,
       #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
       write: false,
@@ -93,6 +417,15 @@ impl Options {
       huge: None,
       #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
       populate: false,
+      #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+      direct_io: false,
+      #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+      anon_grow: false,
+      #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+      lock_all: false,
+      #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+      read_ahead: 0,
+      sub_range: None,
     }
   }
 
@@ -114,6 +447,58 @@ impl Options {
   #[inline]
   pub const fn with_reserved(mut self, reserved: u32) -> Self {
     self.reserved = reserved;
+    self.reserved_regions = [0; MAX_RESERVED_REGIONS];
+    if reserved == 0 {
+      self.num_reserved_regions = 0;
+    } else {
+      self.reserved_regions[0] = reserved;
+      self.num_reserved_regions = 1;
+    }
+    self
+  }
+
+  /// Set multiple independent reserved regions of the `Log`.
+  ///
+  /// The regions are laid out one after another after the header of the `Log`, and
+  /// the total reserved bytes is the sum of the sizes of all regions. Each region can be
+  /// accessed by [`Log::reserved_region`](crate::Log::reserved_region) with its index.
+  ///
+  /// This will override the configuration of [`with_reserved`](Options::with_reserved).
+  ///
+  /// ## Panics
+  ///
+  /// - If the number of regions is larger than [`MAX_RESERVED_REGIONS`].
+  /// - If the total size of regions overflows `u32`.
+  ///
+  /// ## Example
+  ///
+  /// ```rust
+  /// use valog::options::Options;
+  ///
+  /// let opts = Options::new().with_reserved_regions(&[8, 16]);
+  /// assert_eq!(opts.reserved(), 24);
+  /// ```
+  #[inline]
+  pub const fn with_reserved_regions(mut self, regions: &[u32]) -> Self {
+    assert!(
+      regions.len() <= MAX_RESERVED_REGIONS,
+      "too many reserved regions"
+    );
+
+    let mut reserved = 0u32;
+    let mut i = 0;
+    self.reserved_regions = [0; MAX_RESERVED_REGIONS];
+    while i < regions.len() {
+      reserved = match reserved.checked_add(regions[i]) {
+        Some(reserved) => reserved,
+        None => panic!("the total size of reserved regions overflows u32"),
+      };
+      self.reserved_regions[i] = regions[i];
+      i += 1;
+    }
+
+    self.reserved = reserved;
+    self.num_reserved_regions = regions.len() as u8;
     self
   }
 
@@ -134,6 +519,43 @@ impl Options {
     self
   }
 
+  /// Set when the header is flushed together with the synced values, see [`HeaderSync`].
+  ///
+  /// Default is [`HeaderSync::Always`].
+  ///
+  /// ## Example
+  ///
+  /// ```rust
+  /// use valog::options::{HeaderSync, Options};
+  ///
+  /// let opts = Options::new().with_header_sync(HeaderSync::Always);
+  /// ```
+  #[inline]
+  pub const fn with_header_sync(mut self, header_sync: HeaderSync) -> Self {
+    self.header_sync = header_sync;
+    self
+  }
+
+  /// Set how the synced values are flushed to the disk, see [`SyncStrategy`].
+  ///
+  /// The strategies other than [`SyncStrategy::Msync`] flush through a file handle opened
+  /// when the log is mapped, the logs on the memory are not affected.
+  ///
+  /// Default is [`SyncStrategy::Msync`].
+  ///
+  /// ## Example
+  ///
+  /// ```rust
+  /// use valog::options::{Options, SyncStrategy};
+  ///
+  /// let opts = Options::new().with_sync_strategy(SyncStrategy::SyncFileRange);
+  /// ```
+  #[inline]
+  pub const fn with_sync_strategy(mut self, sync_strategy: SyncStrategy) -> Self {
+    self.sync_strategy = sync_strategy;
+    self
+  }
+
   /// Set if validate the checksum of the value when reading the value.
   ///
   /// Default is `true`.
@@ -151,6 +573,27 @@ impl Options {
     self
   }
 
+  /// Set the length of the checksum digest stored after each value.
+  ///
+  /// The checksum length is recorded in the header of the value log. When reopening a file backed value log,
+  /// an explicitly set checksum length must match the one in the header, otherwise the checksum length in
+  /// the header is used.
+  ///
+  /// The default value is [`ChecksumLen::Eight`].
+  ///
+  /// ## Example
+  ///
+  /// ```rust
+  /// use valog::options::{Options, ChecksumLen};
+  ///
+  /// let opts = Options::new().with_checksum_len(ChecksumLen::Four);
+  /// ```
+  #[inline]
+  pub const fn with_checksum_len(mut self, checksum_len: ChecksumLen) -> Self {
+    self.checksum_len = Some(checksum_len);
+    self
+  }
+
   /// Set if lock the meta of the `Log` in the memory to prevent OS from swapping out the first page of `Log`.
   /// When using memory map backed `Log`, the meta of the `Log`
   /// is in the first page, meta is frequently accessed,
@@ -180,6 +623,8 @@ impl Options {
   /// This is used by the application using value log
   /// to ensure that it doesn't open the value log
   /// with incompatible data format.
+  ///
+  /// The magic version is stored in little-endian regardless of the endianness of the host.
   ///  
   /// The default value is `0`.
   ///
@@ -253,8 +698,49 @@ impl Options {
     self
   }
 
+  /// Sets what happens when inserting a value larger than the [`maximum_value_size`](Options::maximum_value_size),
+  /// see [`OversizePolicy`].
+  ///
+  /// Default is [`OversizePolicy::Error`].
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use valog::options::{Options, OversizePolicy};
+  ///
+  /// let options = Options::new().with_oversize_policy(OversizePolicy::Truncate);
+  /// ```
+  #[inline]
+  pub const fn with_oversize_policy(mut self, policy: OversizePolicy) -> Self {
+    self.oversize_policy = policy;
+    self
+  }
+
+  /// Sets the maximum number of the non-empty values can be inserted into the log,
+  /// the inserts beyond the cap return [`Error::MaxEntriesReached`](crate::error::Error::MaxEntriesReached).
+  ///
+  /// The entries are counted by the log handle (shared with its clones) since the log is created or opened,
+  /// see [`LogWriter::entries`](crate::LogWriter::entries).
+  ///
+  /// Default is `u32::MAX`, which means unlimited.
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use valog::options::Options;
+  ///
+  /// let options = Options::new().with_max_entries(128);
+  /// ```
+  #[inline]
+  pub const fn with_max_entries(mut self, max_entries: u32) -> Self {
+    self.max_entries = max_entries;
+    self
+  }
+
   /// Sets the capacity of the underlying `Log`.
   ///
+  /// For memory map backed `Log`, the capacity will be rounded up to the page size of the OS.
+  ///
   /// ## Example
   ///
   /// ```
@@ -268,6 +754,143 @@ impl Options {
     self
   }
 
+  /// Sets the capacity of the underlying `Log` from a `u64`, which is useful when the capacity is computed,
+  /// e.g. from a `usize`, so that an overflowing capacity is not silently truncated.
+  ///
+  /// ## Errors
+  /// - [`Error::CapacityOverflow`](crate::error::Error::CapacityOverflow) if `capacity` is larger than `u32::MAX`.
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use valog::options::Options;
+  ///
+  /// let options = Options::new().try_with_capacity(1024).unwrap();
+  /// assert_eq!(options.capacity(), 1024);
+  ///
+  /// assert!(Options::new().try_with_capacity(1 << 33).is_err());
+  /// ```
+  #[inline]
+  pub const fn try_with_capacity(self, capacity: u64) -> Result<Self, crate::error::Error> {
+    if capacity > u32::MAX as u64 {
+      return Err(crate::error::Error::capacity_overflow(capacity));
+    }
+
+    Ok(self.with_capacity(capacity as u32))
+  }
+
+  /// Sets the capacity of the underlying `Log`, `None` means let the backend decide.
+  ///
+  /// When reopening a file backed `Log`, `None` means the capacity is inferred from the file size.
+  /// For anonymous memory map backed `Log`, `None` means one page of the OS.
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use valog::options::Options;
+  ///
+  /// let options = Options::new().with_capacity_opt(None);
+  /// assert_eq!(options.capacity_opt(), None);
+  /// ```
+  #[inline]
+  pub const fn with_capacity_opt(mut self, capacity: Option<u32>) -> Self {
+    self.capacity = capacity;
+    self
+  }
+
+  /// Sets the alignment of the start offset of each value.
+  ///
+  /// Each value will be stored at an offset which is a multiple of `align`, the padding bytes
+  /// wasted by the alignment are counted toward the discarded bytes of the value log.
+  /// This is useful when the value needs to be read as an aligned type directly from the value log.
+  ///
+  /// The memory of the value log is aligned to `align` as well (the memory map is page aligned),
+  /// so the slice returned by [`LogReader::read`](crate::LogReader::read) for a value starts at an address
+  /// which is a multiple of `align`, e.g. `32` or `64` for the aligned SIMD loads.
+  /// This does not hold for the value logs built by [`Builder::with_external_arena`](crate::Builder::with_external_arena),
+  /// whose memory is aligned by the external allocator.
+  ///
+  /// The default value is `1`, which means the values are stored back-to-back.
+  ///
+  /// ## Panics
+  ///
+  /// - If `align` is not a power of 2.
+  /// - If `align` is larger than [`MAX_VALUE_ALIGNMENT`].
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use valog::options::Options;
+  ///
+  /// let options = Options::new().with_value_alignment(64);
+  /// ```
+  #[inline]
+  pub const fn with_value_alignment(mut self, align: usize) -> Self {
+    assert!(align.is_power_of_two(), "alignment must be a power of 2");
+    assert!(
+      align <= MAX_VALUE_ALIGNMENT,
+      "alignment must not be larger than MAX_VALUE_ALIGNMENT"
+    );
+    self.value_alignment = align;
+    self
+  }
+
+  /// Sets the size of the write buffer used by [`BufferedWriter`](crate::BufferedWriter).
+  ///
+  /// A [`BufferedWriter`](crate::BufferedWriter) reserves `size` bytes from the log up front,
+  /// and inserts the small values into the reserved space, so that the allocation and the
+  /// flush are paid once per `size` bytes instead of once per value.
+  ///
+  /// The default value is `0`, which means the values are inserted one by one.
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use valog::options::Options;
+  ///
+  /// let options = Options::new().with_write_buffer(4096);
+  /// ```
+  #[inline]
+  pub const fn with_write_buffer(mut self, size: u32) -> Self {
+    self.write_buffer = size;
+    self
+  }
+
+  /// Sets the cipher which encrypts the values of the log at rest.
+  ///
+  /// Each value is sealed with a unique nonce, which is the salt of the log followed by the offset of the value,
+  /// and stored as the nonce, the ciphertext and the authentication tag, so a value takes 28 bytes more, which are
+  /// counted in the [`maximum_value_size`](Options::maximum_value_size) and the size of the value pointer. The salt is
+  /// random and renewed whenever a writable log is built, so the nonces are never reused, even if the tail of the
+  /// log is reclaimed by [`MutableLog::repair`](crate::MutableLog::repair). The stored checksum covers the sealed value.
+  ///
+  /// The owned reads, e.g. [`LogReader::read_owned`](crate::LogReader::read_owned), decrypt the values, the
+  /// zero-copy reads, e.g. [`LogReader::read`](crate::LogReader::read), return the sealed values as stored, so the
+  /// typed reads do not work on an encrypted log. A value which cannot be authenticated, e.g. read with a wrong key
+  /// or tampered, is reported as [`Error::AuthenticationFailed`](crate::error::Error::AuthenticationFailed).
+  /// Reopening a log with a wrong key returns the same error, and reopening a log with a different cipher or without
+  /// a cipher returns [`Error::OptionsMismatch`](crate::error::Error::OptionsMismatch).
+  ///
+  /// The footer written by [`MutableLog::write_footer`](crate::MutableLog::write_footer) only holds the offsets and the sizes of
+  /// the values, and is not encrypted.
+  ///
+  /// The default value is `None`, which means the values are stored in plain.
+  ///
+  /// ## Example
+  ///
+  /// ```rust
+  /// use valog::options::{Cipher, Options};
+  ///
+  /// let options = Options::new().with_cipher(Cipher::Aes256Gcm { key: [7; 32] });
+  /// ```
+  #[cfg(feature = "encryption")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "encryption")))]
+  #[inline]
+  pub const fn with_cipher(mut self, cipher: Cipher) -> Self {
+    self.cipher = Some(cipher);
+    self
+  }
+
   /// Get the reserved of the `Log`.
   ///
   /// The reserved is used to configure the start position of the `Log`. This is useful
@@ -290,6 +913,27 @@ impl Options {
     self.reserved
   }
 
+  /// Get the sizes of the reserved regions of the `Log`.
+  ///
+  /// ## Example
+  ///
+  /// ```rust
+  /// use valog::options::Options;
+  ///
+  /// let opts = Options::new().with_reserved_regions(&[8, 16]);
+  /// assert_eq!(opts.reserved_regions(), &[8, 16]);
+  ///
+  /// let opts = Options::new().with_reserved(8);
+  /// assert_eq!(opts.reserved_regions(), &[8]);
+  /// ```
+  #[inline]
+  pub const fn reserved_regions(&self) -> &[u32] {
+    self
+      .reserved_regions
+      .split_at(self.num_reserved_regions as usize)
+      .0
+  }
+
   /// Get if flush the data to the disk when new value is inserted.
   ///
   /// Default is `true`.
@@ -308,6 +952,42 @@ impl Options {
     self.sync
   }
 
+  /// Get when the header is flushed together with the synced values.
+  ///
+  /// Default is [`HeaderSync::Always`].
+  ///
+  /// ## Example
+  ///
+  /// ```rust
+  /// use valog::options::{HeaderSync, Options};
+  ///
+  /// let opts = Options::new().with_header_sync(HeaderSync::OnClose);
+  ///
+  /// assert_eq!(opts.header_sync(), HeaderSync::OnClose);
+  /// ```
+  #[inline]
+  pub const fn header_sync(&self) -> HeaderSync {
+    self.header_sync
+  }
+
+  /// Get how the synced values are flushed to the disk.
+  ///
+  /// Default is [`SyncStrategy::Msync`].
+  ///
+  /// ## Example
+  ///
+  /// ```rust
+  /// use valog::options::{Options, SyncStrategy};
+  ///
+  /// let opts = Options::new().with_sync_strategy(SyncStrategy::Fdatasync);
+  ///
+  /// assert_eq!(opts.sync_strategy(), SyncStrategy::Fdatasync);
+  /// ```
+  #[inline]
+  pub const fn sync_strategy(&self) -> SyncStrategy {
+    self.sync_strategy
+  }
+
   /// Get if validate the checksum of the value when reading the value.
   ///
   /// Default is `true`.
@@ -326,6 +1006,27 @@ impl Options {
     self.validate_checksum
   }
 
+  /// Get the length of the checksum digest stored after each value.
+  ///
+  /// The default value is [`ChecksumLen::Eight`].
+  ///
+  /// ## Example
+  ///
+  /// ```rust
+  /// use valog::options::{Options, ChecksumLen};
+  ///
+  /// let opts = Options::new().with_checksum_len(ChecksumLen::Four);
+  ///
+  /// assert_eq!(opts.checksum_len(), ChecksumLen::Four);
+  /// ```
+  #[inline]
+  pub const fn checksum_len(&self) -> ChecksumLen {
+    match self.checksum_len {
+      Some(checksum_len) => checksum_len,
+      None => ChecksumLen::Eight,
+    }
+  }
+
   /// Get if lock the meta of the `Log` in the memory to prevent OS from swapping out the first page of `Log`.
   /// When using memory map backed `Log`, the meta of the `Log`
   /// is in the first page, meta is frequently accessed,
@@ -364,6 +1065,40 @@ impl Options {
     self.max_value_size
   }
 
+  /// Returns what happens when inserting a value larger than the [`maximum_value_size`](Options::maximum_value_size).
+  ///
+  /// Default is [`OversizePolicy::Error`].
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use valog::options::{Options, OversizePolicy};
+  ///
+  /// let options = Options::new().with_oversize_policy(OversizePolicy::Truncate);
+  /// assert_eq!(options.oversize_policy(), OversizePolicy::Truncate);
+  /// ```
+  #[inline]
+  pub const fn oversize_policy(&self) -> OversizePolicy {
+    self.oversize_policy
+  }
+
+  /// Returns the maximum number of the non-empty values can be inserted into the log.
+  ///
+  /// Default is `u32::MAX`, which means unlimited.
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use valog::options::Options;
+  ///
+  /// let options = Options::new().with_max_entries(128);
+  /// assert_eq!(options.max_entries(), 128);
+  /// ```
+  #[inline]
+  pub const fn max_entries(&self) -> u32 {
+    self.max_entries
+  }
+
   /// Returns the configuration of underlying `Log` size.
   ///
   /// ## Example
@@ -381,6 +1116,21 @@ impl Options {
     }
   }
 
+  /// Returns the configuration of underlying `Log` size, `None` means let the backend decide.
+  ///
+  /// ## Example
+  ///
+  /// ```rust
+  /// use valog::options::Options;
+  ///
+  /// let options = Options::new().with_capacity(1024);
+  /// assert_eq!(options.capacity_opt(), Some(1024));
+  /// ```
+  #[inline]
+  pub const fn capacity_opt(&self) -> Option<u32> {
+    self.capacity
+  }
+
   /// Get if use the unify memory layout of the value log.
   ///
   /// File backed value log has different memory layout with other kind backed value log,
@@ -444,18 +1194,275 @@ impl Options {
   pub const fn freelist(&self) -> Freelist {
     self.freelist
   }
+
+  /// Returns the alignment of the start offset of each value.
+  ///
+  /// The default value is `1`.
+  ///
+  /// ## Example
+  ///
+  /// ```rust
+  /// use valog::options::Options;
+  ///
+  /// let opts = Options::new().with_value_alignment(8);
+  ///
+  /// assert_eq!(opts.value_alignment(), 8);
+  /// ```
+  #[inline]
+  pub const fn value_alignment(&self) -> usize {
+    self.value_alignment
+  }
+
+  /// Returns the size of the write buffer used by [`BufferedWriter`](crate::BufferedWriter).
+  ///
+  /// The default value is `0`.
+  ///
+  /// ## Example
+  ///
+  /// ```rust
+  /// use valog::options::Options;
+  ///
+  /// let opts = Options::new().with_write_buffer(4096);
+  ///
+  /// assert_eq!(opts.write_buffer(), 4096);
+  /// ```
+  #[inline]
+  pub const fn write_buffer(&self) -> u32 {
+    self.write_buffer
+  }
+
+  /// Returns the cipher which encrypts the values of the log at rest.
+  ///
+  /// The default value is `None`.
+  ///
+  /// ## Example
+  ///
+  /// ```rust
+  /// use valog::options::{Cipher, Options};
+  ///
+  /// let opts = Options::new().with_cipher(Cipher::Aes256Gcm { key: [7; 32] });
+  ///
+  /// assert_eq!(opts.cipher(), Some(Cipher::Aes256Gcm { key: [7; 32] }));
+  /// ```
+  #[cfg(feature = "encryption")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "encryption")))]
+  #[inline]
+  pub const fn cipher(&self) -> Option<Cipher> {
+    self.cipher
+  }
 }
 
 impl Options {
+  /// Checks the options for the obvious mistakes, and returns [`Error::InvalidOptions`](crate::error::Error::InvalidOptions)
+  /// describing the first problem found.
+  ///
+  /// The following problems are checked:
+  /// - The header and the reserved bytes do not fit in the capacity.
+  /// - The maximum value size is greater than the capacity, the default `u32::MAX` means unbounded and is not checked.
+  /// - Both `create_new` and `truncate` are enabled.
+  ///
+  /// The capacity related checks are skipped if the capacity is not set. This method is called
+  /// by the constructors of [`Builder`] which create a new log, e.g. [`Builder::alloc`] and `Builder::map_mut`.
+  ///
+  /// ## Example
+  ///
+  /// ```rust
+  /// use valog::{error::Error, options::Options};
+  ///
+  /// assert!(Options::new().with_capacity(1024).validate().is_ok());
+  ///
+  /// let err = Options::new().with_capacity(1024).with_reserved(2048).validate().unwrap_err();
+  /// assert!(matches!(err, Error::InvalidOptions { .. }));
+  /// ```
+  pub fn validate(&self) -> Result<(), crate::error::Error> {
+    use crate::error::Error;
+
+    if let Some(cap) = self.capacity {
+      if self.header_size() as u64 + self.reserved as u64 > cap as u64 {
+        return Err(Error::invalid_options(
+          "the header and the reserved bytes exceed the capacity",
+        ));
+      }
+
+      if self.max_value_size != u32::MAX && self.max_value_size > cap {
+        return Err(Error::invalid_options(
+          "the maximum value size exceeds the capacity",
+        ));
+      }
+    }
+
+    #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+    if self.create_new && self.truncate {
+      return Err(Error::invalid_options(
+        "create_new and truncate cannot be enabled at the same time",
+      ));
+    }
+
+    Ok(())
+  }
+
+  /// Rounds the capacity up to the page size of the OS.
+  #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+  #[inline]
+  pub(crate) fn with_page_aligned_capacity(mut self) -> Self {
+    if let Some(cap) = self.capacity {
+      let page_size = page_size() as u64;
+      let aligned = (cap as u64).next_multiple_of(page_size);
+      if aligned <= u32::MAX as u64 {
+        self.capacity = Some(aligned as u32);
+      }
+    }
+    self
+  }
+
+  /// Uses one page of the OS as the capacity if the capacity is not set.
+  #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+  #[inline]
+  pub(super) fn with_default_anon_capacity(mut self) -> Self {
+    if self.capacity.is_none() {
+      self.capacity = Some(page_size() as u32);
+    }
+    self
+  }
+
+  /// Returns the sub-range of the shared allocator which the log is restricted to.
+  #[inline]
+  pub(crate) const fn sub_range(&self) -> Option<(u32, u32)> {
+    self.sub_range
+  }
+
+  /// Returns the stable fingerprint of the on-disk layout configured by the options, see
+  /// [`Log::config_fingerprint`](crate::Log::config_fingerprint) for the hashed fields.
+  ///
+  /// The fingerprint is the 64-bit FNV-1a hash over the little-endian encoded fields, so that it is
+  /// reproducible across runs, platforms and Rust versions.
+  pub(crate) fn fingerprint(&self, checksum_probe: u64, id_width: usize) -> u64 {
+    const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+    let fields: [&[u8]; 8] = [
+      &MAGIC_TEXT,
+      &self.version.to_le_bytes(),
+      &self.magic_version.to_le_bytes(),
+      &[self.checksum_len() as u8],
+      &checksum_probe.to_le_bytes(),
+      &(id_width as u64).to_le_bytes(),
+      &self.reserved.to_le_bytes(),
+      &(self.value_alignment as u64).to_le_bytes(),
+    ];
+
+    fields
+      .iter()
+      .flat_map(|field| field.iter())
+      .fold(FNV_OFFSET_BASIS, |hash, b| {
+        (hash ^ *b as u64).wrapping_mul(FNV_PRIME)
+      })
+  }
+
+  /// Returns the range of the reserved region at `index`, relative to the start of the reserved slice.
+  #[inline]
+  pub(super) fn reserved_region_range(&self, index: usize) -> Option<core::ops::Range<usize>> {
+    let regions = self.reserved_regions();
+    if index >= regions.len() {
+      return None;
+    }
+
+    let start = regions[..index].iter().map(|r| *r as usize).sum::<usize>();
+    Some(start..start + regions[index] as usize)
+  }
+
+  /// Returns the size of the header in the layout version of the log.
+  #[inline]
+  pub(crate) const fn header_size(&self) -> usize {
+    if self.version == 0 {
+      return LEGACY_HEADER_SIZE;
+    }
+
+    #[cfg(feature = "encryption")]
+    if self.cipher.is_some() {
+      return HEADER_SIZE + CIPHER_HEADER_SIZE;
+    }
+
+    HEADER_SIZE
+  }
+
+  /// Returns the identifier of the cipher of the log, `0` means the log is not encrypted.
+  #[inline]
+  const fn cipher_id(&self) -> u8 {
+    #[cfg(feature = "encryption")]
+    if let Some(cipher) = self.cipher {
+      return cipher.id();
+    }
+
+    0
+  }
+
+  /// Returns the number of bytes a stored value takes more than the value, which are the nonce and the
+  /// authentication tag of an encrypted log.
+  #[inline]
+  pub(crate) const fn seal_overhead(&self) -> usize {
+    #[cfg(feature = "encryption")]
+    if self.cipher.is_some() {
+      return crate::cipher::SEAL_OVERHEAD;
+    }
+
+    0
+  }
+
+  /// Returns the offset of the value in a stored value, which is after the nonce of an encrypted log.
+  #[inline]
+  pub(crate) const fn nonce_size(&self) -> usize {
+    #[cfg(feature = "encryption")]
+    if self.cipher.is_some() {
+      return crate::cipher::NONCE_SIZE;
+    }
+
+    0
+  }
+
+  /// Returns `true` if the values stored by the logs with the two options are sealed with the same cipher and key.
+  #[inline]
+  pub(crate) fn same_cipher(&self, other: &Self) -> bool {
+    #[cfg(feature = "encryption")]
+    if self.cipher != other.cipher {
+      return false;
+    }
+
+    #[cfg(not(feature = "encryption"))]
+    let _ = other;
+
+    true
+  }
+
+  /// Returns the salt of the nonces of the values written by the log.
+  #[cfg(feature = "encryption")]
+  #[inline]
+  pub(crate) const fn salt(&self) -> u64 {
+    self.salt
+  }
+
+  /// Returns `true` if the log is written in the version `0` layout, whose header only holds the magic text
+  /// and the magic version.
+  #[inline]
+  pub(crate) const fn is_legacy(&self) -> bool {
+    self.version == 0
+  }
+
   #[allow(clippy::wrong_self_convention)]
   #[inline]
-  pub(super) const fn to_arena_options(&self) -> ArenaOptions {
+  pub(crate) const fn to_arena_options(&self) -> ArenaOptions {
+    // the layout version of the log is recorded as the magic version of the allocator.
     let opts = ArenaOptions::new()
-      .with_magic_version(CURRENT_VERSION)
-      .with_reserved(HEADER_SIZE as u32 + self.reserved())
+      .with_magic_version(self.version)
+      .with_reserved(self.header_size() as u32 + self.reserved())
       .with_unify(self.unify())
       .maybe_capacity(self.capacity)
-      .with_freelist(self.freelist());
+      .with_freelist(self.freelist())
+      .with_maximum_alignment(if self.value_alignment > 8 {
+        self.value_alignment
+      } else {
+        8
+      });
 
     #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
     {
@@ -478,9 +1485,328 @@ impl Options {
   }
 }
 
+/// Returns the page size of the OS.
+#[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+fn page_size() -> usize {
+  static PAGE_SIZE: std::sync::OnceLock<usize> = std::sync::OnceLock::new();
+
+  // the page size of the OS is recorded by the allocator, probe it with a tiny allocator.
+  *PAGE_SIZE.get_or_init(|| {
+    ArenaOptions::new()
+      .with_capacity(1024)
+      .alloc::<rarena_allocator::unsync::Arena>()
+      .map(|arena| rarena_allocator::Allocator::page_size(&arena))
+      .unwrap_or(4096)
+  })
+}
+
+/// The tags recorded in the header, which are derived from the type parameters of the value log.
+#[derive(Debug, Clone, Copy)]
+struct HeaderTags {
+  /// The checksum algorithm of the checksumer.
+  algorithm: u8,
+  /// The width of the id type, saturated to `u8::MAX`.
+  id_width: u8,
+}
+
+impl HeaderTags {
+  #[inline]
+  fn new<S: ChecksumAlgo, I>() -> Self {
+    let id_width = core::mem::size_of::<I>();
+    Self {
+      algorithm: S::algorithm(),
+      id_width: if id_width > u8::MAX as usize {
+        u8::MAX
+      } else {
+        id_width as u8
+      },
+    }
+  }
+}
+
+/// Writes the header of a new log, whose values start at `data_offset`.
 #[inline]
-fn write_header(buf: &mut [u8], magic_version: u16) {
+fn write_header(buf: &mut [u8], opts: &Options, tags: HeaderTags, data_offset: u32) {
   buf[..MAGIC_TEXT_SIZE].copy_from_slice(&MAGIC_TEXT);
   buf[MAGIC_TEXT_SIZE..MAGIC_TEXT_SIZE + MAGIC_VERSION_SIZE]
-    .copy_from_slice(&magic_version.to_le_bytes());
+    .copy_from_slice(&opts.magic_version.to_le_bytes());
+  buf[VERSION_OFFSET..CHECKSUM_LEN_OFFSET].copy_from_slice(&CURRENT_VERSION.to_le_bytes());
+  buf[CHECKSUM_LEN_OFFSET] = opts.checksum_len() as u8;
+  buf[CHECKSUM_ALGO_OFFSET] = tags.algorithm;
+  buf[ID_WIDTH_OFFSET] = tags.id_width;
+  buf[FREELIST_OFFSET] = opts.freelist as u8;
+  buf[CIPHER_OFFSET] = opts.cipher_id();
+  buf[RESERVED_OFFSET..DATA_OFFSET_OFFSET].copy_from_slice(&opts.reserved.to_le_bytes());
+  buf[DATA_OFFSET_OFFSET..COMMITTED_OFFSET].copy_from_slice(&data_offset.to_le_bytes());
+  buf[COMMITTED_OFFSET..FOOTER_OFFSET_OFFSET].copy_from_slice(&data_offset.to_le_bytes());
+  write_footer_offset(buf, 0);
+
+  #[cfg(feature = "encryption")]
+  if let Some(cipher) = opts.cipher {
+    buf[SALT_OFFSET..KEY_CHECK_OFFSET].copy_from_slice(&opts.salt.to_le_bytes());
+    buf[KEY_CHECK_OFFSET..SALT_OFFSET + CIPHER_HEADER_SIZE]
+      .copy_from_slice(&crate::cipher::key_check(&cipher));
+  }
+}
+
+/// Renews the salt of the nonces of a writable encrypted log, and records it in the header.
+///
+/// The nonce of a value is the salt followed by the offset of the value, a fresh salt makes sure that the values
+/// written by the log never reuse the nonces of the values written at the same offsets before, e.g. the reclaimed
+/// tail of the log or the values written to a copy of the file.
+#[cfg(feature = "encryption")]
+#[inline]
+fn renew_salt<A: rarena_allocator::Allocator>(arena: &A, opts: &mut Options) {
+  if opts.cipher.is_none() || arena.read_only() {
+    return;
+  }
+
+  opts.salt = crate::cipher::random_salt();
+  // Safety: the log is not built yet, so the header is not shared.
+  unsafe {
+    arena.reserved_slice_mut()[SALT_OFFSET..KEY_CHECK_OFFSET]
+      .copy_from_slice(&opts.salt.to_le_bytes());
+  }
+}
+
+/// Records the offset of the footer in the header, `0` means the log does not have a footer.
+///
+/// The header checksum is updated as well.
+#[inline]
+pub(crate) fn write_footer_offset(buf: &mut [u8], offset: u32) {
+  buf[FOOTER_OFFSET_OFFSET..HEADER_CHECKSUM_OFFSET].copy_from_slice(&offset.to_le_bytes());
+  update_header_checksum(buf);
+}
+
+/// Records the committed length in the header, the values before it are flushed to the disk.
+///
+/// The header checksum is updated as well.
+#[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+#[inline]
+pub(crate) fn write_committed(buf: &mut [u8], committed: u32) {
+  buf[COMMITTED_OFFSET..FOOTER_OFFSET_OFFSET].copy_from_slice(&committed.to_le_bytes());
+  update_header_checksum(buf);
+}
+
+/// Returns the committed length recorded in the header.
+#[inline]
+pub(crate) fn committed(buf: &[u8]) -> u32 {
+  u32::from_le_bytes(
+    buf[COMMITTED_OFFSET..FOOTER_OFFSET_OFFSET]
+      .try_into()
+      .unwrap(),
+  )
+}
+
+/// Returns the data offset recorded in the header.
+#[inline]
+fn recorded_data_offset(buf: &[u8]) -> u32 {
+  u32::from_le_bytes(
+    buf[DATA_OFFSET_OFFSET..COMMITTED_OFFSET]
+      .try_into()
+      .unwrap(),
+  )
+}
+
+#[inline]
+fn update_header_checksum(buf: &mut [u8]) {
+  let checksum = header_checksum(buf);
+  buf[HEADER_CHECKSUM_OFFSET..HEADER_SIZE].copy_from_slice(&checksum.to_le_bytes());
+}
+
+/// Returns the offset of the footer recorded in the header, `0` means the log does not have a footer.
+#[inline]
+pub(crate) fn footer_offset(buf: &[u8]) -> u32 {
+  u32::from_le_bytes(
+    buf[FOOTER_OFFSET_OFFSET..HEADER_CHECKSUM_OFFSET]
+      .try_into()
+      .unwrap(),
+  )
+}
+
+/// Calculates the checksum over the header fields before the header checksum.
+///
+/// The header checksum is always CRC32, regardless of the checksumer of the value log.
+#[inline]
+fn header_checksum(buf: &[u8]) -> u32 {
+  use dbutils::checksum::{BuildChecksumer, Crc32};
+
+  Crc32::new().checksum_one(&buf[..HEADER_CHECKSUM_OFFSET]) as u32
+}
+
+/// Checks the header in the buffer, and updates the options with the configurations recorded in the header.
+///
+/// The header of the version `0` layout, which is selected by the [`version`](Options::version) of the options,
+/// only holds the magic text and the magic version, the checksum is always 8 bytes long.
+#[inline]
+fn check_header(
+  buf: &[u8],
+  opts: &mut Options,
+  tags: HeaderTags,
+) -> Result<(), crate::error::Error> {
+  if buf.len() < opts.header_size() {
+    return Err(crate::error::Error::InvalidLayout);
+  }
+
+  if buf[..MAGIC_TEXT_SIZE] != MAGIC_TEXT {
+    return Err(crate::error::Error::BadMagicText);
+  }
+
+  if !opts.is_legacy() {
+    let checksum_from_buf =
+      u32::from_le_bytes(buf[HEADER_CHECKSUM_OFFSET..HEADER_SIZE].try_into().unwrap());
+    if checksum_from_buf != header_checksum(buf) {
+      return Err(crate::error::Error::HeaderCorrupt);
+    }
+
+    let version_from_buf =
+      u16::from_le_bytes(buf[VERSION_OFFSET..CHECKSUM_LEN_OFFSET].try_into().unwrap());
+    if version_from_buf != CURRENT_VERSION {
+      return Err(crate::error::Error::unsupported_version(
+        CURRENT_VERSION,
+        version_from_buf,
+      ));
+    }
+  }
+
+  let magic_version_from_buf = u16::from_le_bytes(
+    buf[MAGIC_TEXT_SIZE..MAGIC_TEXT_SIZE + MAGIC_VERSION_SIZE]
+      .try_into()
+      .unwrap(),
+  );
+  if magic_version_from_buf != opts.magic_version {
+    return Err(crate::error::Error::bad_magic_version(
+      opts.magic_version,
+      magic_version_from_buf,
+    ));
+  }
+
+  if opts.is_legacy() {
+    if opts
+      .checksum_len
+      .is_some_and(|len| len != ChecksumLen::Eight)
+    {
+      return Err(crate::error::Error::options_mismatch("checksum_len"));
+    }
+    // the header of the version `0` layout cannot record a cipher.
+    if opts.cipher_id() != 0 {
+      return Err(crate::error::Error::options_mismatch("cipher"));
+    }
+    opts.checksum_len = Some(ChecksumLen::Eight);
+    return Ok(());
+  }
+
+  let checksum_len_from_buf =
+    ChecksumLen::from_u8(buf[CHECKSUM_LEN_OFFSET]).ok_or(crate::error::Error::InvalidLayout)?;
+  // the unset checksum length adopts the one recorded in the header.
+  if opts
+    .checksum_len
+    .is_some_and(|len| len != checksum_len_from_buf)
+  {
+    return Err(crate::error::Error::options_mismatch("checksum_len"));
+  }
+  opts.checksum_len = Some(checksum_len_from_buf);
+
+  let reserved_from_buf =
+    u32::from_le_bytes(buf[RESERVED_OFFSET..DATA_OFFSET_OFFSET].try_into().unwrap());
+  if reserved_from_buf != opts.reserved {
+    return Err(crate::error::Error::options_mismatch("reserved"));
+  }
+
+  let algorithm_from_buf = buf[CHECKSUM_ALGO_OFFSET];
+  if tags.algorithm != 0 && algorithm_from_buf != 0 && tags.algorithm != algorithm_from_buf {
+    return Err(crate::error::Error::checksum_algorithm_mismatch(
+      tags.algorithm,
+      algorithm_from_buf,
+    ));
+  }
+
+  let id_width_from_buf = buf[ID_WIDTH_OFFSET];
+  if tags.id_width != id_width_from_buf {
+    return Err(crate::error::Error::id_type_mismatch(
+      tags.id_width,
+      id_width_from_buf,
+    ));
+  }
+
+  if committed(buf) < recorded_data_offset(buf) {
+    return Err(crate::error::Error::InvalidLayout);
+  }
+
+  if buf[CIPHER_OFFSET] != opts.cipher_id() {
+    return Err(crate::error::Error::options_mismatch("cipher"));
+  }
+
+  #[cfg(feature = "encryption")]
+  if let Some(cipher) = opts.cipher {
+    if buf[KEY_CHECK_OFFSET..SALT_OFFSET + CIPHER_HEADER_SIZE] != crate::cipher::key_check(&cipher)
+    {
+      return Err(crate::error::Error::AuthenticationFailed);
+    }
+    opts.salt = u64::from_le_bytes(buf[SALT_OFFSET..KEY_CHECK_OFFSET].try_into().unwrap());
+  }
+
+  Ok(())
+}
+
+/// Checks the header of the existing log at the [`offset`](Options::offset) of the file before the file is mapped,
+/// and updates the options with the configurations recorded in the header, so that a log written with different
+/// options is rejected before the allocator interprets the file with a mismatched layout.
+///
+/// The header of the version `0` layout does not have a checksum, such a log is recognized by mapping it read-only
+/// with the allocator of the version `0` layout, which validates its own header, and the
+/// [`version`](Options::version) of the options is switched to `0`.
+///
+/// Returns the data offset and the committed length of the log, the allocated length of the allocator is used
+/// for the version `0` layout, which does not record the committed length. Returns `None` for an empty file,
+/// which is left to the allocator.
+#[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+fn check_existing(
+  path: &std::path::Path,
+  opts: &mut Options,
+  tags: HeaderTags,
+  check_freelist: bool,
+) -> std::io::Result<Option<(u32, u32)>> {
+  use rarena_allocator::Allocator;
+  use std::io::{Read, Seek, SeekFrom};
+
+  let mut file = std::fs::File::open(path)?;
+  file.seek(SeekFrom::Start(opts.offset))?;
+  let header_size = opts.header_size().max(HEADER_SIZE);
+  let mut buf = std::vec::Vec::with_capacity(header_size);
+  file.take(header_size as u64).read_to_end(&mut buf)?;
+  if buf.is_empty() {
+    return Ok(None);
+  }
+
+  let intact = buf.len() >= HEADER_SIZE
+    && u32::from_le_bytes(buf[HEADER_CHECKSUM_OFFSET..HEADER_SIZE].try_into().unwrap())
+      == header_checksum(&buf);
+  let mut legacy_layout = None;
+  if !intact && buf.starts_with(&MAGIC_TEXT) {
+    let mut legacy = *opts;
+    legacy.version = 0;
+    // Safety: the allocator is dropped right after the header of the allocator is validated.
+    let mapped = unsafe {
+      legacy
+        .to_arena_options()
+        .with_unify(true)
+        .map::<rarena_allocator::sync::Arena, _>(path)
+    };
+    if let Ok(arena) = mapped {
+      opts.version = 0;
+      legacy_layout = Some((arena.data_offset() as u32, arena.allocated() as u32));
+    }
+  }
+
+  check_header(&buf, opts, tags).map_err(crate::error::Error::into_io_err)?;
+  if legacy_layout.is_some() {
+    // the header of the version `0` layout does not record the freelist, which is checked by the allocator.
+    return Ok(legacy_layout);
+  }
+
+  if check_freelist && buf[FREELIST_OFFSET] != opts.freelist as u8 {
+    return Err(crate::error::Error::options_mismatch("freelist").into_io_err());
+  }
+  Ok(Some((recorded_data_offset(&buf), committed(&buf))))
 }
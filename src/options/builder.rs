@@ -1,11 +1,27 @@
 use dbutils::checksum::{BuildChecksumer, Crc32};
-use rarena_allocator::Allocator;
+use rarena_allocator::{Allocator, ArenaPosition, Buffer};
 
 use crate::{error::Error, sealed::Constructor, Mutable};
 
 use super::*;
 
 /// The builder to build a `Log`
+///
+/// The builder is [`Clone`] (and [`Copy`]) if the checksumer is, so that the same options
+/// can be reused to build many logs.
+///
+/// ## Example
+///
+/// ```rust
+/// use valog::{Builder, sync::ValueLog, Log};
+///
+/// let builder = Builder::new().with_capacity(1024);
+/// let logs = (0..4u32)
+///   .map(|fid| builder.clone().alloc::<ValueLog>(fid).unwrap())
+///   .collect::<Vec<_>>();
+/// assert_eq!(*logs[3].id(), 3);
+/// ```
+#[derive(Debug, Clone, Copy)]
 pub struct Builder<S = Crc32> {
   pub(super) opts: Options,
   pub(super) cks: S,
@@ -32,6 +48,10 @@ impl Builder {
 impl<S> Builder<S> {
   /// Returns a new map builder with the new [`BuildChecksumer`](crate::checksum::BuildChecksumer).
   ///
+  /// The [`ChecksumAlgo`](crate::options::ChecksumAlgo) of the checksumer is recorded in the header
+  /// when creating a value log, and reopening the value log with a different checksumer will return
+  /// [`Error::ChecksumAlgorithmMismatch`](crate::error::Error::ChecksumAlgorithmMismatch).
+  ///
   /// ## Example
   ///
   /// ```rust
@@ -62,6 +82,66 @@ impl<S> Builder<S> {
     self
   }
 
+  /// Returns the [`Options`] of the builder.
+  ///
+  /// ## Example
+  ///
+  /// ```rust
+  /// use valog::Builder;
+  ///
+  /// let builder = Builder::new().with_capacity(1024);
+  /// assert_eq!(builder.options().capacity(), 1024);
+  /// ```
+  #[inline]
+  pub const fn options(&self) -> &Options {
+    &self.opts
+  }
+
+  /// Returns a mutable reference to the [`Options`] of the builder, which can be used to
+  /// adjust the current options in place.
+  ///
+  /// ## Example
+  ///
+  /// ```rust
+  /// use valog::Builder;
+  ///
+  /// let mut builder = Builder::new().with_capacity(1024).with_sync(false);
+  ///
+  /// // layer the overrides on top of the defaults
+  /// let opts = builder.options_mut();
+  /// *opts = opts.with_capacity(2048);
+  ///
+  /// assert_eq!(builder.capacity(), 2048);
+  /// assert!(!builder.sync());
+  /// ```
+  #[inline]
+  pub fn options_mut(&mut self) -> &mut Options {
+    &mut self.opts
+  }
+
+  /// Restores the options of the builder to the defaults of [`Options::new`].
+  ///
+  /// The checksumer configured by [`with_checksumer`](Builder::with_checksumer) is not touched,
+  /// so one builder can be reused to build many logs with different options but the same checksum algorithm.
+  ///
+  /// ## Example
+  ///
+  /// ```rust
+  /// use valog::{Builder, checksum::Crc32, options::Options};
+  ///
+  /// let mut builder = Builder::new()
+  ///   .with_checksumer(Crc32::new())
+  ///   .with_capacity(1024)
+  ///   .with_sync(false);
+  ///
+  /// builder.reset();
+  /// assert_eq!(builder.options(), &Options::new());
+  /// ```
+  #[inline]
+  pub fn reset(&mut self) {
+    self.opts = Options::new();
+  }
+
   /// Set the reserved bytes of the `Log`.
   ///
   /// The reserved is used to configure the start position of the `Log`. This is useful
@@ -79,7 +159,32 @@ impl<S> Builder<S> {
   /// ```
   #[inline]
   pub const fn with_reserved(mut self, reserved: u32) -> Self {
-    self.opts.reserved = reserved;
+    self.opts = self.opts.with_reserved(reserved);
+    self
+  }
+
+  /// Set multiple independent reserved regions of the `Log`.
+  ///
+  /// The regions are laid out one after another after the header of the `Log`, and
+  /// the total reserved bytes is the sum of the sizes of all regions.
+  ///
+  /// This will override the configuration of [`with_reserved`](Builder::with_reserved).
+  ///
+  /// ## Panics
+  ///
+  /// - If the number of regions is larger than [`MAX_RESERVED_REGIONS`](crate::options::MAX_RESERVED_REGIONS).
+  /// - If the total size of regions overflows `u32`.
+  ///
+  /// ## Example
+  ///
+  /// ```rust
+  /// use valog::Builder;
+  ///
+  /// let builder = Builder::new().with_reserved_regions(&[8, 16]);
+  /// ```
+  #[inline]
+  pub const fn with_reserved_regions(mut self, regions: &[u32]) -> Self {
+    self.opts = self.opts.with_reserved_regions(regions);
     self
   }
 
@@ -100,6 +205,60 @@ impl<S> Builder<S> {
     self
   }
 
+  /// Set when the header is flushed together with the synced values, see [`HeaderSync`].
+  ///
+  /// Default is [`HeaderSync::Always`].
+  ///
+  /// ## Example
+  ///
+  /// ```rust
+  /// use valog::{options::HeaderSync, Builder};
+  ///
+  /// let opts = Builder::new().with_header_sync(HeaderSync::Always);
+  /// ```
+  #[inline]
+  pub const fn with_header_sync(mut self, header_sync: HeaderSync) -> Self {
+    self.opts.header_sync = header_sync;
+    self
+  }
+
+  /// Set how the synced values are flushed to the disk, see [`SyncStrategy`].
+  ///
+  /// The strategies other than [`SyncStrategy::Msync`] flush through a file handle opened
+  /// when the log is mapped, the logs on the memory are not affected.
+  ///
+  /// Default is [`SyncStrategy::Msync`].
+  ///
+  /// ## Example
+  ///
+  /// ```rust
+  /// use valog::{options::SyncStrategy, Builder};
+  ///
+  /// let builder = Builder::new().with_sync_strategy(SyncStrategy::SyncFileRange);
+  /// ```
+  #[inline]
+  pub const fn with_sync_strategy(mut self, sync_strategy: SyncStrategy) -> Self {
+    self.opts.sync_strategy = sync_strategy;
+    self
+  }
+
+  /// Set if validate the checksum of the value when reading the value.
+  ///
+  /// Default is `true`.
+  ///
+  /// ## Example
+  ///
+  /// ```rust
+  /// use valog::Builder;
+  ///
+  /// let builder = Builder::new().with_validate_checksum(false);
+  /// ```
+  #[inline]
+  pub const fn with_validate_checksum(mut self, validate_checksum: bool) -> Self {
+    self.opts.validate_checksum = validate_checksum;
+    self
+  }
+
   /// Set if lock the meta of the `Log` in the memory to prevent OS from swapping out the first page of `Log`.
   /// When using memory map backed `Log`, the meta of the `Log`
   /// is in the first page, meta is frequently accessed,
@@ -202,9 +361,46 @@ impl<S> Builder<S> {
     self
   }
 
+  /// Sets what happens when inserting a value larger than the [`maximum_value_size`](Builder::maximum_value_size),
+  /// see [`OversizePolicy`].
+  ///
+  /// Default is [`OversizePolicy::Error`].
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use valog::{options::OversizePolicy, Builder};
+  ///
+  /// let builder = Builder::new().with_oversize_policy(OversizePolicy::Truncate);
+  /// ```
+  #[inline]
+  pub const fn with_oversize_policy(mut self, policy: OversizePolicy) -> Self {
+    self.opts.oversize_policy = policy;
+    self
+  }
+
+  /// Sets the maximum number of the non-empty values can be inserted into the log,
+  /// see [`Options::with_max_entries`].
+  ///
+  /// Default is `u32::MAX`, which means unlimited.
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use valog::Builder;
+  ///
+  /// let builder = Builder::new().with_max_entries(128);
+  /// ```
+  #[inline]
+  pub const fn with_max_entries(mut self, max_entries: u32) -> Self {
+    self.opts.max_entries = max_entries;
+    self
+  }
+
   /// Sets the capacity of the underlying `Log`.
   ///
   /// Default is `1024`. This configuration will be ignored if the map is memory-mapped.
+  /// For newly created memory map backed `Log`, the capacity will be rounded up to the page size of the OS.
   ///
   /// ## Example
   ///
@@ -219,6 +415,138 @@ impl<S> Builder<S> {
     self
   }
 
+  /// Sets the capacity of the underlying `Log` from a `u64`, which is useful when the capacity is computed,
+  /// e.g. from a `usize`, so that an overflowing capacity is not silently truncated.
+  ///
+  /// ## Errors
+  /// - [`Error::CapacityOverflow`] if `capacity` is larger than `u32::MAX`.
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use valog::{error::Error, Builder};
+  ///
+  /// let builder = Builder::new().try_with_capacity(1024).unwrap();
+  /// assert_eq!(builder.capacity(), 1024);
+  ///
+  /// let err = Builder::new().try_with_capacity(1 << 33).unwrap_err();
+  /// assert!(matches!(err, Error::CapacityOverflow { capacity } if capacity == 1 << 33));
+  /// ```
+  #[inline]
+  pub fn try_with_capacity(mut self, capacity: u64) -> Result<Self, Error> {
+    self.opts = self.opts.try_with_capacity(capacity)?;
+    Ok(self)
+  }
+
+  /// Sets the capacity of the underlying `Log`, `None` means let the backend decide.
+  ///
+  /// When reopening a file backed `Log`, `None` means the capacity is inferred from the file size.
+  /// For anonymous memory map backed `Log`, `None` means one page of the OS.
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use valog::Builder;
+  ///
+  /// let builder = Builder::new().with_capacity_opt(None);
+  /// assert_eq!(builder.capacity_opt(), None);
+  /// ```
+  #[inline]
+  pub const fn with_capacity_opt(mut self, capacity: Option<u32>) -> Self {
+    self.opts.capacity = capacity;
+    self
+  }
+
+  /// Set the length of the checksum digest stored after each value.
+  ///
+  /// The checksum length is recorded in the header of the value log. When reopening a file backed value log,
+  /// an explicitly set checksum length must match the one in the header, otherwise the checksum length in
+  /// the header is used.
+  ///
+  /// The default value is [`ChecksumLen::Eight`].
+  ///
+  /// ## Example
+  ///
+  /// ```rust
+  /// use valog::{Builder, options::ChecksumLen};
+  ///
+  /// let builder = Builder::new().with_checksum_len(ChecksumLen::Four);
+  /// ```
+  #[inline]
+  pub const fn with_checksum_len(mut self, checksum_len: ChecksumLen) -> Self {
+    self.opts.checksum_len = Some(checksum_len);
+    self
+  }
+
+  /// Sets the alignment of the start offset of each value.
+  ///
+  /// Each value will be stored at an offset which is a multiple of `align`, the padding bytes
+  /// wasted by the alignment are counted toward the discarded bytes of the value log.
+  ///
+  /// See [`Options::with_value_alignment`] for the alignment of the slices returned by reads.
+  ///
+  /// The default value is `1`.
+  ///
+  /// ## Panics
+  ///
+  /// - If `align` is not a power of 2.
+  /// - If `align` is larger than [`MAX_VALUE_ALIGNMENT`](crate::options::MAX_VALUE_ALIGNMENT).
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use valog::Builder;
+  ///
+  /// let builder = Builder::new().with_value_alignment(8);
+  /// ```
+  #[inline]
+  pub const fn with_value_alignment(mut self, align: usize) -> Self {
+    self.opts = self.opts.with_value_alignment(align);
+    self
+  }
+
+  /// Sets the size of the write buffer used by [`BufferedWriter`](crate::BufferedWriter).
+  ///
+  /// A [`BufferedWriter`](crate::BufferedWriter) reserves `size` bytes from the log up front,
+  /// and inserts the small values into the reserved space, so that the allocation and the
+  /// flush are paid once per `size` bytes instead of once per value.
+  ///
+  /// The default value is `0`, which means the values are inserted one by one.
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use valog::Builder;
+  ///
+  /// let builder = Builder::new().with_write_buffer(4096);
+  /// ```
+  #[inline]
+  pub const fn with_write_buffer(mut self, size: u32) -> Self {
+    self.opts.write_buffer = size;
+    self
+  }
+
+  /// Sets the cipher which encrypts the values of the log at rest.
+  ///
+  /// See [`Options::with_cipher`] for the layout of the encrypted values and which reads decrypt them.
+  ///
+  /// The default value is `None`, which means the values are stored in plain.
+  ///
+  /// ## Example
+  ///
+  /// ```rust
+  /// use valog::{options::Cipher, Builder};
+  ///
+  /// let builder = Builder::new().with_cipher(Cipher::Aes256Gcm { key: [7; 32] });
+  /// ```
+  #[cfg(feature = "encryption")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "encryption")))]
+  #[inline]
+  pub const fn with_cipher(mut self, cipher: Cipher) -> Self {
+    self.opts.cipher = Some(cipher);
+    self
+  }
+
   /// Get the reserved of the `Log`.
   ///
   /// The reserved is used to configure the start position of the `Log`. This is useful
@@ -241,6 +569,40 @@ impl<S> Builder<S> {
     self.opts.reserved
   }
 
+  /// Get the sizes of the reserved regions of the `Log`.
+  ///
+  /// ## Example
+  ///
+  /// ```rust
+  /// use valog::Builder;
+  ///
+  /// let builder = Builder::new().with_reserved_regions(&[8, 16]);
+  ///
+  /// assert_eq!(builder.reserved_regions(), &[8, 16]);
+  /// ```
+  #[inline]
+  pub const fn reserved_regions(&self) -> &[u32] {
+    self.opts.reserved_regions()
+  }
+
+  /// Get the length of the checksum digest stored after each value.
+  ///
+  /// The default value is [`ChecksumLen::Eight`].
+  ///
+  /// ## Example
+  ///
+  /// ```rust
+  /// use valog::{Builder, options::ChecksumLen};
+  ///
+  /// let builder = Builder::new().with_checksum_len(ChecksumLen::Four);
+  ///
+  /// assert_eq!(builder.checksum_len(), ChecksumLen::Four);
+  /// ```
+  #[inline]
+  pub const fn checksum_len(&self) -> ChecksumLen {
+    self.opts.checksum_len()
+  }
+
   /// Get if flush the data to the disk when new value is inserted.
   ///
   /// Default is `true`.
@@ -259,6 +621,60 @@ impl<S> Builder<S> {
     self.opts.sync
   }
 
+  /// Get when the header is flushed together with the synced values.
+  ///
+  /// Default is [`HeaderSync::Always`].
+  ///
+  /// ## Example
+  ///
+  /// ```rust
+  /// use valog::{options::HeaderSync, Builder};
+  ///
+  /// let builder = Builder::new().with_header_sync(HeaderSync::OnClose);
+  ///
+  /// assert_eq!(builder.header_sync(), HeaderSync::OnClose);
+  /// ```
+  #[inline]
+  pub const fn header_sync(&self) -> HeaderSync {
+    self.opts.header_sync
+  }
+
+  /// Get how the synced values are flushed to the disk.
+  ///
+  /// Default is [`SyncStrategy::Msync`].
+  ///
+  /// ## Example
+  ///
+  /// ```rust
+  /// use valog::{options::SyncStrategy, Builder};
+  ///
+  /// let builder = Builder::new().with_sync_strategy(SyncStrategy::Fdatasync);
+  ///
+  /// assert_eq!(builder.sync_strategy(), SyncStrategy::Fdatasync);
+  /// ```
+  #[inline]
+  pub const fn sync_strategy(&self) -> SyncStrategy {
+    self.opts.sync_strategy
+  }
+
+  /// Get if validate the checksum of the value when reading the value.
+  ///
+  /// Default is `true`.
+  ///
+  /// ## Example
+  ///
+  /// ```rust
+  /// use valog::Builder;
+  ///
+  /// let builder = Builder::new().with_validate_checksum(false);
+  ///
+  /// assert_eq!(builder.validate_checksum(), false);
+  /// ```
+  #[inline]
+  pub const fn validate_checksum(&self) -> bool {
+    self.opts.validate_checksum
+  }
+
   /// Get if lock the meta of the `Log` in the memory to prevent OS from swapping out the first page of `Log`.
   /// When using memory map backed `Log`, the meta of the `Log`
   /// is in the first page, meta is frequently accessed,
@@ -297,6 +713,40 @@ impl<S> Builder<S> {
     self.opts.max_value_size
   }
 
+  /// Returns what happens when inserting a value larger than the [`maximum_value_size`](Builder::maximum_value_size).
+  ///
+  /// Default is [`OversizePolicy::Error`].
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use valog::{options::OversizePolicy, Builder};
+  ///
+  /// let builder = Builder::new().with_oversize_policy(OversizePolicy::Truncate);
+  /// assert_eq!(builder.oversize_policy(), OversizePolicy::Truncate);
+  /// ```
+  #[inline]
+  pub const fn oversize_policy(&self) -> OversizePolicy {
+    self.opts.oversize_policy
+  }
+
+  /// Returns the maximum number of the non-empty values can be inserted into the log.
+  ///
+  /// Default is `u32::MAX`, which means unlimited.
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use valog::Builder;
+  ///
+  /// let builder = Builder::new().with_max_entries(128);
+  /// assert_eq!(builder.max_entries(), 128);
+  /// ```
+  #[inline]
+  pub const fn max_entries(&self) -> u32 {
+    self.opts.max_entries
+  }
+
   /// Returns the configuration of underlying `Log` size.
   ///
   /// Default is `1024`. This configuration will be ignored if the map is memory-mapped.
@@ -314,6 +764,21 @@ impl<S> Builder<S> {
     self.opts.capacity()
   }
 
+  /// Returns the configuration of underlying `Log` size, `None` means let the backend decide.
+  ///
+  /// ## Example
+  ///
+  /// ```rust
+  /// use valog::Builder;
+  ///
+  /// let builder = Builder::new().with_capacity(1024);
+  /// assert_eq!(builder.capacity_opt(), Some(1024));
+  /// ```
+  #[inline]
+  pub const fn capacity_opt(&self) -> Option<u32> {
+    self.opts.capacity_opt()
+  }
+
   /// Get if use the unify memory layout of the value log.
   ///
   /// File backed value log has different memory layout with other kind backed value log,
@@ -377,9 +842,65 @@ impl<S> Builder<S> {
   pub const fn freelist(&self) -> Freelist {
     self.opts.freelist
   }
+
+  /// Returns the alignment of the start offset of each value.
+  ///
+  /// The default value is `1`.
+  ///
+  /// ## Example
+  ///
+  /// ```rust
+  /// use valog::Builder;
+  ///
+  /// let builder = Builder::new().with_value_alignment(8);
+  ///
+  /// assert_eq!(builder.value_alignment(), 8);
+  /// ```
+  #[inline]
+  pub const fn value_alignment(&self) -> usize {
+    self.opts.value_alignment
+  }
+
+  /// Returns the size of the write buffer used by [`BufferedWriter`](crate::BufferedWriter).
+  ///
+  /// The default value is `0`.
+  ///
+  /// ## Example
+  ///
+  /// ```rust
+  /// use valog::Builder;
+  ///
+  /// let builder = Builder::new().with_write_buffer(4096);
+  ///
+  /// assert_eq!(builder.write_buffer(), 4096);
+  /// ```
+  #[inline]
+  pub const fn write_buffer(&self) -> u32 {
+    self.opts.write_buffer
+  }
+
+  /// Returns the cipher which encrypts the values of the log at rest.
+  ///
+  /// The default value is `None`.
+  ///
+  /// ## Example
+  ///
+  /// ```rust
+  /// use valog::{options::Cipher, Builder};
+  ///
+  /// let builder = Builder::new().with_cipher(Cipher::Aes256Gcm { key: [7; 32] });
+  ///
+  /// assert_eq!(builder.cipher(), Some(Cipher::Aes256Gcm { key: [7; 32] }));
+  /// ```
+  #[cfg(feature = "encryption")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "encryption")))]
+  #[inline]
+  pub const fn cipher(&self) -> Option<Cipher> {
+    self.opts.cipher
+  }
 }
 
-impl<S: BuildChecksumer> Builder<S> {
+impl<S: BuildChecksumer + ChecksumAlgo> Builder<S> {
   /// Create a new in-memory value log which is backed by a `AlignedVec`.
   ///
   /// **What the difference between this method and [`Builder::map_anon`]?**
@@ -411,22 +932,219 @@ impl<S: BuildChecksumer> Builder<S> {
     C: Constructor<Checksumer = S> + Mutable,
   {
     let Self { opts, cks } = self;
+    opts.validate()?;
 
     let unify = opts.unify;
-    let mv = opts.magic_version;
     opts
       .to_arena_options()
       .alloc::<C::Allocator>()
-      .map_err(Error::from_insufficient_space)
+      .map_err(Error::from)
       .map(|arena| {
         if unify {
           unsafe {
             let slice = arena.reserved_slice_mut();
-            write_header(slice, mv);
+            write_header(
+              slice,
+              &opts,
+              HeaderTags::new::<S, C::Id>(),
+              arena.data_offset() as u32,
+            );
           }
         }
 
+        #[cfg(feature = "encryption")]
+        let opts = {
+          let mut opts = opts;
+          super::renew_salt(&arena, &mut opts);
+          opts
+        };
+
         C::construct(fid, arena, cks, opts)
       })
   }
+
+  /// Creates a value log over an already-built allocator, which is restricted to the `range` of the allocator.
+  ///
+  /// This can be used to construct several logical value logs over one allocator (the allocator is cheap to clone),
+  /// e.g. to carve a large file into logical segments without separate files. Reads out of `range`
+  /// return [`Error::OutOfBounds`], and inserts which would be placed out of `range` return
+  /// [`Error::InsufficientSpace`].
+  ///
+  /// If the allocator is writable, the unallocated part of `range` is claimed from the allocator when
+  /// the log is built (the unallocated bytes before `range` are claimed as discarded bytes), and the
+  /// values are allocated by the log in its own range, so the logs over the disjoint sub-ranges can be
+  /// written in any order without taking the space of each other. The bytes of `range` which are already
+  /// allocated are treated as the written values of the log. Returns [`Error::Overlap`] if the range cannot
+  /// be claimed at the end of the allocator, e.g. the space is allocated concurrently or taken from the
+  /// freelist. The header of the value log is not written or checked.
+  ///
+  /// ## Example
+  ///
+  /// ```rust
+  /// use rarena_allocator::{sync::Arena, Allocator, Options};
+  /// use valog::{sync::ValueLog, Builder, Log, LogReader, LogWriter};
+  ///
+  /// let arena = Options::new().with_capacity(1024).alloc::<Arena>().unwrap();
+  /// let start = arena.data_offset() as u32;
+  ///
+  /// let a = Builder::new()
+  ///   .with_external_arena::<ValueLog>(arena.clone(), start..start + 512, 0)
+  ///   .unwrap();
+  /// let b = Builder::new()
+  ///   .with_external_arena::<ValueLog>(arena, start + 512..1024, 1)
+  ///   .unwrap();
+  ///
+  /// let vp = a.insert(b"Hello, valog!").unwrap();
+  /// let data = unsafe { a.read(a.id(), vp.offset(), vp.size()).unwrap() };
+  /// assert_eq!(data, b"Hello, valog!");
+  /// ```
+  pub fn with_external_arena<C>(
+    self,
+    arena: C::Allocator,
+    range: core::ops::Range<u32>,
+    fid: C::Id,
+  ) -> Result<C, Error>
+  where
+    C: Constructor<Checksumer = S> + Mutable,
+  {
+    let Self { mut opts, cks } = self;
+
+    // the header of the value log is not written, so there is no place to record the salt of the nonces.
+    #[cfg(feature = "encryption")]
+    if opts.cipher.is_some() {
+      return Err(Error::invalid_options(
+        "the encryption requires the header of the value log",
+      ));
+    }
+
+    let data_offset = arena.data_offset() as u32;
+    let capacity = arena.capacity() as u32;
+    if range.start < data_offset || range.end > capacity || range.start > range.end {
+      return Err(Error::out_of_bounds(
+        range.start,
+        range.end.saturating_sub(range.start),
+        data_offset,
+        capacity,
+      ));
+    }
+
+    opts.sub_range = Some((range.start, range.end));
+    opts.capacity = Some(range.end - range.start);
+
+    let allocated = arena.allocated() as u32;
+    if !arena.read_only() && allocated < range.end {
+      let mut buf = arena
+        .alloc_bytes(range.end - allocated)
+        .map_err(Error::from)?;
+      if buf.offset() != allocated as usize {
+        // dropping the buffer gives the space back to the allocator
+        return Err(Error::overlap(range.start, arena.allocated() as u32));
+      }
+      arena.increase_discarded(range.start.saturating_sub(allocated));
+      // Safety: the range is used by the log, no need to drop
+      unsafe {
+        buf.detach();
+      }
+    }
+
+    let log = C::construct(fid, arena, cks, opts);
+    if let Some(cursor) = log.range_cursor() {
+      cursor.store(
+        allocated.clamp(range.start, range.end),
+        core::sync::atomic::Ordering::Release,
+      );
+    }
+    Ok(log)
+  }
+
+  /// Create a new in-memory value log which is initialized from the given bytes.
+  ///
+  /// The bytes are treated as an already-initialized value log in the unified memory layout,
+  /// e.g. the output of [`LogExt::flush_to`](crate::LogExt::flush_to) for a log created with
+  /// [`with_unify(true)`](Builder::with_unify), or the content of a file backed value log.
+  /// The header of the value log is checked, and all bytes after the data offset are treated as allocated.
+  ///
+  /// The bytes are copied into a fresh `AlignedVec`, and the capacity of the log is the larger one of
+  /// the configured capacity and the length of the bytes. This is useful for fuzzing the read path
+  /// without touching the file system.
+  ///
+  /// Only the header and the reserved bytes (`..HEADER_SIZE + reserved`) and the values (`data_offset..`)
+  /// are copied. The allocator metadata in between is initialized by the new allocator instead of being
+  /// taken from the bytes, so that arbitrary input cannot corrupt the allocator state. Hence
+  /// [`Log::as_slice`](crate::Log::as_slice) of the new log only equals `bytes` outside of that range.
+  ///
+  /// ## Example
+  ///
+  /// ```rust
+  /// use valog::{sync::ValueLog, Builder, Log, LogExt, LogReader, LogWriter};
+  ///
+  /// let log = Builder::new()
+  ///   .with_capacity(1024)
+  ///   .with_unify(true)
+  ///   .alloc::<ValueLog>(1)
+  ///   .unwrap();
+  /// let vp = log.insert(b"Hello, valog!").unwrap();
+  ///
+  /// let mut buf = Vec::new();
+  /// log.flush_to(&mut buf).unwrap();
+  ///
+  /// let log = Builder::new().from_bytes::<ValueLog>(&buf, 1).unwrap();
+  /// let data = unsafe { log.read(log.id(), vp.offset(), vp.size()).unwrap() };
+  /// assert_eq!(data, b"Hello, valog!");
+  /// ```
+  pub fn from_bytes<C>(self, bytes: &[u8], fid: C::Id) -> Result<C, Error>
+  where
+    C: Constructor<Checksumer = S>,
+  {
+    let Self { mut opts, cks } = self;
+
+    let len = u32::try_from(bytes.len()).map_err(|_| Error::InvalidLayout)?;
+    let cap = opts.capacity();
+    let arena = opts
+      .to_arena_options()
+      .with_unify(true)
+      .with_capacity(cap.max(len))
+      .alloc::<C::Allocator>()
+      .map_err(|e| {
+        // the bytes are too short to hold even the header of the value log.
+        if len > cap {
+          Error::InvalidLayout
+        } else {
+          Error::from(e)
+        }
+      })?;
+
+    let reserved = opts.header_size() + opts.reserved() as usize;
+    let data_offset = arena.data_offset();
+    if bytes.len() < data_offset {
+      return Err(Error::InvalidLayout);
+    }
+
+    // Safety: the arena is freshly allocated with enough capacity, and the header of the arena
+    // is not touched, so the allocator state cannot be corrupted by the given bytes.
+    unsafe {
+      let ptr = arena.raw_mut_ptr();
+      core::ptr::copy_nonoverlapping(bytes.as_ptr(), ptr, reserved);
+      core::ptr::copy_nonoverlapping(
+        bytes.as_ptr().add(data_offset),
+        ptr.add(data_offset),
+        bytes.len() - data_offset,
+      );
+    }
+
+    check_header(
+      arena.reserved_slice(),
+      &mut opts,
+      HeaderTags::new::<S, C::Id>(),
+    )?;
+    #[cfg(feature = "encryption")]
+    super::renew_salt(&arena, &mut opts);
+
+    // Safety: `len` is in the bounds of the arena.
+    unsafe {
+      arena.rewind(ArenaPosition::Start(len));
+    }
+
+    Ok(C::construct(fid, arena, cks, opts))
+  }
 }
@@ -1,8 +1,8 @@
 use rarena_allocator::{either::Either, Allocator};
 
 use super::{
-  super::error::{bad_magic_text, bad_magic_version, Error},
-  write_header, Builder, Options, HEADER_SIZE, MAGIC_TEXT, MAGIC_TEXT_SIZE,
+  super::error::Error, check_existing, check_header, committed, recorded_data_offset, write_header,
+  Builder, ChecksumAlgo, HeaderTags, Options, SyncStrategy, HEADER_SIZE,
 };
 use crate::{sealed::Constructor, Frozen, Mutable};
 
@@ -177,6 +177,29 @@ impl Options {
     self
   }
 
+  /// Sets the option to create the missing parent directories of the file, like [`std::fs::create_dir_all`],
+  /// before a map is created by [`Builder::map_mut`] or [`Builder::map_mut_with_path_builder`].
+  ///
+  /// By default, this option is disabled, and opening a file in a missing directory fails.
+  ///
+  /// ## Examples
+  ///
+  /// ```rust
+  /// use valog::options::Options;
+  ///
+  /// let opts = Options::new()
+  ///   .with_write(true)
+  ///   .with_create(true)
+  ///   .with_create_dir(true);
+  /// ```
+  #[inline]
+  #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+  #[cfg_attr(docsrs, doc(cfg(all(feature = "memmap", not(target_family = "wasm")))))]
+  pub fn with_create_dir(mut self, val: bool) -> Self {
+    self.create_dir = val;
+    self
+  }
+
   /// Configures the memory map to start at byte `offset` from the beginning of the file.
   ///
   /// This option has no effect on anonymous memory maps or vec backed `Log`.
@@ -266,6 +289,103 @@ impl Options {
     self.populate = populate;
     self
   }
+
+  /// Set if bypass the page cache for the file backed `Log` after bulk scans.
+  ///
+  /// A memory map cannot be opened with `O_DIRECT`, so instead, when this option is enabled, the mapped pages of the
+  /// `Log` are paged out (`MADV_PAGEOUT`, falling back to `MADV_DONTNEED`) after bulk scans,
+  /// e.g. [`LogReader::scrub`](crate::LogReader::scrub), or explicitly by [`Log::evict_page_cache`](crate::Log::evict_page_cache),
+  /// so that the page cache is kept warm for the other hot data.
+  ///
+  /// This option only works on Linux and Android, and has no effect on anonymous memory map and vec backed `Log`.
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use valog::options::Options;
+  ///
+  /// let opts = Options::new().with_direct_io(true);
+  /// ```
+  #[inline]
+  #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+  #[cfg_attr(docsrs, doc(cfg(all(feature = "memmap", not(target_family = "wasm")))))]
+  pub fn with_direct_io(mut self, direct_io: bool) -> Self {
+    self.direct_io = direct_io;
+    self
+  }
+
+  /// Sets the number of extra bytes read ahead by each positioned read of [`PreadValueLog`](crate::PreadValueLog).
+  ///
+  /// When it is not `0`, each read which misses the buffered window reads the requested entry plus `read_ahead`
+  /// bytes after it, and keeps them for the next reads, so a forward scan issues much fewer `pread`s.
+  /// The other backends are not affected.
+  ///
+  /// By default, the read ahead is `0`, which reads the exact bytes of the entries.
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use valog::options::Options;
+  ///
+  /// let opts = Options::new().with_read_ahead(64 * 1024);
+  /// ```
+  #[inline]
+  #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+  #[cfg_attr(docsrs, doc(cfg(all(feature = "memmap", not(target_family = "wasm")))))]
+  pub fn with_read_ahead(mut self, read_ahead: u32) -> Self {
+    self.read_ahead = read_ahead;
+    self
+  }
+
+  /// Set if grow the anonymous memory map backed `Log` when there is not enough space.
+  ///
+  /// When this option is enabled, [`LogWriter::insert_or_grow`](crate::LogWriter::insert_or_grow) creates a new
+  /// larger anonymous memory map (at least double the capacity), copies the old contents to it and swaps it in,
+  /// instead of returning [`Error::InsufficientSpace`].
+  /// The existing value pointers remain valid, because they are offset-relative.
+  ///
+  /// This option has no effect on file backed memory map and vec backed `Log`.
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use valog::options::Options;
+  ///
+  /// let opts = Options::new().with_anon_grow(true);
+  /// ```
+  #[inline]
+  #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+  #[cfg_attr(docsrs, doc(cfg(all(feature = "memmap", not(target_family = "wasm")))))]
+  pub fn with_anon_grow(mut self, anon_grow: bool) -> Self {
+    self.anon_grow = anon_grow;
+    self
+  }
+
+  /// Set if lock the whole memory map of the `Log` in the memory, after the `Log` is mapped.
+  ///
+  /// Unlike [`with_lock_meta`](Options::with_lock_meta) which only locks the first page, this option `mlock`s
+  /// `0..capacity`, so that reading and writing the `Log` never page-fault, which is useful for real-time systems.
+  /// It composes with [`with_lock_meta`](Options::with_lock_meta).
+  ///
+  /// **Warning:** the locked memory cannot be swapped out, locking large logs puts pressure on the memory of the system,
+  /// and mapping fails if the locked memory exceeds the limit of the process (e.g. `RLIMIT_MEMLOCK` on Linux).
+  ///
+  /// Default is `false`. This option has no effect on windows and vec backed `Log`.
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use valog::options::Options;
+  ///
+  /// let opts = Options::new().with_lock_all(true);
+  /// ```
+  #[inline]
+  #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+  #[cfg_attr(docsrs, doc(cfg(all(feature = "memmap", not(target_family = "wasm")))))]
+  pub fn with_lock_all(mut self, lock_all: bool) -> Self {
+    self.lock_all = lock_all;
+    self
+  }
 }
 
 impl Options {
@@ -371,6 +491,23 @@ impl Options {
     self.create_new
   }
 
+  /// Returns `true` if the missing parent directories of the file should be created.
+  ///
+  /// ## Examples
+  ///
+  /// ```rust
+  /// use valog::options::Options;
+  ///
+  /// let opts = Options::new().with_create_dir(true);
+  /// assert_eq!(opts.create_dir(), true);
+  /// ```
+  #[inline]
+  #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+  #[cfg_attr(docsrs, doc(cfg(all(feature = "memmap", not(target_family = "wasm")))))]
+  pub const fn create_dir(&self) -> bool {
+    self.create_dir
+  }
+
   /// Returns the offset of the memory map.
   ///
   /// ## Examples
@@ -438,9 +575,77 @@ impl Options {
   pub const fn populate(&self) -> bool {
     self.populate
   }
+
+  /// Returns `true` if bypass the page cache for the file backed `Log` after bulk scans.
+  ///
+  /// ## Examples
+  ///
+  /// ```rust
+  /// use valog::options::Options;
+  ///
+  /// let opts = Options::new().with_direct_io(true);
+  /// assert_eq!(opts.direct_io(), true);
+  /// ```
+  #[inline]
+  #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+  #[cfg_attr(docsrs, doc(cfg(all(feature = "memmap", not(target_family = "wasm")))))]
+  pub const fn direct_io(&self) -> bool {
+    self.direct_io
+  }
+
+  /// Returns the number of extra bytes read ahead by each positioned read of [`PreadValueLog`](crate::PreadValueLog).
+  ///
+  /// ## Examples
+  ///
+  /// ```rust
+  /// use valog::options::Options;
+  ///
+  /// let opts = Options::new().with_read_ahead(64 * 1024);
+  /// assert_eq!(opts.read_ahead(), 64 * 1024);
+  /// ```
+  #[inline]
+  #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+  #[cfg_attr(docsrs, doc(cfg(all(feature = "memmap", not(target_family = "wasm")))))]
+  pub const fn read_ahead(&self) -> u32 {
+    self.read_ahead
+  }
+
+  /// Returns `true` if grow the anonymous memory map backed `Log` when there is not enough space.
+  ///
+  /// ## Examples
+  ///
+  /// ```rust
+  /// use valog::options::Options;
+  ///
+  /// let opts = Options::new().with_anon_grow(true);
+  /// assert_eq!(opts.anon_grow(), true);
+  /// ```
+  #[inline]
+  #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+  #[cfg_attr(docsrs, doc(cfg(all(feature = "memmap", not(target_family = "wasm")))))]
+  pub const fn anon_grow(&self) -> bool {
+    self.anon_grow
+  }
+
+  /// Returns `true` if lock the whole memory map of the `Log` in the memory.
+  ///
+  /// ## Examples
+  ///
+  /// ```rust
+  /// use valog::options::Options;
+  ///
+  /// let opts = Options::new().with_lock_all(true);
+  /// assert_eq!(opts.lock_all(), true);
+  /// ```
+  #[inline]
+  #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+  #[cfg_attr(docsrs, doc(cfg(all(feature = "memmap", not(target_family = "wasm")))))]
+  pub const fn lock_all(&self) -> bool {
+    self.lock_all
+  }
 }
 
-impl<S> Builder<S> {
+impl<S: ChecksumAlgo> Builder<S> {
   /// Create a new in-memory value log which is backed by a anonymous memory map.
   ///
   /// **What the difference between this method and [`Builder::alloc`]?**
@@ -476,26 +681,47 @@ impl<S> Builder<S> {
     C: Constructor<Checksumer = S> + Mutable,
   {
     let Self { opts, cks } = self;
+    opts.validate().map_err(Error::into_io_err)?;
+    let opts = opts
+      .with_default_anon_capacity()
+      .with_page_aligned_capacity();
     let unify = opts.unify;
-    let mv = opts.magic_version;
     opts
       .to_arena_options()
       .map_anon::<C::Allocator>()
-      .map_err(Into::into)
-      .map(|arena| {
+      .and_then(|arena| {
         if unify {
           unsafe {
             let slice = arena.reserved_slice_mut();
-            write_header(slice, mv);
+            write_header(
+              slice,
+              &opts,
+              HeaderTags::new::<S, C::Id>(),
+              arena.data_offset() as u32,
+            );
           }
         }
 
-        C::construct(fid, arena, cks, opts)
+        #[cfg(feature = "encryption")]
+        let opts = {
+          let mut opts = opts;
+          super::renew_salt(&arena, &mut opts);
+          opts
+        };
+
+        lock_all(&arena, &opts)?;
+        Ok(C::construct(fid, arena, cks, opts))
       })
   }
 
   /// Opens a read-only map which backed by file-backed memory map.
   ///
+  /// The options which affect the on-disk layout (the [`reserved`](Options::reserved) bytes and an explicitly
+  /// configured [`checksum_len`](Options::checksum_len)) are checked against the file, and a discrepancy is
+  /// returned as [`Error::OptionsMismatch`] wrapped in an error of
+  /// [`InvalidInput`](std::io::ErrorKind::InvalidInput). The default checksum length adopts the one recorded
+  /// in the file. The value alignment is not recorded in the file, so it cannot be checked.
+  ///
   /// ## Safety
   /// - All file-backed memory map constructors are marked `unsafe` because of the potential for
   ///   *Undefined Behavior* (UB) using the map if the underlying file is subsequently modified, in or
@@ -598,24 +824,34 @@ impl<S> Builder<S> {
     C: Constructor<Checksumer = S> + Frozen,
     PB: FnOnce() -> Result<std::path::PathBuf, E>,
   {
-    let Self { opts, cks } = self;
-
-    let magic_version = opts.magic_version();
+    let Self { mut opts, cks } = self;
 
+    let path = path_builder().map_err(Either::Left)?;
+    Self::check_existing::<C::Id>(&path, &mut opts, false).map_err(Either::Right)?;
     opts
       .to_arena_options()
       .with_unify(true)
-      .map_with_path_builder::<C::Allocator, _, _>(path_builder)
-      .map_err(|e| e.map_right(Error::from_arena_io_err))
+      .map::<C::Allocator, _>(&path)
+      .map_err(|e| Either::Right(Error::from_arena_io_err(e)))
       .and_then(|arena| {
-        Self::check_header(arena.reserved_slice(), magic_version)
-          .map(|_| C::construct(fid, arena, cks, opts.with_magic_version(magic_version)))
+        Self::check_header::<C::Id>(arena.reserved_slice(), &mut opts)
+          .and_then(|_| lock_all(&arena, &opts))
+          .map(|_| {
+            let mut log = C::construct(fid, arena, cks, opts);
+            log.set_shared_file_path(path.clone());
+            log
+          })
           .map_err(Either::Right)
       })
   }
 
   /// Creates a new map or reopens a map which backed by a file backed memory map.
   ///
+  /// When reopening an existing file, the allocated length recorded in the file is recovered,
+  /// so the new values are appended after the existing ones. The options which affect the on-disk
+  /// layout are checked against the file the same as [`map`](Builder::map), and the
+  /// [`freelist`](Options::freelist) is checked as well.
+  ///
   /// ## Safety
   /// - All file-backed memory map constructors are marked `unsafe` because of the potential for
   ///   *Undefined Behavior* (UB) using the map if the underlying file is subsequently modified, in or
@@ -698,41 +934,430 @@ impl<S> Builder<S> {
     PB: FnOnce() -> Result<std::path::PathBuf, E>,
   {
     let Self { opts, cks } = self;
+    opts
+      .validate()
+      .map_err(|e| Either::Right(e.into_io_err()))?;
+    let mut opts = opts.with_page_aligned_capacity();
 
-    let magic_version = opts.magic_version();
     let path = path_builder().map_err(Either::Left)?;
+    if opts.create_dir {
+      if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent).map_err(Either::Right)?;
+      }
+    }
+
     let exist = path.exists();
+    if exist {
+      Self::check_existing::<C::Id>(&path, &mut opts, true).map_err(Either::Right)?;
+    }
 
     opts
       .to_arena_options()
       .with_unify(true)
-      .map_mut::<C::Allocator, _>(path)
-      .map_err(|e| Either::Right(crate::error::Error::from_arena_io_err(e)))
+      .map_mut::<C::Allocator, _>(&path)
+      .map_err(|e| Either::Right(Error::from_arena_io_err(e)))
       .and_then(|arena| {
         if !exist {
-          write_header(arena.reserved_slice_mut(), magic_version);
+          write_header(
+            arena.reserved_slice_mut(),
+            &opts,
+            HeaderTags::new::<S, C::Id>(),
+            arena.data_offset() as u32,
+          );
         } else {
-          Self::check_header(arena.reserved_slice(), magic_version).map_err(Either::Right)?;
+          Self::check_header::<C::Id>(arena.reserved_slice(), &mut opts).map_err(Either::Right)?;
         }
+        #[cfg(feature = "encryption")]
+        super::renew_salt(&arena, &mut opts);
 
-        let log = C::construct(fid, arena, cks, opts);
+        lock_all(&arena, &opts).map_err(Either::Right)?;
+        let mut log = C::construct(fid, arena, cks, opts);
+        log.set_shared_file_path(path.clone());
+        if opts.sync_strategy != SyncStrategy::Msync {
+          let file = std::fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(&path)
+            .map_err(Either::Right)?;
+          log.set_sync_file(file);
+        }
         Ok(log)
       })
   }
 
+  /// Opens a map which backed by a copy-on-write memory map of an existing file.
+  ///
+  /// This is the `MAP_PRIVATE` analog of [`map_mut`](Builder::map_mut). The reads see the data of the file,
+  /// but the values inserted are written to the private pages of the process, which are never carried
+  /// through to the file and are discarded when the log is dropped. So a shared base log can be opened by
+  /// many processes for speculative writes without copying the whole file.
+  ///
+  /// The file is opened read-only, so it must exist and the log cannot grow beyond the file, the
+  /// [`capacity`](Options::capacity), the open flags and [`sync`](Options::sync) are ignored. The options which
+  /// affect the on-disk layout are checked against the file the same as [`map_mut`](Builder::map_mut).
+  ///
+  /// ## Safety
+  /// - All file-backed memory map constructors are marked `unsafe` because of the potential for
+  ///   *Undefined Behavior* (UB) using the map if the underlying file is subsequently modified, in or
+  ///   out of process. Applications must consider the risk and take appropriate precautions when
+  ///   using file-backed maps. Solutions such as file permissions, locks or process-private (e.g.
+  ///   unlinked) files exist but are platform specific and limited.
+  ///
+  /// ## Example
+  ///
+  /// ```rust
+  /// use valog::{sync, Builder, Log, LogReader, LogWriter};
+  ///
+  /// let dir = tempfile::tempdir().unwrap();
+  /// let path = dir.path().join("map_cow_example.vlog");
+  /// let foo = unsafe {
+  ///   let log = Builder::new()
+  ///     .with_capacity(1024)
+  ///     .with_create_new(true)
+  ///     .with_read(true)
+  ///     .with_write(true)
+  ///     .map_mut::<sync::ValueLog, _>(&path, 1u32)
+  ///     .unwrap();
+  ///   log.insert(b"foo").unwrap()
+  /// };
+  ///
+  /// let bar = unsafe {
+  ///   let log = Builder::new().map_cow::<sync::ValueLog, _>(&path, 1u32).unwrap();
+  ///   assert_eq!(log.read(log.id(), foo.offset(), foo.size()).unwrap(), b"foo");
+  ///
+  ///   let bar = log.insert(b"bar").unwrap();
+  ///   assert_eq!(log.read(log.id(), bar.offset(), bar.size()).unwrap(), b"bar");
+  ///   bar
+  /// };
+  ///
+  /// // the value inserted through the copy-on-write map does not reach the file
+  /// let log = unsafe {
+  ///   Builder::new()
+  ///     .with_read(true)
+  ///     .map::<sync::ImmutableValueLog, _>(&path, 1u32)
+  ///     .unwrap()
+  /// };
+  /// assert!(unsafe { log.read(log.id(), bar.offset(), bar.size()) }.is_err());
+  /// ```
+  #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+  #[cfg_attr(docsrs, doc(cfg(all(feature = "memmap", not(target_family = "wasm")))))]
   #[inline]
-  fn check_header(buf: &[u8], magic_version: u16) -> std::io::Result<u16> {
-    if buf[..MAGIC_TEXT_SIZE] != MAGIC_TEXT {
-      return Err(bad_magic_text());
+  pub unsafe fn map_cow<C, P: AsRef<std::path::Path>>(
+    self,
+    path: P,
+    fid: C::Id,
+  ) -> std::io::Result<C>
+  where
+    C: Constructor<Checksumer = S> + Mutable,
+  {
+    self
+      .map_cow_with_path_builder::<C, _, ()>(|| Ok(path.as_ref().to_path_buf()), fid)
+      .map_err(Either::unwrap_right)
+  }
+
+  /// Opens a map which backed by a copy-on-write memory map of an existing file with a path builder.
+  ///
+  /// See [`map_cow`](Builder::map_cow) for the details.
+  ///
+  /// ## Safety
+  /// - All file-backed memory map constructors are marked `unsafe` because of the potential for
+  ///   *Undefined Behavior* (UB) using the map if the underlying file is subsequently modified, in or
+  ///   out of process. Applications must consider the risk and take appropriate precautions when
+  ///   using file-backed maps. Solutions such as file permissions, locks or process-private (e.g.
+  ///   unlinked) files exist but are platform specific and limited.
+  ///
+  /// ## Example
+  ///
+  /// ```rust
+  /// use valog::{sync, Builder};
+  ///
+  /// let dir = tempfile::tempdir().unwrap();
+  /// let path = dir.path().join("map_cow_with_path_builder_example.vlog");
+  /// unsafe {
+  ///   Builder::new()
+  ///     .with_capacity(1024)
+  ///     .with_create_new(true)
+  ///     .with_read(true)
+  ///     .with_write(true)
+  ///     .map_mut::<sync::ValueLog, _>(&path, 1u32)
+  ///     .unwrap();
+  /// }
+  ///
+  /// let log = unsafe {
+  ///   Builder::new()
+  ///     .map_cow_with_path_builder::<sync::ValueLog, _, ()>(|| Ok(path), 1u32)
+  ///     .unwrap()
+  /// };
+  /// ```
+  #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+  #[cfg_attr(docsrs, doc(cfg(all(feature = "memmap", not(target_family = "wasm")))))]
+  pub unsafe fn map_cow_with_path_builder<C, PB, E>(
+    self,
+    path_builder: PB,
+    fid: C::Id,
+  ) -> Result<C, Either<E, std::io::Error>>
+  where
+    C: Constructor<Checksumer = S> + Mutable,
+    PB: FnOnce() -> Result<std::path::PathBuf, E>,
+  {
+    let Self { mut opts, cks } = self;
+    opts.read = true;
+    opts.write = false;
+    opts.create = false;
+    opts.create_new = false;
+    opts.append = false;
+    opts.truncate = false;
+    // the private pages cannot outgrow the file, and are never flushed to it.
+    opts.capacity = None;
+    opts.sync = false;
+    opts.sync_strategy = SyncStrategy::Msync;
+
+    let path = path_builder().map_err(Either::Left)?;
+    Self::check_existing::<C::Id>(&path, &mut opts, true).map_err(Either::Right)?;
+    opts
+      .to_arena_options()
+      .with_unify(true)
+      .map_copy::<C::Allocator, _>(&path)
+      .map_err(|e| Either::Right(Error::from_arena_io_err(e)))
+      .and_then(|arena| {
+        Self::check_header::<C::Id>(arena.reserved_slice(), &mut opts).map_err(Either::Right)?;
+        #[cfg(feature = "encryption")]
+        super::renew_salt(&arena, &mut opts);
+        lock_all(&arena, &opts).map_err(Either::Right)?;
+        Ok(C::construct(fid, arena, cks, opts))
+      })
+  }
+
+  /// Opens a read-only log which serves reads by positioned reads (`pread`) on the file, instead of memory map.
+  ///
+  /// This is useful for huge cold files which only serve scattered reads, see [`PreadValueLog`](crate::PreadValueLog).
+  /// The log starts at the [`offset`](Builder::offset) of the file, and the file must be created by
+  /// [`map_mut`](Builder::map_mut) with the same [`reserved`](Builder::reserved). Only the values before
+  /// the [`committed`](crate::PreadValueLog::committed) length recorded in the header can be read.
+  ///
+  /// ## Example
+  ///
+  /// ```rust
+  /// use valog::{sync, Builder, PreadValueLog};
+  ///
+  /// let dir = tempfile::tempdir().unwrap();
+  /// let path = dir.path().join("open_pread_example.vlog");
+  ///
+  /// unsafe {
+  ///   Builder::new()
+  ///     .with_capacity(1024)
+  ///     .with_create_new(true)
+  ///     .with_read(true)
+  ///     .with_write(true)
+  ///     .map_mut::<sync::ValueLog, _>(&path, 1u32)
+  ///     .unwrap();
+  /// }
+  ///
+  /// let log: PreadValueLog<u32> = Builder::new().open_pread(&path, 1).unwrap();
+  /// ```
+  pub fn open_pread<I, P: AsRef<std::path::Path>>(
+    self,
+    path: P,
+    fid: I,
+  ) -> std::io::Result<crate::PreadValueLog<I, S>> {
+    let Self { mut opts, cks } = self;
+    let path = path.as_ref();
+    let file = std::fs::File::open(path)?;
+
+    let (data_offset, committed) = Self::committed_layout::<I>(path, &file, &mut opts)?;
+    crate::PreadValueLog::construct(fid, file, cks, opts, data_offset, committed)
+  }
+
+  /// Opens a read-only log which only maps the `range` of the log into memory, the range is in the
+  /// offsets of the value pointers, the end of the range is clamped to the length of the file.
+  ///
+  /// This is useful when all the value pointers to read are in a known small region of a huge file,
+  /// see [`RangeValueLog`](crate::RangeValueLog). The log starts at the [`offset`](Builder::offset)
+  /// of the file, and the file must be created by [`map_mut`](Builder::map_mut) with the same
+  /// [`reserved`](Builder::reserved).
+  ///
+  /// Returns an error of [`InvalidInput`](std::io::ErrorKind::InvalidInput) if the range is empty
+  /// or starts beyond the end of the file.
+  ///
+  /// ## Safety
+  /// - All file-backed memory map constructors are marked `unsafe` because of the potential for
+  ///   *Undefined Behavior* (UB) using the map if the underlying file is subsequently modified, in or
+  ///   out of process. Applications must consider the risk and take appropriate precautions when
+  ///   using file-backed maps. Solutions such as file permissions, locks or process-private (e.g.
+  ///   unlinked) files exist but are platform specific and limited.
+  ///
+  /// ## Example
+  ///
+  /// ```rust
+  /// use valog::{sync, Builder, RangeValueLog};
+  ///
+  /// let dir = tempfile::tempdir().unwrap();
+  /// let path = dir.path().join("open_range_example.vlog");
+  ///
+  /// unsafe {
+  ///   Builder::new()
+  ///     .with_capacity(1024)
+  ///     .with_create_new(true)
+  ///     .with_read(true)
+  ///     .with_write(true)
+  ///     .map_mut::<sync::ValueLog, _>(&path, 1u32)
+  ///     .unwrap();
+  /// }
+  ///
+  /// let log: RangeValueLog<u32> = unsafe { Builder::new().open_range(&path, 512..1024, 1).unwrap() };
+  /// assert_eq!(log.range(), 512..1024);
+  /// ```
+  pub unsafe fn open_range<I, P: AsRef<std::path::Path>>(
+    self,
+    path: P,
+    range: core::ops::Range<u32>,
+    fid: I,
+  ) -> std::io::Result<crate::RangeValueLog<I, S>> {
+    let Self { mut opts, cks } = self;
+    let path = path.as_ref();
+    let file = std::fs::File::open(path)?;
+    let base = opts.offset;
+
+    let (data_offset, committed) = Self::committed_layout::<I>(path, &file, &mut opts)?;
+
+    let size = file.metadata()?.len().saturating_sub(base);
+    let end = (range.end as u64).min(size);
+    if range.start as u64 >= end {
+      return Err(std::io::Error::new(
+        std::io::ErrorKind::InvalidInput,
+        Error::out_of_bounds(
+          range.start,
+          range.end.saturating_sub(range.start),
+          0,
+          size.min(u32::MAX as u64) as u32,
+        ),
+      ));
+    }
+
+    // the offset of the memory map is aligned to the page size by `memmap2`.
+    let map = memmap2::MmapOptions::new()
+      .offset(base + range.start as u64)
+      .len((end - range.start as u64) as usize)
+      .map(&file)?;
+
+    Ok(crate::RangeValueLog::construct(
+      fid,
+      map,
+      range.start,
+      data_offset,
+      committed,
+      cks,
+      opts,
+    ))
+  }
+
+  /// Opens a read-only log over the memory map of an already opened file.
+  ///
+  /// This is useful when only a file handle is available, e.g. the file descriptor passed by a supervisor
+  /// in a sandboxed environment. The handle is mapped directly, so neither the path nor the permissions of the
+  /// file are needed other than the read access of the handle. The whole log from the
+  /// [`offset`](Builder::offset) to the end of the file is mapped, and the header is checked on the mapped
+  /// bytes, see [`RangeValueLog`](crate::RangeValueLog) for reading the values.
+  ///
+  /// The file must be created by [`map_mut`](Builder::map_mut) with the same [`reserved`](Builder::reserved),
+  /// the logs written in the version `0` layout can only be opened by path. There is no mutable counterpart,
+  /// because the allocator of the mutable logs can only map a file by its path.
+  ///
+  /// ## Safety
+  /// - All file-backed memory map constructors are marked `unsafe` because of the potential for
+  ///   *Undefined Behavior* (UB) using the map if the underlying file is subsequently modified, in or
+  ///   out of process. Applications must consider the risk and take appropriate precautions when
+  ///   using file-backed maps. Solutions such as file permissions, locks or process-private (e.g.
+  ///   unlinked) files exist but are platform specific and limited.
+  ///
+  /// ## Example
+  ///
+  /// ```rust
+  /// use valog::{sync, Builder, LogWriter, RangeValueLog};
+  ///
+  /// let dir = tempfile::tempdir().unwrap();
+  /// let path = dir.path().join("map_from_file_example.vlog");
+  /// let vp = unsafe {
+  ///   let log = Builder::new()
+  ///     .with_capacity(1024)
+  ///     .with_create_new(true)
+  ///     .with_read(true)
+  ///     .with_write(true)
+  ///     .map_mut::<sync::ValueLog, _>(&path, 1u32)
+  ///     .unwrap();
+  ///   log.insert(b"Hello, valog!").unwrap()
+  /// };
+  ///
+  /// let file = std::fs::File::open(&path).unwrap();
+  /// let log: RangeValueLog<u32> = unsafe {
+  ///   Builder::new()
+  ///     .map_from_file(file, 1u32)
+  ///     .unwrap()
+  /// };
+  /// let data = log.read(&1, vp.offset(), vp.size()).unwrap();
+  /// assert_eq!(data, b"Hello, valog!");
+  /// ```
+  pub unsafe fn map_from_file<I>(
+    self,
+    file: std::fs::File,
+    fid: I,
+  ) -> std::io::Result<crate::RangeValueLog<I, S>> {
+    let Self { mut opts, cks } = self;
+    let base = opts.offset;
+
+    let size = file.metadata()?.len().saturating_sub(base);
+    if size < HEADER_SIZE as u64 {
+      return Err(Error::InvalidLayout.into_io_err());
     }
 
-    let magic_version_from_buf =
-      u16::from_le_bytes(buf[MAGIC_TEXT_SIZE..HEADER_SIZE].try_into().unwrap());
-    if magic_version_from_buf != magic_version {
-      return Err(bad_magic_version());
+    let map = memmap2::MmapOptions::new()
+      .offset(base)
+      .len(size.min(u32::MAX as u64) as usize)
+      .map(&file)?;
+    Self::check_header::<I>(&map, &mut opts)?;
+
+    let (data_offset, committed) = (recorded_data_offset(&map), committed(&map));
+    if committed as usize > map.len() {
+      return Err(Error::InvalidLayout.into_io_err());
     }
 
-    Ok(magic_version_from_buf)
+    Ok(crate::RangeValueLog::construct(
+      fid,
+      map,
+      0,
+      data_offset,
+      committed,
+      cks,
+      opts,
+    ))
+  }
+
+  #[inline]
+  fn check_header<I>(buf: &[u8], opts: &mut Options) -> std::io::Result<()> {
+    check_header(buf, opts, HeaderTags::new::<S, I>()).map_err(Error::into_io_err)
+  }
+
+  #[inline]
+  fn check_existing<I>(
+    path: &std::path::Path,
+    opts: &mut Options,
+    check_freelist: bool,
+  ) -> std::io::Result<Option<(u32, u32)>> {
+    check_existing(path, opts, HeaderTags::new::<S, I>(), check_freelist)
+  }
+
+  /// Returns the data offset and the committed length of the existing log in the file, which are checked
+  /// against the size of the file.
+  fn committed_layout<I>(
+    path: &std::path::Path,
+    file: &std::fs::File,
+    opts: &mut Options,
+  ) -> std::io::Result<(u32, u32)> {
+    let size = file.metadata()?.len().saturating_sub(opts.offset);
+    match Self::check_existing::<I>(path, opts, false)? {
+      Some((data_offset, committed)) if committed as u64 <= size => Ok((data_offset, committed)),
+      _ => Err(Error::InvalidLayout.into_io_err()),
+    }
   }
 }
 
@@ -907,6 +1532,29 @@ impl<C> Builder<C> {
     self
   }
 
+  /// Sets the option to create the missing parent directories of the file, like [`std::fs::create_dir_all`],
+  /// before a map is created by [`Builder::map_mut`] or [`Builder::map_mut_with_path_builder`].
+  ///
+  /// By default, this option is disabled, and opening a file in a missing directory fails.
+  ///
+  /// ## Examples
+  ///
+  /// ```rust
+  /// use valog::Builder;
+  ///
+  /// let opts = Builder::new()
+  ///   .with_write(true)
+  ///   .with_create(true)
+  ///   .with_create_dir(true);
+  /// ```
+  #[inline]
+  #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+  #[cfg_attr(docsrs, doc(cfg(all(feature = "memmap", not(target_family = "wasm")))))]
+  pub fn with_create_dir(mut self, val: bool) -> Self {
+    self.opts.create_dir = val;
+    self
+  }
+
   /// Configures the memory map to start at byte `offset` from the beginning of the file.
   ///
   /// This option has no effect on anonymous memory maps or vec backed `Log`.
@@ -996,6 +1644,103 @@ impl<C> Builder<C> {
     self.opts.populate = populate;
     self
   }
+
+  /// Set if bypass the page cache for the file backed `Log` after bulk scans.
+  ///
+  /// A memory map cannot be opened with `O_DIRECT`, so instead, when this option is enabled, the mapped pages of the
+  /// `Log` are paged out (`MADV_PAGEOUT`, falling back to `MADV_DONTNEED`) after bulk scans,
+  /// e.g. [`LogReader::scrub`](crate::LogReader::scrub), or explicitly by [`Log::evict_page_cache`](crate::Log::evict_page_cache),
+  /// so that the page cache is kept warm for the other hot data.
+  ///
+  /// This option only works on Linux and Android, and has no effect on anonymous memory map and vec backed `Log`.
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use valog::Builder;
+  ///
+  /// let opts = Builder::new().with_direct_io(true);
+  /// ```
+  #[inline]
+  #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+  #[cfg_attr(docsrs, doc(cfg(all(feature = "memmap", not(target_family = "wasm")))))]
+  pub fn with_direct_io(mut self, direct_io: bool) -> Self {
+    self.opts.direct_io = direct_io;
+    self
+  }
+
+  /// Sets the number of extra bytes read ahead by each positioned read of [`PreadValueLog`](crate::PreadValueLog).
+  ///
+  /// When it is not `0`, each read which misses the buffered window reads the requested entry plus `read_ahead`
+  /// bytes after it, and keeps them for the next reads, so a forward scan issues much fewer `pread`s.
+  /// The other backends are not affected.
+  ///
+  /// By default, the read ahead is `0`, which reads the exact bytes of the entries.
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use valog::Builder;
+  ///
+  /// let opts = Builder::new().with_read_ahead(64 * 1024);
+  /// ```
+  #[inline]
+  #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+  #[cfg_attr(docsrs, doc(cfg(all(feature = "memmap", not(target_family = "wasm")))))]
+  pub fn with_read_ahead(mut self, read_ahead: u32) -> Self {
+    self.opts.read_ahead = read_ahead;
+    self
+  }
+
+  /// Set if grow the anonymous memory map backed `Log` when there is not enough space.
+  ///
+  /// When this option is enabled, [`LogWriter::insert_or_grow`](crate::LogWriter::insert_or_grow) creates a new
+  /// larger anonymous memory map (at least double the capacity), copies the old contents to it and swaps it in,
+  /// instead of returning [`Error::InsufficientSpace`](crate::error::Error::InsufficientSpace).
+  /// The existing value pointers remain valid, because they are offset-relative.
+  ///
+  /// This option has no effect on file backed memory map and vec backed `Log`.
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use valog::Builder;
+  ///
+  /// let opts = Builder::new().with_anon_grow(true);
+  /// ```
+  #[inline]
+  #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+  #[cfg_attr(docsrs, doc(cfg(all(feature = "memmap", not(target_family = "wasm")))))]
+  pub fn with_anon_grow(mut self, anon_grow: bool) -> Self {
+    self.opts.anon_grow = anon_grow;
+    self
+  }
+
+  /// Set if lock the whole memory map of the `Log` in the memory, after the `Log` is mapped.
+  ///
+  /// Unlike [`with_lock_meta`](Builder::with_lock_meta) which only locks the first page, this option `mlock`s
+  /// `0..capacity`, so that reading and writing the `Log` never page-fault, which is useful for real-time systems.
+  /// It composes with [`with_lock_meta`](Builder::with_lock_meta).
+  ///
+  /// **Warning:** the locked memory cannot be swapped out, locking large logs puts pressure on the memory of the system,
+  /// and mapping fails if the locked memory exceeds the limit of the process (e.g. `RLIMIT_MEMLOCK` on Linux).
+  ///
+  /// Default is `false`. This option has no effect on windows and vec backed `Log`.
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use valog::Builder;
+  ///
+  /// let opts = Builder::new().with_lock_all(true);
+  /// ```
+  #[inline]
+  #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+  #[cfg_attr(docsrs, doc(cfg(all(feature = "memmap", not(target_family = "wasm")))))]
+  pub fn with_lock_all(mut self, lock_all: bool) -> Self {
+    self.opts.lock_all = lock_all;
+    self
+  }
 }
 
 impl<C> Builder<C> {
@@ -1101,6 +1846,23 @@ impl<C> Builder<C> {
     self.opts.create_new
   }
 
+  /// Returns `true` if the missing parent directories of the file should be created.
+  ///
+  /// ## Examples
+  ///
+  /// ```rust
+  /// use valog::Builder;
+  ///
+  /// let opts = Builder::new().with_create_dir(true);
+  /// assert_eq!(opts.create_dir(), true);
+  /// ```
+  #[inline]
+  #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+  #[cfg_attr(docsrs, doc(cfg(all(feature = "memmap", not(target_family = "wasm")))))]
+  pub const fn create_dir(&self) -> bool {
+    self.opts.create_dir
+  }
+
   /// Returns the offset of the memory map.
   ///
   /// ## Examples
@@ -1168,4 +1930,82 @@ impl<C> Builder<C> {
   pub const fn populate(&self) -> bool {
     self.opts.populate
   }
+
+  /// Returns `true` if bypass the page cache for the file backed `Log` after bulk scans.
+  ///
+  /// ## Examples
+  ///
+  /// ```rust
+  /// use valog::Builder;
+  ///
+  /// let opts = Builder::new().with_direct_io(true);
+  /// assert_eq!(opts.direct_io(), true);
+  /// ```
+  #[inline]
+  #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+  #[cfg_attr(docsrs, doc(cfg(all(feature = "memmap", not(target_family = "wasm")))))]
+  pub const fn direct_io(&self) -> bool {
+    self.opts.direct_io
+  }
+
+  /// Returns the number of extra bytes read ahead by each positioned read of [`PreadValueLog`](crate::PreadValueLog).
+  ///
+  /// ## Examples
+  ///
+  /// ```rust
+  /// use valog::Builder;
+  ///
+  /// let opts = Builder::new().with_read_ahead(64 * 1024);
+  /// assert_eq!(opts.read_ahead(), 64 * 1024);
+  /// ```
+  #[inline]
+  #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+  #[cfg_attr(docsrs, doc(cfg(all(feature = "memmap", not(target_family = "wasm")))))]
+  pub const fn read_ahead(&self) -> u32 {
+    self.opts.read_ahead
+  }
+
+  /// Returns `true` if grow the anonymous memory map backed `Log` when there is not enough space.
+  ///
+  /// ## Examples
+  ///
+  /// ```rust
+  /// use valog::Builder;
+  ///
+  /// let opts = Builder::new().with_anon_grow(true);
+  /// assert_eq!(opts.anon_grow(), true);
+  /// ```
+  #[inline]
+  #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+  #[cfg_attr(docsrs, doc(cfg(all(feature = "memmap", not(target_family = "wasm")))))]
+  pub const fn anon_grow(&self) -> bool {
+    self.opts.anon_grow
+  }
+
+  /// Returns `true` if lock the whole memory map of the `Log` in the memory.
+  ///
+  /// ## Examples
+  ///
+  /// ```rust
+  /// use valog::Builder;
+  ///
+  /// let opts = Builder::new().with_lock_all(true);
+  /// assert_eq!(opts.lock_all(), true);
+  /// ```
+  #[inline]
+  #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+  #[cfg_attr(docsrs, doc(cfg(all(feature = "memmap", not(target_family = "wasm")))))]
+  pub const fn lock_all(&self) -> bool {
+    self.opts.lock_all
+  }
+}
+
+/// Locks the whole memory map of the arena, if [`lock_all`](Options::lock_all) is enabled.
+pub(crate) fn lock_all<A: Allocator>(arena: &A, opts: &Options) -> std::io::Result<()> {
+  if opts.lock_all {
+    // Safety: `0..capacity` is the whole memory map of the arena.
+    unsafe { arena.mlock(0, arena.capacity()) }
+  } else {
+    Ok(())
+  }
 }
@@ -1,6 +1,9 @@
 use dbutils::CheapClone;
+#[cfg(feature = "sync")]
 use error::Error;
+#[cfg(feature = "sync")]
 use rarena_allocator::Allocator;
+#[cfg(feature = "sync")]
 use sealed::Sealed;
 
 use super::*;
@@ -8,6 +11,7 @@ use super::*;
 pub(crate) const MB: u32 = 1024 * 1024;
 
 #[test]
+#[cfg(feature = "sync")]
 fn test_read_out_of_bounds() {
   let log = Builder::new()
     .with_capacity(100)
@@ -22,6 +26,46 @@ fn test_read_out_of_bounds() {
 }
 
 #[test]
+#[cfg(feature = "sync")]
+fn test_peek() {
+  let log = Builder::new()
+    .with_capacity(100)
+    .alloc::<crate::sync::ValueLog>(0)
+    .unwrap();
+
+  let vp = log.insert(b"Hello, valog!").unwrap();
+  assert_eq!(log.peek(&vp).unwrap(), vp.size());
+
+  // the checksum is not validated
+  unsafe {
+    log
+      .allocator()
+      .raw_mut_ptr()
+      .add(vp.offset() as usize)
+      .write(0);
+  }
+  assert_eq!(log.peek(&vp).unwrap(), vp.size());
+  assert!(matches!(
+    unsafe { log.read(log.id(), vp.offset(), vp.size()) },
+    Err(Error::ChecksumMismatch)
+  ));
+
+  let empty = log.insert(b"").unwrap();
+  assert_eq!(log.peek(&empty).unwrap(), 0);
+
+  // the checksum of the last value must be in the bounds as well
+  let stale = ValuePointer::new(0, vp.offset(), vp.size() + 1);
+  assert!(matches!(log.peek(&stale), Err(Error::OutOfBounds { .. })));
+
+  let stale = ValuePointer::new(0, 0, vp.size());
+  assert!(matches!(log.peek(&stale), Err(Error::OutOfBounds { .. })));
+
+  let stale = ValuePointer::new(1, vp.offset(), vp.size());
+  assert!(matches!(log.peek(&stale), Err(Error::IdMismatch)));
+}
+
+#[test]
+#[cfg(feature = "sync")]
 fn test_checksum_mismatch() {
   let log = Builder::new()
     .with_capacity(100)
@@ -37,141 +81,4106 @@ fn test_checksum_mismatch() {
       .add(vp.offset() as usize)
       .write(0);
   }
-  let err = unsafe { log.read(log.id(), vp.offset(), vp.size()).unwrap_err() };
-  assert!(matches!(err, Error::ChecksumMismatch));
+  let err = unsafe { log.read(log.id(), vp.offset(), vp.size()).unwrap_err() };
+  assert!(matches!(err, Error::ChecksumMismatch));
+
+  let log = Builder::new()
+    .with_capacity(100)
+    .alloc::<crate::sync::GenericValueLog<String>>(0)
+    .unwrap();
+
+  let vp = log.insert(&"Hello, valog!".to_string()).unwrap();
+  assert_eq!(*vp.id(), 0);
+  unsafe {
+    log
+      .allocator()
+      .raw_mut_ptr()
+      .add(vp.offset() as usize)
+      .write(0);
+  }
+  let err = unsafe { log.read(log.id(), vp.offset(), vp.size()).unwrap_err() };
+  assert!(matches!(err, Error::ChecksumMismatch));
+}
+
+#[test]
+#[cfg(feature = "sync")]
+fn test_scrub() {
+  let log = Builder::new()
+    .with_capacity(100)
+    .with_validate_checksum(false)
+    .alloc::<crate::sync::ValueLog>(0)
+    .unwrap();
+
+  let ptrs = [log.insert(b"foo").unwrap(), log.insert(b"bar").unwrap()];
+  unsafe { log.scrub(&ptrs).unwrap() };
+
+  unsafe {
+    log
+      .allocator()
+      .raw_mut_ptr()
+      .add(ptrs[1].offset() as usize)
+      .write(0);
+  }
+  let data = unsafe {
+    log
+      .read(log.id(), ptrs[1].offset(), ptrs[1].size())
+      .unwrap()
+  };
+  assert_eq!(data, b"\0ar");
+
+  let err = unsafe { log.scrub(&ptrs).unwrap_err() };
+  assert!(matches!(err, Error::ChecksumMismatch));
+}
+
+#[test]
+#[cfg(feature = "sync")]
+#[cfg(any(feature = "alloc", feature = "std"))]
+fn test_verify_all() {
+  let log = Builder::new()
+    .with_capacity(100)
+    .with_validate_checksum(false)
+    .alloc::<crate::sync::ValueLog>(0)
+    .unwrap();
+
+  let ptrs = [
+    log.insert(b"foo").unwrap(),
+    log.insert(b"bar").unwrap(),
+    log.insert(b"baz").unwrap(),
+  ];
+  assert!(unsafe { log.verify_all(&ptrs) }.is_empty());
+
+  for vp in [&ptrs[0], &ptrs[2]] {
+    unsafe {
+      log
+        .allocator()
+        .raw_mut_ptr()
+        .add(vp.offset() as usize)
+        .write(0);
+    }
+  }
+
+  let report = unsafe { log.verify_all(&ptrs) };
+  assert_eq!(report.len(), 2);
+  assert_eq!(report[0].0, ptrs[0]);
+  assert!(matches!(report[0].1, Error::ChecksumMismatch));
+  assert_eq!(report[1].0, ptrs[2]);
+  assert!(matches!(report[1].1, Error::ChecksumMismatch));
+}
+
+#[test]
+#[cfg(feature = "sync")]
+fn test_read_lossy() {
+  let log = Builder::new()
+    .with_capacity(100)
+    .alloc::<crate::sync::ValueLog>(0)
+    .unwrap();
+
+  let vp = log.insert(b"foo").unwrap();
+  let (data, intact) = unsafe { log.read_lossy(vp.id(), vp.offset(), vp.size()).unwrap() };
+  assert_eq!(data, b"foo");
+  assert!(intact);
+
+  unsafe {
+    log
+      .allocator()
+      .raw_mut_ptr()
+      .add(vp.offset() as usize)
+      .write(b'g');
+  }
+
+  assert!(matches!(
+    unsafe { log.read(vp.id(), vp.offset(), vp.size()) },
+    Err(Error::ChecksumMismatch)
+  ));
+  let (data, intact) = unsafe { log.read_lossy(vp.id(), vp.offset(), vp.size()).unwrap() };
+  assert_eq!(data, b"goo");
+  assert!(!intact);
+
+  assert_eq!(
+    unsafe { log.read_lossy(vp.id(), vp.offset(), 0).unwrap() },
+    (&[][..], true)
+  );
+  assert!(matches!(
+    unsafe { log.read_lossy(vp.id(), 100, 3) },
+    Err(Error::OutOfBounds { .. })
+  ));
+}
+
+#[test]
+#[cfg(feature = "sync")]
+fn test_insert_big_value() {
+  let log = Builder::new()
+    .with_capacity(100)
+    .with_maximum_value_size(3)
+    .alloc::<crate::sync::ValueLog>(0)
+    .unwrap();
+
+  let err = log.insert(&[0; 10]).unwrap_err();
+  assert!(matches!(err, Error::ValueTooLarge { .. }));
+
+  // the size is checked before building the value, so an oversize value is never encoded
+  let err = log
+    .insert_with(ValueBuilder::new(10, |_: &mut VacantBuffer<'_>| {
+      unreachable!("an oversize value must not be built") as Result<(), ()>
+    }))
+    .unwrap_err();
+  assert!(matches!(
+    err,
+    rarena_allocator::either::Either::Right(Error::ValueTooLarge { .. })
+  ));
+  let err = log
+    .insert_generic(&std::string::String::from("Hello, valog!"))
+    .unwrap_err();
+  assert!(matches!(
+    err,
+    rarena_allocator::either::Either::Right(Error::ValueTooLarge { .. })
+  ));
+}
+
+#[test]
+#[cfg(feature = "sync")]
+fn test_oversize_policy() {
+  use crate::options::{Options, OversizePolicy};
+  use dbutils::buffer::VacantBuffer;
+  use rarena_allocator::either::Either;
+
+  let opts = Options::new();
+  assert_eq!(opts.oversize_policy(), OversizePolicy::Error);
+
+  let log = Builder::new()
+    .with_capacity(256)
+    .with_maximum_value_size(11)
+    .with_oversize_policy(OversizePolicy::Truncate)
+    .alloc::<crate::sync::ValueLog>(0)
+    .unwrap();
+
+  let vp = log.insert(b"abcdef").unwrap();
+  assert!(vp.is_truncated());
+  assert_eq!(vp.size(), 3);
+  assert_eq!(
+    unsafe { log.read(&0, vp.offset(), vp.size()).unwrap() },
+    b"abc"
+  );
+  assert!(vp.to_string().ends_with(", truncated)"));
+
+  let vp = log.insert(b"ab").unwrap();
+  assert!(!vp.is_truncated());
+
+  let vps = log.insert_all(&[b"xyzw", b"", b"uv"]).unwrap();
+  assert!(vps[0].is_truncated());
+  assert!(!vps[1].is_truncated());
+  assert!(!vps[2].is_truncated());
+  assert_eq!(
+    unsafe { log.read(&0, vps[0].offset(), vps[0].size()).unwrap() },
+    b"xyz"
+  );
+  assert_eq!(
+    unsafe { log.read(&0, vps[2].offset(), vps[2].size()).unwrap() },
+    b"uv"
+  );
+
+  // all the inserts from slices are truncated
+  let vp = log.insert_tombstone(b"abcdef").unwrap();
+  assert!(vp.is_truncated() && vp.is_tombstone());
+  assert_eq!(vp.size(), 3);
+  let vp = log.insert_array(b"ghijkl").unwrap();
+  assert!(vp.is_truncated());
+  assert_eq!(
+    unsafe { log.read(&0, vp.offset(), vp.size()).unwrap() },
+    b"ghi"
+  );
+  let end = log.allocator().allocated() as u32;
+  let vp = log.insert_at(end + 1, b"mnopqr").unwrap();
+  assert!(vp.is_truncated());
+  assert_eq!(
+    unsafe { log.read(&0, vp.offset(), vp.size()).unwrap() },
+    b"mno"
+  );
+  let mut writer = log.buffered();
+  let vp = writer.insert(b"stuvwx").unwrap();
+  assert!(vp.is_truncated());
+  assert_eq!(
+    unsafe { log.read(&0, vp.offset(), vp.size()).unwrap() },
+    b"stu"
+  );
+  drop(writer);
+  assert!(log.can_insert(64).is_ok());
+
+  // the values built in place are still rejected
+  let err = log
+    .insert_with(ValueBuilder::new(10, |buf: &mut VacantBuffer<'_>| {
+      buf.put_slice(&[0; 10])
+    }))
+    .unwrap_err();
+  assert!(matches!(err, Either::Right(Error::ValueTooLarge { .. })));
+  let err = log.insert_with_flags(b"abcdef", 0).unwrap_err();
+  assert!(matches!(err, Error::ValueTooLarge { .. }));
+  let err = log.insert_with_checksum(b"abcdef", 0).unwrap_err();
+  assert!(matches!(err, Error::ValueTooLarge { .. }));
+}
+
+#[test]
+#[cfg(feature = "sync")]
+fn test_entry_overhead() {
+  use crate::options::ChecksumLen;
+
+  let log = Builder::new()
+    .with_capacity(1024)
+    .with_checksum_len(ChecksumLen::Four)
+    .with_value_alignment(8)
+    .alloc::<crate::sync::ValueLog>(0)
+    .unwrap();
+  assert_eq!(log.entry_overhead(), 11);
+
+  for len in 1..20 {
+    let before = log.allocator().allocated();
+    log.insert(&[0; 20][..len]).unwrap();
+    let used = log.allocator().allocated() - before;
+    assert!(used <= len + log.entry_overhead() as usize);
+  }
+}
+
+#[test]
+#[cfg(feature = "sync")]
+fn test_insert_array() {
+  let log = Builder::new()
+    .with_capacity(100)
+    .with_maximum_value_size(16)
+    .alloc::<crate::sync::ValueLog>(0)
+    .unwrap();
+
+  let vp = log.insert_array(&1234u64.to_le_bytes()).unwrap();
+  let data = unsafe { log.read_u64(log.id(), vp.offset()).unwrap() };
+  assert_eq!(data, 1234);
+
+  let vp = log.insert_array(&[]).unwrap();
+  assert_eq!(vp.size(), 0);
+
+  let err = log.insert_array(&[0; 9]).unwrap_err();
+  assert!(matches!(err, Error::ValueTooLarge { .. }));
+}
+
+#[test]
+#[cfg(feature = "sync")]
+fn test_insert_at() {
+  let log = Builder::new()
+    .with_capacity(1024)
+    .alloc::<crate::sync::ValueLog>(0)
+    .unwrap();
+
+  let first = log.insert(b"first").unwrap();
+  let slot = log.insert_at(512, b"slot").unwrap();
+  assert_eq!(slot.offset(), 512);
+  assert_eq!(log.entries(), 2);
+  assert_eq!(
+    log.discarded() as usize,
+    512 - (first.offset() as usize + 5 + 8)
+  );
+
+  // the following inserts are placed after the slot
+  let next = log.insert(b"next").unwrap();
+  assert_eq!(next.offset(), 512 + 4 + 8);
+  for (vp, expected) in [(first, &b"first"[..]), (slot, b"slot"), (next, b"next")] {
+    let data = unsafe { log.read(log.id(), vp.offset(), vp.size()).unwrap() };
+    assert_eq!(data, expected);
+  }
+
+  let err = log.insert_at(520, b"overlap").unwrap_err();
+  assert!(matches!(err, Error::Overlap { offset: 520, .. }));
+  let err = log.insert_at(0, b"header").unwrap_err();
+  assert!(matches!(err, Error::OutOfBounds { .. }));
+  let err = log.insert_at(1020, b"too far").unwrap_err();
+  assert!(matches!(err, Error::InsufficientSpace { .. }));
+  assert_eq!(log.insert_at(0, b"").unwrap().size(), 0);
+}
+
+#[test]
+#[cfg(feature = "sync")]
+fn test_insert_insufficient() {
+  let log = Builder::new()
+    .with_capacity(100)
+    .alloc::<crate::sync::ValueLog>(0)
+    .unwrap();
+
+  let err = log.insert(&[0; 100]).unwrap_err();
+  assert!(matches!(err, Error::InsufficientSpace { .. }));
+}
+
+#[test]
+fn test_from_arena_error() {
+  use crate::error::Error;
+  use dbutils::leb128::DecodeVarintError;
+  use rarena_allocator::Error as ArenaError;
+
+  let err = Error::from(ArenaError::InsufficientSpace {
+    requested: 10,
+    available: 5,
+  });
+  assert!(matches!(
+    err,
+    Error::InsufficientSpace {
+      requested: 10,
+      available: 5
+    }
+  ));
+  assert!(matches!(Error::from(ArenaError::ReadOnly), Error::ReadOnly));
+
+  let err = Error::from(ArenaError::OutOfBounds {
+    offset: usize::MAX,
+    allocated: 64,
+  });
+  assert!(matches!(
+    err,
+    Error::AllocatorOutOfBounds {
+      offset: usize::MAX,
+      allocated: 64,
+    }
+  ));
+
+  let err = Error::from(ArenaError::DecodeVarintError(DecodeVarintError::Overflow));
+  assert!(matches!(err, Error::InvalidLayout));
+}
+
+#[test]
+#[cfg(feature = "sync")]
+fn test_can_insert() {
+  let log = Builder::new()
+    .with_capacity(100)
+    .with_maximum_value_size(50)
+    .alloc::<crate::sync::ValueLog>(0)
+    .unwrap();
+
+  assert!(log.can_insert(0).is_ok());
+  let err = log.can_insert(43).unwrap_err();
+  assert!(matches!(err, Error::ValueTooLarge { .. }));
+
+  while log.can_insert(20).is_ok() {
+    log.insert(&[1; 20]).unwrap();
+  }
+  let err = log.can_insert(20).unwrap_err();
+  assert!(matches!(err, Error::InsufficientSpace { .. }));
+  let err = log.insert(&[1; 20]).unwrap_err();
+  assert!(matches!(err, Error::InsufficientSpace { .. }));
+
+  let log = Builder::new()
+    .with_capacity(1024)
+    .with_max_entries(1)
+    .alloc::<crate::sync::ValueLog>(0)
+    .unwrap();
+  assert!(log.can_insert(3).is_ok());
+  log.insert(b"foo").unwrap();
+  let err = log.can_insert(3).unwrap_err();
+  assert!(matches!(err, Error::MaxEntriesReached { maximum: 1 }));
+  let err = log.insert(b"bar").unwrap_err();
+  assert!(matches!(err, Error::MaxEntriesReached { maximum: 1 }));
+
+  // the space of a log in an external arena is limited by the end of its range
+  let arena = Builder::new()
+    .with_capacity(1024)
+    .alloc::<crate::sync::ValueLog>(0)
+    .unwrap()
+    .allocator()
+    .clone();
+  let start = arena.allocated() as u32;
+  let sub = Builder::new()
+    .with_external_arena::<crate::sync::ValueLog>(arena, start..start + 32, 1)
+    .unwrap();
+  while sub.can_insert(8).is_ok() {
+    sub.insert(&[1; 8]).unwrap();
+  }
+  let err = sub.can_insert(8).unwrap_err();
+  assert!(matches!(err, Error::InsufficientSpace { .. }));
+  let err = sub.insert(&[1; 8]).unwrap_err();
+  assert!(matches!(err, Error::InsufficientSpace { .. }));
+  assert!(sub.allocator().remaining() > 8);
+}
+
+#[test]
+#[cfg(feature = "sync")]
+fn test_insert_empty_value() {
+  let log = Builder::new()
+    .with_capacity(100)
+    .alloc::<crate::sync::ValueLog>(0)
+    .unwrap();
+
+  let vp = log.insert(&[]).unwrap();
+  assert_eq!(*vp.id(), 0);
+  assert_eq!(vp.offset(), 0);
+  assert_eq!(vp.size(), 0);
+}
+
+#[test]
+#[cfg(feature = "sync")]
+fn test_basic() {
+  let log = Builder::new()
+    .with_capacity(100)
+    .alloc::<crate::sync::ValueLog>(0)
+    .unwrap();
+  assert_eq!(*log.id(), 0);
+  assert_eq!(log.options().capacity(), 100);
+
+  let empty = unsafe { log.read(log.id(), 0, 0).unwrap() };
+  assert_eq!(empty, &[]);
+
+  let log = Builder::new()
+    .with_capacity(100)
+    .with_lock_meta(true)
+    .alloc::<crate::sync::GenericValueLog<String>>(0)
+    .unwrap();
+  assert_eq!(*log.id(), 0);
+  assert_eq!(log.options().capacity(), 100);
+}
+
+#[test]
+#[cfg(feature = "sync")]
+fn test_insert_with_checksum() {
+  let src = Builder::new()
+    .with_capacity(100)
+    .alloc::<crate::sync::ValueLog>(0)
+    .unwrap();
+  let dst = Builder::new()
+    .with_capacity(100)
+    .alloc::<crate::sync::ValueLog>(1)
+    .unwrap();
+
+  let vp = src.insert(b"Hello, valog!").unwrap();
+  let value = unsafe { src.read(src.id(), vp.offset(), vp.size()).unwrap() };
+  let checksum = src.checksum(value);
+
+  let vp = dst.insert_with_checksum(value, checksum).unwrap();
+  let data = unsafe { dst.read(dst.id(), vp.offset(), vp.size()).unwrap() };
+  assert_eq!(data, b"Hello, valog!");
+}
+
+#[test]
+#[cfg(feature = "sync")]
+fn test_checksum_len() {
+  use crate::options::ChecksumLen;
+
+  let log = Builder::new()
+    .with_capacity(100)
+    .with_checksum_len(ChecksumLen::Four)
+    .alloc::<crate::sync::ValueLog>(0)
+    .unwrap();
+
+  let vp1 = log.insert(b"Hello").unwrap();
+  let vp2 = log.insert(b"valog!").unwrap();
+  assert_eq!(vp2.offset(), vp1.offset() + 5 + 4);
+  assert_eq!(log.checksum_len(), 4);
+  let data = unsafe { log.read(log.id(), vp2.offset(), vp2.size()).unwrap() };
+  assert_eq!(data, b"valog!");
+
+  unsafe {
+    log
+      .allocator()
+      .raw_mut_ptr()
+      .add(vp1.offset() as usize + 5)
+      .write(0xFF);
+  }
+  let err = unsafe { log.read(log.id(), vp1.offset(), vp1.size()).unwrap_err() };
+  assert!(matches!(err, Error::ChecksumMismatch));
+}
+
+#[test]
+#[cfg(feature = "sync")]
+#[cfg_attr(miri, ignore)]
+#[cfg(all(feature = "metrics", feature = "memmap", not(target_family = "wasm")))]
+fn test_page_fault_stats() {
+  use crate::sync::{ImmutableValueLog, ValueLog};
+
+  let dir = tempfile::tempdir().unwrap();
+  let p = dir.path().join("test_page_fault_stats");
+  let ptrs = unsafe {
+    let log = Builder::new()
+      .with_capacity(1 << 16)
+      .with_create_new(true)
+      .with_read(true)
+      .with_write(true)
+      .map_mut::<ValueLog, _>(&p, 0)
+      .unwrap();
+    (0..64)
+      .map(|_| log.insert(&[7; 1000]).unwrap())
+      .collect::<std::vec::Vec<_>>()
+  };
+
+  for populate in [false, true] {
+    let log = unsafe {
+      Builder::new()
+        .with_populate(populate)
+        .with_read(true)
+        .map::<ImmutableValueLog, _>(&p, 0)
+        .unwrap()
+    };
+    assert!(log.page_fault_stats().is_none());
+
+    let cloned = log.clone();
+    unsafe { log.scrub(&ptrs).unwrap() };
+    let stats = log.page_fault_stats();
+    assert_eq!(stats.is_some(), cfg!(target_os = "linux"));
+    assert_eq!(cloned.page_fault_stats(), stats);
+
+    // only the first scan is recorded
+    unsafe { log.scrub(&ptrs).unwrap() };
+    assert_eq!(log.page_fault_stats(), stats);
+  }
+}
+
+#[test]
+#[cfg(feature = "sync")]
+#[cfg_attr(miri, ignore)]
+#[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+fn test_map_cow() {
+  use crate::sync::ValueLog;
+
+  let dir = tempfile::tempdir().unwrap();
+  let p = dir.path().join("test_map_cow");
+  assert!(unsafe { Builder::new().map_cow::<ValueLog, _>(&p, 0) }.is_err());
+
+  let foo = unsafe {
+    let log = Builder::new()
+      .with_capacity(1024)
+      .with_create_new(true)
+      .with_read(true)
+      .with_write(true)
+      .map_mut::<ValueLog, _>(&p, 0)
+      .unwrap();
+    log.insert(b"foo").unwrap()
+  };
+  let base = std::fs::read(&p).unwrap();
+
+  let a = unsafe { Builder::new().map_cow::<ValueLog, _>(&p, 0).unwrap() };
+  let b = unsafe { Builder::new().map_cow::<ValueLog, _>(&p, 0).unwrap() };
+  assert!(!a.is_readonly());
+  assert_eq!(a.capacity() as usize, base.len());
+
+  let bar = a.insert(b"bar").unwrap();
+  let baz = b.insert(b"baz").unwrap();
+  // both logs start from the allocated length of the file
+  assert_eq!(bar.offset(), baz.offset());
+  unsafe {
+    assert_eq!(a.read(a.id(), foo.offset(), foo.size()).unwrap(), b"foo");
+    assert_eq!(a.read(a.id(), bar.offset(), bar.size()).unwrap(), b"bar");
+    assert_eq!(b.read(b.id(), baz.offset(), baz.size()).unwrap(), b"baz");
+  }
+
+  // the private pages are not evicted, which would discard the copied values
+  assert!(a.shared_file_path().is_none());
+  a.evict_page_cache().unwrap();
+  unsafe {
+    assert_eq!(a.read(a.id(), bar.offset(), bar.size()).unwrap(), b"bar");
+  }
+
+  drop(a);
+  drop(b);
+  assert_eq!(std::fs::read(&p).unwrap(), base);
+}
+
+#[test]
+#[cfg(feature = "sync")]
+#[cfg_attr(miri, ignore)]
+#[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+fn test_meta_locked() {
+  use crate::sync::{ImmutableValueLog, ValueLog};
+
+  let dir = tempfile::tempdir().unwrap();
+  let p = dir.path().join("test_meta_locked");
+  for lock_meta in [false, true] {
+    let log = unsafe {
+      Builder::new()
+        .with_capacity(100)
+        .with_lock_meta(lock_meta)
+        .with_create(true)
+        .with_read(true)
+        .with_write(true)
+        .map_mut::<ValueLog, _>(&p, 0)
+        .unwrap()
+    };
+    assert_eq!(log.meta_locked(), lock_meta && cfg!(not(windows)));
+  }
+
+  let log = unsafe {
+    Builder::new()
+      .with_lock_meta(true)
+      .with_read(true)
+      .map::<ImmutableValueLog, _>(&p, 0)
+      .unwrap()
+  };
+  assert_eq!(log.meta_locked(), cfg!(not(windows)));
+
+  let log = Builder::new()
+    .with_capacity(100)
+    .with_lock_meta(true)
+    .alloc::<ValueLog>(0)
+    .unwrap();
+  assert!(!log.meta_locked());
+}
+
+#[test]
+#[cfg(feature = "sync")]
+#[cfg_attr(miri, ignore)]
+#[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+fn test_will_sync() {
+  use crate::sync::{ImmutableValueLog, ValueLog};
+
+  let log = Builder::new()
+    .with_capacity(100)
+    .alloc::<ValueLog>(0)
+    .unwrap();
+  assert!(!log.will_sync());
+  let log = Builder::new()
+    .with_capacity(100)
+    .map_anon::<ValueLog>(0)
+    .unwrap();
+  assert!(!log.will_sync());
+
+  let dir = tempfile::tempdir().unwrap();
+  let p = dir.path().join("test_will_sync");
+  for sync in [true, false] {
+    let log = unsafe {
+      Builder::new()
+        .with_capacity(100)
+        .with_sync(sync)
+        .with_create(true)
+        .with_read(true)
+        .with_write(true)
+        .map_mut::<ValueLog, _>(&p, 0)
+        .unwrap()
+    };
+    assert_eq!(log.will_sync(), sync);
+  }
+
+  let log = unsafe {
+    Builder::new()
+      .with_read(true)
+      .map::<ImmutableValueLog, _>(&p, 0)
+      .unwrap()
+  };
+  assert!(!log.will_sync());
+}
+
+#[test]
+#[cfg(feature = "sync")]
+#[cfg_attr(miri, ignore)]
+#[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+fn test_direct_io() {
+  use crate::sync::ValueLog;
+
+  let dir = tempfile::tempdir().unwrap();
+  let p = dir.path().join("test_direct_io");
+
+  let log = unsafe {
+    Builder::new()
+      .with_capacity(MB)
+      .with_direct_io(true)
+      .with_create_new(true)
+      .with_read(true)
+      .with_write(true)
+      .map_mut::<ValueLog, _>(&p, 0)
+      .unwrap()
+  };
+
+  let ptrs = (0..100u32)
+    .map(|i| log.insert(i.to_string().as_bytes()).unwrap())
+    .collect::<Vec<_>>();
+  unsafe { log.scrub(&ptrs).unwrap() };
+
+  // the pages are loaded from the file again after evicted.
+  for (i, vp) in ptrs.iter().enumerate() {
+    let data = unsafe { log.read(log.id(), vp.offset(), vp.size()).unwrap() };
+    assert_eq!(data, i.to_string().as_bytes());
+  }
+
+  log.insert(b"Hello, valog!").unwrap();
+  assert_eq!(log.shared_file_path(), Some(p.as_path()));
+  log.evict_page_cache().unwrap();
+  unsafe { log.scrub(&ptrs).unwrap() };
+}
+
+#[test]
+#[cfg(feature = "sync")]
+#[cfg_attr(miri, ignore)]
+#[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+fn test_create_dir() {
+  use crate::sync::ValueLog;
+
+  let dir = tempfile::tempdir().unwrap();
+  let p = dir.path().join("a/b/test_create_dir.vlog");
+  let builder = Builder::new()
+    .with_capacity(1024)
+    .with_create_new(true)
+    .with_read(true)
+    .with_write(true);
+
+  let err = unsafe { builder.clone().map_mut::<ValueLog, _>(&p, 0).unwrap_err() };
+  assert_eq!(err.kind(), std::io::ErrorKind::NotFound);
+
+  let log = unsafe {
+    builder
+      .with_create_dir(true)
+      .map_mut::<ValueLog, _>(&p, 0)
+      .unwrap()
+  };
+  let vp = log.insert(b"Hello, valog!").unwrap();
+  let data = unsafe { log.read(log.id(), vp.offset(), vp.size()).unwrap() };
+  assert_eq!(data, b"Hello, valog!");
+  assert!(p.exists());
+}
+
+#[test]
+#[cfg(feature = "sync")]
+#[cfg_attr(miri, ignore)]
+#[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+fn test_page_aligned_capacity() {
+  use crate::sync::ValueLog;
+
+  let dir = tempfile::tempdir().unwrap();
+  let p = dir.path().join("test_page_aligned_capacity");
+
+  let log = unsafe {
+    Builder::new()
+      .with_capacity(100)
+      .with_create_new(true)
+      .with_read(true)
+      .with_write(true)
+      .map_mut::<ValueLog, _>(&p, 0)
+      .unwrap()
+  };
+  let page_size = log.page_size();
+  assert_eq!(log.options().capacity() as usize, page_size);
+  assert_eq!(log.allocator().capacity(), page_size);
+
+  // the whole page is usable.
+  log.insert(&[1; 200]).unwrap();
+}
+
+#[test]
+#[cfg(feature = "sync")]
+#[cfg_attr(miri, ignore)]
+#[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+fn test_log_capacity() {
+  use crate::sync::{ImmutableValueLog, ValueLog};
+
+  let dir = tempfile::tempdir().unwrap();
+  let p = dir.path().join("test_log_capacity");
+
+  let log = unsafe {
+    Builder::new()
+      .with_capacity(100)
+      .with_create_new(true)
+      .with_read(true)
+      .with_write(true)
+      .map_mut::<ValueLog, _>(&p, 0)
+      .unwrap()
+  };
+  let page_size = log.page_size();
+  assert_eq!(log.capacity() as usize, page_size);
+  drop(log);
+
+  let log = unsafe {
+    Builder::new()
+      .with_read(true)
+      .map::<ImmutableValueLog, _>(&p, 0)
+      .unwrap()
+  };
+  assert_eq!(log.capacity() as u64, std::fs::metadata(&p).unwrap().len());
+
+  let log = Builder::new()
+    .with_capacity(1024)
+    .alloc::<ValueLog>(0)
+    .unwrap();
+  assert_eq!(log.capacity(), 1024);
+  let start = log.allocator().allocated() as u32;
+  let sub = Builder::new()
+    .with_external_arena::<ValueLog>(log.allocator().clone(), start..start + 64, 1)
+    .unwrap();
+  assert_eq!(sub.capacity(), 64);
+}
+
+#[test]
+#[cfg(feature = "sync")]
+#[cfg_attr(miri, ignore)]
+#[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+fn test_reopen_with_checksum_len() {
+  use crate::{
+    options::ChecksumLen,
+    unsync::{ImmutableValueLog, ValueLog},
+  };
+
+  let dir = tempfile::tempdir().unwrap();
+  let p = dir.path().join("test_reopen_with_checksum_len");
+
+  let log = unsafe {
+    Builder::new()
+      .with_capacity(MB)
+      .with_checksum_len(ChecksumLen::Four)
+      .with_create_new(true)
+      .with_read(true)
+      .with_write(true)
+      .map_mut::<ValueLog, _>(&p, 0)
+      .unwrap()
+  };
+  let vp = log.insert(b"Hello, valog!").unwrap();
+  drop(log);
+
+  let log = unsafe {
+    Builder::new()
+      .with_read(true)
+      .map::<ImmutableValueLog, _>(&p, 0)
+      .unwrap()
+  };
+  assert_eq!(log.options().checksum_len(), ChecksumLen::Four);
+  let data = unsafe { log.read(log.id(), vp.offset(), vp.size()).unwrap() };
+  assert_eq!(data, b"Hello, valog!");
+  drop(log);
+
+  // an explicitly set checksum length is checked, even if it is the default one.
+  let err = unsafe {
+    Builder::new()
+      .with_checksum_len(ChecksumLen::Eight)
+      .with_read(true)
+      .map::<ImmutableValueLog, _>(&p, 0)
+      .unwrap_err()
+  };
+  assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+  assert!(matches!(
+    *err.into_inner().unwrap().downcast::<Error>().unwrap(),
+    Error::OptionsMismatch {
+      field: "checksum_len"
+    }
+  ));
+}
+
+#[test]
+#[cfg(feature = "sync")]
+#[cfg_attr(miri, ignore)]
+#[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+fn test_open_legacy_version() {
+  use crate::{
+    options::ChecksumLen,
+    sync::{ImmutableValueLog, ValueLog},
+    PreadValueLog,
+  };
+  use dbutils::checksum::{BuildChecksumer, Crc32};
+  use rarena_allocator::{sync::Arena, Buffer, Options as ArenaOptions};
+
+  let dir = tempfile::tempdir().unwrap();
+  let p = dir.path().join("test_open_legacy_version");
+
+  // the version `0` layout: the magic text and the magic version, followed by the allocator of version `0`,
+  // the values are followed by their 8 bytes checksums.
+  let (offset, size) = unsafe {
+    let arena = ArenaOptions::new()
+      .with_capacity(1024)
+      .with_magic_version(0)
+      .with_reserved(8 + 4)
+      .with_freelist(rarena_allocator::Freelist::None)
+      .with_unify(true)
+      .with_create_new(true)
+      .with_read(true)
+      .with_write(true)
+      .map_mut::<Arena, _>(&p)
+      .unwrap();
+    let header = arena.reserved_slice_mut();
+    header[..6].copy_from_slice(b"valog!");
+    header[6..8].copy_from_slice(&0u16.to_le_bytes());
+    header[8..].copy_from_slice(b"vlog");
+
+    let value = b"Hello, valog!";
+    let mut buf = arena.alloc_bytes(value.len() as u32 + 8).unwrap();
+    buf.detach();
+    buf.put_slice_unchecked(value);
+    buf.put_u64_le_unchecked(Crc32::new().checksum_one(value));
+    let offset = buf.offset() as u32;
+    arena.flush().unwrap();
+    (offset, value.len() as u32)
+  };
+
+  let log = unsafe {
+    Builder::new()
+      .with_reserved(4)
+      .with_read(true)
+      .map::<ImmutableValueLog, _>(&p, 0)
+      .unwrap()
+  };
+  assert_eq!(log.options().checksum_len(), ChecksumLen::Eight);
+  assert_eq!(unsafe { log.reserved_slice() }, b"vlog");
+  assert_eq!(
+    unsafe { log.read(&0, offset, size).unwrap() },
+    b"Hello, valog!"
+  );
+  assert!(log.footer().unwrap().is_none());
+  drop(log);
+
+  let log = unsafe {
+    Builder::new()
+      .with_reserved(4)
+      .with_read(true)
+      .with_write(true)
+      .map_mut::<ValueLog, _>(&p, 0)
+      .unwrap()
+  };
+  assert_eq!(
+    unsafe { log.read(&0, offset, size).unwrap() },
+    b"Hello, valog!"
+  );
+  let vp = log.insert(b"appended").unwrap();
+  assert!(matches!(
+    log.write_footer(&[vp]).unwrap_err(),
+    Error::InvalidOptions { .. }
+  ));
+  drop(log);
+
+  let log: PreadValueLog<u32> = Builder::new().with_reserved(4).open_pread(&p, 0).unwrap();
+  assert_eq!(log.read(&0, offset, size).unwrap(), b"Hello, valog!");
+  assert_eq!(log.read(&0, vp.offset(), vp.size()).unwrap(), b"appended");
+
+  // the version `0` layout always uses 8 bytes checksums.
+  let err = unsafe {
+    Builder::new()
+      .with_reserved(4)
+      .with_checksum_len(ChecksumLen::Four)
+      .with_read(true)
+      .map::<ImmutableValueLog, _>(&p, 0)
+      .unwrap_err()
+  };
+  assert!(matches!(
+    *err.into_inner().unwrap().downcast::<Error>().unwrap(),
+    Error::OptionsMismatch {
+      field: "checksum_len"
+    }
+  ));
+}
+
+#[test]
+#[cfg(feature = "sync")]
+#[cfg_attr(miri, ignore)]
+#[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+fn test_reopen_options_mismatch() {
+  use crate::{
+    options::{ChecksumLen, Freelist},
+    sync::{ImmutableValueLog, ValueLog},
+  };
+
+  let dir = tempfile::tempdir().unwrap();
+  let p = dir.path().join("test_reopen_options_mismatch");
+
+  let log = unsafe {
+    Builder::new()
+      .with_capacity(MB)
+      .with_reserved(8)
+      .with_create_new(true)
+      .with_read(true)
+      .with_write(true)
+      .map_mut::<ValueLog, _>(&p, 0)
+      .unwrap()
+  };
+  let vp = log.insert(b"Hello, valog!").unwrap();
+  drop(log);
+
+  let field_of = |err: std::io::Error| {
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+    match *err.into_inner().unwrap().downcast::<Error>().unwrap() {
+      Error::OptionsMismatch { field } => field,
+      err => panic!("unexpected error: {err}"),
+    }
+  };
+
+  for reserved in [0, 4, 16] {
+    let err = unsafe {
+      Builder::new()
+        .with_reserved(reserved)
+        .with_read(true)
+        .map::<ImmutableValueLog, _>(&p, 0)
+        .unwrap_err()
+    };
+    assert_eq!(field_of(err), "reserved");
+
+    let err = unsafe {
+      Builder::new()
+        .with_reserved(reserved)
+        .with_read(true)
+        .with_write(true)
+        .map_mut::<ValueLog, _>(&p, 0)
+        .unwrap_err()
+    };
+    assert_eq!(field_of(err), "reserved");
+  }
+
+  let err = unsafe {
+    Builder::new()
+      .with_reserved(8)
+      .with_checksum_len(ChecksumLen::Four)
+      .with_read(true)
+      .map::<ImmutableValueLog, _>(&p, 0)
+      .unwrap_err()
+  };
+  assert_eq!(field_of(err), "checksum_len");
+
+  let err = unsafe {
+    Builder::new()
+      .with_reserved(8)
+      .with_freelist(Freelist::Optimistic)
+      .with_read(true)
+      .with_write(true)
+      .map_mut::<ValueLog, _>(&p, 0)
+      .unwrap_err()
+  };
+  assert_eq!(field_of(err), "freelist");
+
+  // the options which do not affect the on-disk layout are exempt.
+  let log = unsafe {
+    Builder::new()
+      .with_reserved(8)
+      .with_sync(false)
+      .with_read(true)
+      .with_write(true)
+      .map_mut::<ValueLog, _>(&p, 0)
+      .unwrap()
+  };
+  let data = unsafe { log.read(log.id(), vp.offset(), vp.size()).unwrap() };
+  assert_eq!(data, b"Hello, valog!");
+}
+
+#[test]
+#[cfg(feature = "sync")]
+fn test_merge_from() {
+  let src = Builder::new()
+    .with_capacity(1024)
+    .alloc::<crate::sync::ValueLog>(0)
+    .unwrap();
+  let ptrs = [
+    src.insert(b"alpha").unwrap(),
+    src.insert(&[]).unwrap(),
+    src.insert_tombstone(b"beta").unwrap(),
+  ];
+
+  let dst = Builder::new()
+    .with_capacity(1024)
+    .alloc::<crate::unsync::ValueLog>(1)
+    .unwrap();
+  dst.insert(b"existing").unwrap();
+  let new_ptrs = unsafe { dst.merge_from(&src, &ptrs).unwrap() };
+  assert_eq!(new_ptrs.len(), ptrs.len());
+
+  for (old, new) in ptrs.iter().zip(new_ptrs.iter()) {
+    assert_eq!(*new.id(), 1);
+    assert_eq!(old.is_tombstone(), new.is_tombstone());
+    let expected = unsafe { src.read(src.id(), old.offset(), old.size()).unwrap() };
+    let data = unsafe { dst.read(dst.id(), new.offset(), new.size()).unwrap() };
+    assert_eq!(data, expected);
+  }
+  assert_eq!(dst.discarded(), 4);
+
+  let err = unsafe {
+    dst
+      .merge_from(&dst, &[ValuePointer::new(0, 0, 1)])
+      .unwrap_err()
+  };
+  assert!(matches!(err, Error::IdMismatch));
+}
+
+#[test]
+#[cfg(feature = "sync")]
+fn test_copy_raw_from() {
+  let src = Builder::new()
+    .with_capacity(1024)
+    .with_checksum_len(options::ChecksumLen::Four)
+    .alloc::<crate::sync::ValueLog>(0)
+    .unwrap();
+  let vp = src.insert(b"Hello, valog!").unwrap();
+  let cks_len = src.options().checksum_len();
+  let entry_len = vp.size() + cks_len.len() as u32;
+
+  let dst = Builder::new()
+    .with_capacity(1024)
+    .with_checksum_len(options::ChecksumLen::Four)
+    .with_value_alignment(8)
+    .alloc::<crate::sync::ValueLog>(1)
+    .unwrap();
+  dst.insert(b"existing").unwrap();
+
+  let new_vp = dst.copy_raw_from(&src, vp.offset(), entry_len).unwrap();
+  assert_eq!(*new_vp.id(), 1);
+  assert_eq!(new_vp.size(), vp.size());
+  assert_eq!(new_vp.offset() % 8, 0);
+  let src_entry =
+    &src.allocator().allocated_memory()[vp.range(cks_len).start as usize..][..entry_len as usize];
+  let dst_entry = &dst.allocator().allocated_memory()[new_vp.range(cks_len).start as usize..]
+    [..entry_len as usize];
+  assert_eq!(src_entry, dst_entry);
+  let data = unsafe { dst.read(dst.id(), new_vp.offset(), new_vp.size()).unwrap() };
+  assert_eq!(data, b"Hello, valog!");
+
+  // the bytes are copied as is, the checksum is not validated
+  unsafe {
+    *src.allocator().get_pointer_mut(vp.offset() as usize) = b'h';
+  }
+  let new_vp = dst.copy_raw_from(&src, vp.offset(), entry_len).unwrap();
+  let err = unsafe {
+    dst
+      .read(dst.id(), new_vp.offset(), new_vp.size())
+      .unwrap_err()
+  };
+  assert!(matches!(err, Error::ChecksumMismatch));
+
+  let err = dst
+    .copy_raw_from(&src, vp.offset(), entry_len + 1)
+    .unwrap_err();
+  assert!(matches!(err, Error::OutOfBounds { .. }));
+  let err = dst.copy_raw_from(&src, 0, entry_len).unwrap_err();
+  assert!(matches!(err, Error::OutOfBounds { .. }));
+
+  let other = Builder::new()
+    .with_capacity(1024)
+    .alloc::<crate::sync::ValueLog>(2)
+    .unwrap();
+  let err = other
+    .copy_raw_from(&src, vp.offset(), entry_len)
+    .unwrap_err();
+  assert!(matches!(err, Error::IncompatibleLayout));
+}
+
+#[test]
+#[cfg(feature = "sync")]
+fn test_reserved_regions() {
+  let log = Builder::new()
+    .with_capacity(100)
+    .with_reserved_regions(&[2, 0, 3])
+    .alloc::<crate::sync::ValueLog>(0)
+    .unwrap();
+
+  unsafe {
+    log.reserved_region_mut(0).copy_from_slice(b"ab");
+    log.reserved_region_mut(2).copy_from_slice(b"cde");
+    assert!(log.reserved_region(1).is_empty());
+    assert_eq!(log.reserved_slice(), b"abcde");
+  }
+
+  let vp = log.insert(b"Hello, valog!").unwrap();
+  let data = unsafe { log.read(log.id(), vp.offset(), vp.size()).unwrap() };
+  assert_eq!(data, b"Hello, valog!");
+  unsafe {
+    assert_eq!(log.reserved_region(0), b"ab");
+    assert_eq!(log.reserved_region(2), b"cde");
+  }
+}
+
+#[test]
+#[cfg(feature = "sync")]
+fn test_reserved_empty() {
+  let log = Builder::new()
+    .with_capacity(100)
+    .alloc::<crate::sync::ValueLog>(0)
+    .unwrap();
+  assert_eq!(log.reserved_len(), 0);
+
+  let vp = log.insert(b"Hello, valog!").unwrap();
+  let header = log.allocator().reserved_slice().to_vec();
+  unsafe {
+    let reserved = log.reserved_slice_mut();
+    assert!(reserved.is_empty());
+    reserved.fill(0xFF);
+    reserved.copy_from_slice(&[]);
+    assert!(log.reserved_slice().is_empty());
+  }
+
+  // the header and the values are untouched
+  assert_eq!(log.allocator().reserved_slice(), header);
+  let data = unsafe { log.read(log.id(), vp.offset(), vp.size()).unwrap() };
+  assert_eq!(data, b"Hello, valog!");
+}
+
+#[test]
+#[cfg(feature = "sync")]
+#[should_panic]
+fn test_reserved_region_out_of_bounds() {
+  let log = Builder::new()
+    .with_capacity(100)
+    .with_reserved(8)
+    .alloc::<crate::sync::ValueLog>(0)
+    .unwrap();
+
+  unsafe {
+    log.reserved_region(1);
+  }
+}
+
+#[test]
+#[cfg(feature = "sync")]
+fn test_value_alignment() {
+  let log = Builder::new()
+    .with_capacity(1024)
+    .with_value_alignment(16)
+    .alloc::<crate::sync::ValueLog>(0)
+    .unwrap();
+
+  let mut vps = Vec::new();
+  for i in 1..=5u8 {
+    let value = vec![i; i as usize * 3];
+    let vp = log.insert(&value).unwrap();
+    assert_eq!(vp.offset() % 16, 0);
+    assert_eq!(log.allocator().raw_ptr() as usize % 16, 0);
+    vps.push((vp, value));
+  }
+
+  for (vp, value) in vps {
+    let data = unsafe { log.read(log.id(), vp.offset(), vp.size()).unwrap() };
+    assert_eq!(data, value.as_slice());
+  }
+  assert_eq!(log.discarded(), 5 * 15);
+}
+
+#[test]
+#[cfg(feature = "sync")]
+#[cfg_attr(miri, ignore)]
+#[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+fn test_insert_read_only() {
+  use crate::sync::{ImmutableValueLog, ValueLog};
+  use rarena_allocator::either::Either;
+
+  let dir = tempfile::tempdir().unwrap();
+  let p = dir.path().join("test_insert_read_only");
+
+  let log = unsafe {
+    Builder::new()
+      .with_capacity(1024)
+      .with_create_new(true)
+      .with_read(true)
+      .with_write(true)
+      .map_mut::<ValueLog, _>(&p, 0)
+      .unwrap()
+  };
+  log.insert(b"Hello, valog!").unwrap();
+  drop(log);
+
+  let log = unsafe {
+    Builder::new()
+      .with_read(true)
+      .map::<ImmutableValueLog, _>(&p, 0)
+      .unwrap()
+  };
+
+  // route the writes to the read-only memory map through a writable handle
+  let arena = log.allocator().clone();
+  let start = arena.data_offset() as u32;
+  let end = arena.capacity() as u32;
+  let writer = Builder::new()
+    .with_external_arena::<ValueLog>(arena, start..end, 0)
+    .unwrap();
+  assert!(writer.is_readonly());
+
+  let err = writer.can_insert(3).unwrap_err();
+  assert!(matches!(err, Error::ReadOnly));
+  let err = writer.insert(b"foo").unwrap_err();
+  assert!(matches!(err, Error::ReadOnly));
+  let err = writer
+    .insert_with(ValueBuilder::new(3, |buf: &mut VacantBuffer<'_>| {
+      buf.put_slice(b"foo")
+    }))
+    .unwrap_err();
+  assert!(matches!(err, Either::Right(Error::ReadOnly)));
+  assert!(writer.buffered().insert(b"foo").is_err());
+}
+
+#[test]
+#[cfg(feature = "sync")]
+fn test_max_entries() {
+  let log = Builder::new()
+    .with_capacity(1024)
+    .with_max_entries(4)
+    .alloc::<crate::sync::ValueLog>(0)
+    .unwrap();
+  assert_eq!(log.entries(), 0);
+
+  // empty values are not counted
+  log.insert(b"").unwrap();
+  log.insert(b"foo").unwrap();
+  assert_eq!(log.entries(), 1);
+
+  // the failed inserts do not consume the entries
+  assert!(log.insert(&[0; 2048]).is_err());
+  assert_eq!(log.entries(), 1);
+
+  // the clones share the counter
+  let clone = log.clone();
+  clone.insert_tombstone(b"bar").unwrap();
+  assert_eq!(log.entries(), 2);
+
+  // none of the values is inserted if the cap is exceeded
+  let allocated = log.allocator().allocated();
+  let err = log.insert_all(&[b"a", b"b", b"c"]).unwrap_err();
+  assert!(matches!(err, Error::MaxEntriesReached { maximum: 4 }));
+  assert_eq!(log.allocator().allocated(), allocated);
+  log.insert_all(&[b"a", b"", b"b"]).unwrap();
+  assert_eq!(log.entries(), 4);
+
+  let err = log.insert(b"baz").unwrap_err();
+  assert!(matches!(err, Error::MaxEntriesReached { maximum: 4 }));
+  let err = log.buffered().insert(b"baz").unwrap_err();
+  assert!(matches!(err, Error::MaxEntriesReached { maximum: 4 }));
+  let err = log.insert_generic(&"baz".to_string()).unwrap_err();
+  assert!(matches!(
+    err,
+    rarena_allocator::either::Either::Right(Error::MaxEntriesReached { .. })
+  ));
+  assert_eq!(log.entries(), 4);
+}
+
+#[test]
+#[cfg(feature = "sync")]
+fn test_simd_value_alignment() {
+  fn check(log: &crate::sync::ValueLog, align: usize) {
+    for i in 1..=5u8 {
+      let value = vec![i; i as usize * 7];
+      let vp = log.insert(&value).unwrap();
+      let data = unsafe { log.read(log.id(), vp.offset(), vp.size()).unwrap() };
+      assert_eq!(data, value.as_slice());
+      assert_eq!(data.as_ptr() as usize % align, 0);
+    }
+  }
+
+  for align in [32, options::MAX_VALUE_ALIGNMENT] {
+    let log = Builder::new()
+      .with_capacity(1024)
+      .with_value_alignment(align)
+      .alloc::<crate::sync::ValueLog>(0)
+      .unwrap();
+    check(&log, align);
+
+    #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+    {
+      let log = Builder::new()
+        .with_capacity(1024)
+        .with_value_alignment(align)
+        .map_anon::<crate::sync::ValueLog>(0)
+        .unwrap();
+      check(&log, align);
+    }
+  }
+}
+
+#[test]
+#[should_panic]
+fn test_value_alignment_too_large() {
+  let _ = Builder::new().with_value_alignment(options::MAX_VALUE_ALIGNMENT * 2);
+}
+
+#[test]
+#[cfg(feature = "sync")]
+#[cfg(any(feature = "std", feature = "alloc"))]
+fn test_value_pointer_display() {
+  use std::string::ToString;
+
+  let vp = ValuePointer::new(3u32, 128, 64);
+  assert_eq!(vp.to_string(), "vp(id=3, off=128, size=64)");
+
+  let log = Builder::new()
+    .with_capacity(100)
+    .alloc::<crate::sync::ValueLog>(1)
+    .unwrap();
+  let vp = log.insert_tombstone(b"Hello, valog!").unwrap();
+  assert_eq!(
+    vp.to_string(),
+    std::format!("vp(id=1, off={}, size=13, tombstone)", vp.offset())
+  );
+}
+
+#[test]
+#[cfg(feature = "sync")]
+fn test_read_raw_range() {
+  let log = Builder::new()
+    .with_capacity(100)
+    .with_unify(true)
+    .alloc::<crate::sync::ValueLog>(0)
+    .unwrap();
+  let vp = log.insert(b"Hello, valog!").unwrap();
+
+  unsafe {
+    // the header is readable
+    assert_eq!(log.read_raw_range(0, 6).unwrap(), b"valog!");
+
+    let allocated = log.allocator().allocated();
+    let entry = log
+      .read_raw_range(vp.offset() as usize, allocated - vp.offset() as usize)
+      .unwrap();
+    assert_eq!(&entry[..13], b"Hello, valog!");
+    assert_eq!(
+      u64::from_le_bytes(entry[13..21].try_into().unwrap()),
+      log.checksum(b"Hello, valog!")
+    );
+
+    assert!(log.read_raw_range(allocated, 0).unwrap().is_empty());
+    let err = log.read_raw_range(allocated, 1).unwrap_err();
+    assert!(matches!(err, Error::OutOfBounds { .. }));
+    let err = log.read_raw_range(usize::MAX, 2).unwrap_err();
+    assert!(matches!(err, Error::OutOfBounds { .. }));
+  }
+}
+
+#[test]
+#[cfg(feature = "sync")]
+fn test_null_value_pointer() {
+  let log = Builder::new()
+    .with_capacity(100)
+    .alloc::<crate::sync::ValueLog>(0)
+    .unwrap();
+
+  let null = ValuePointer::null(0u32);
+  assert!(null.is_null());
+  assert!(!null.is_tombstone());
+
+  let empty = log.insert(&[]).unwrap();
+  assert!(!empty.is_null());
+  assert_ne!(null, empty);
+  assert!(!log.insert(b"Hello, valog!").unwrap().is_null());
+
+  assert_eq!(log.peek(&null).unwrap(), 0);
+  let data = unsafe { log.read(null.id(), null.offset(), null.size()).unwrap() };
+  assert!(data.is_empty());
+}
+
+#[test]
+#[cfg(feature = "wasm")]
+fn test_wasm_builder() {
+  use crate::{
+    options::ChecksumLen,
+    wasm::{Builder, GenericValueLog, ImmutableValueLog, ValueLog},
+  };
+
+  let builder = Builder::new()
+    .with_capacity(1024)
+    .with_unify(true)
+    .with_reserved(4)
+    .with_checksum_len(ChecksumLen::Four)
+    .with_value_alignment(4)
+    .with_max_entries(2);
+  assert_eq!(builder.options().capacity(), 1024);
+  assert_eq!(builder.options().reserved(), 4);
+
+  let log = builder.clone().alloc::<ValueLog>(1).unwrap();
+  assert_eq!(log.checksum_len(), 4);
+  let vp = log.insert(b"Hello, valog!").unwrap();
+  assert_eq!(vp.offset() % 4, 0);
+  log.insert(b"foo").unwrap();
+  assert!(matches!(
+    log.insert(b"bar"),
+    Err(Error::MaxEntriesReached { maximum: 2 })
+  ));
+
+  let buf = log.allocator().allocated_memory().to_vec();
+  let frozen = builder.from_bytes::<ImmutableValueLog>(&buf, 1).unwrap();
+  let data = unsafe { frozen.read(frozen.id(), vp.offset(), vp.size()).unwrap() };
+  assert_eq!(data, b"Hello, valog!");
+  assert!(matches!(
+    Builder::new().from_bytes::<ImmutableValueLog>(&buf[..4], 1),
+    Err(Error::InvalidLayout)
+  ));
+
+  let generic = Builder::default()
+    .with_capacity(1024)
+    .alloc::<GenericValueLog<std::string::String>>(0)
+    .unwrap();
+  let vp = generic.insert(&"Hello, valog!".into()).unwrap();
+  let data = unsafe { generic.read(generic.id(), vp.offset(), vp.size()).unwrap() };
+  assert_eq!(data, "Hello, valog!");
+}
+
+#[test]
+#[cfg(feature = "sync")]
+fn test_insert_empty() {
+  for unify in [false, true] {
+    let log = Builder::new()
+      .with_capacity(100)
+      .with_unify(unify)
+      .with_value_alignment(4)
+      .alloc::<crate::sync::ValueLog>(0)
+      .unwrap();
+
+    let before = log.allocator().allocated();
+    let stored = log.insert_empty().unwrap();
+    assert!(stored.is_stored());
+    assert!(!stored.is_null());
+    assert_eq!(stored.size(), 0);
+    assert_eq!(stored.offset() % 4, 0);
+    assert!(stored.offset() as usize >= log.data_offset());
+    assert_eq!(log.allocator().allocated(), before + 8 + 3);
+    assert_eq!(log.entries(), 1);
+
+    // the checksum of the empty value is stored in the entry
+    let cks = unsafe { log.read_raw_range(stored.offset() as usize, 8).unwrap() };
+    assert_eq!(cks, log.checksum(&[]).to_le_bytes());
+
+    let data = unsafe { log.read(log.id(), stored.offset(), stored.size()).unwrap() };
+    assert!(data.is_empty());
+
+    let unstored = log.insert(&[]).unwrap();
+    assert!(!unstored.is_stored());
+    assert_eq!(log.entries(), 1);
+    assert!(log.insert(b"foo").unwrap().is_stored());
+  }
+}
+
+#[test]
+#[cfg(feature = "sync")]
+fn test_total_size() {
+  let log = Builder::new()
+    .with_capacity(1024)
+    .with_value_alignment(16)
+    .alloc::<crate::sync::ValueLog>(0)
+    .unwrap();
+
+  let before = log.allocator().allocated();
+  let ptrs = [
+    log.insert(b"Hello").unwrap(),
+    log.insert(b"").unwrap(),
+    log.insert(b"valog!").unwrap(),
+  ];
+  let consumed = (log.allocator().allocated() - before) as u64;
+
+  assert_eq!(log.total_size(&ptrs, false), 5 + 6 + 2 * 8);
+  assert_eq!(log.total_size(&ptrs, true), consumed);
+  assert_eq!(log.total_size(&[], true), 0);
+}
+
+#[test]
+#[cfg(feature = "sync")]
+fn test_freelist_stats() {
+  let log = Builder::new()
+    .with_capacity(100)
+    .with_value_alignment(4)
+    .with_freelist(options::Freelist::Pessimistic)
+    .alloc::<crate::sync::ValueLog>(0)
+    .unwrap();
+
+  let stats = log.freelist_stats();
+  assert_eq!(stats.kind(), options::Freelist::Pessimistic);
+  assert_eq!(stats.discarded(), 0);
+  assert_eq!(
+    stats.minimum_segment_size(),
+    log.allocator().minimum_segment_size()
+  );
+
+  log.insert_tombstone(b"Hello, valog!").unwrap();
+  assert_eq!(log.freelist_stats().discarded(), 13 + 3);
+}
+
+#[test]
+#[cfg(feature = "sync")]
+fn test_entry_checksum() {
+  for cks_len in [options::ChecksumLen::Four, options::ChecksumLen::Eight] {
+    let log = Builder::new()
+      .with_capacity(100)
+      .with_checksum_len(cks_len)
+      .alloc::<crate::sync::ValueLog>(0)
+      .unwrap();
+
+    let vp = log.insert(b"Hello, valog!").unwrap();
+    let range = vp.range(cks_len);
+    let stored =
+      cks_len.decode(&log.allocator().memory()[range.start as usize + 13..range.end as usize]);
+    assert_eq!(log.entry_checksum(b"Hello, valog!"), stored);
+  }
+}
+
+#[test]
+#[cfg(feature = "sync")]
+#[cfg(any(feature = "alloc", feature = "std"))]
+fn test_insert_all() {
+  let log = Builder::new()
+    .with_capacity(1024)
+    .with_value_alignment(8)
+    .alloc::<crate::sync::ValueLog>(0)
+    .unwrap();
+
+  let values: [&[u8]; 4] = [b"foo", b"", b"Hello, valog!", b"bar"];
+  let ptrs = log.insert_all(&values).unwrap();
+  assert_eq!(ptrs.len(), values.len());
+  assert_eq!(log.discarded(), 3 * 7);
+  assert_eq!(
+    log.total_size(&ptrs, true),
+    log.total_size(&ptrs, false) + 3 * 7
+  );
+
+  for (vp, value) in ptrs.iter().zip(values) {
+    assert_eq!(vp.offset() % 8, 0);
+    let data = unsafe { log.read(log.id(), vp.offset(), vp.size()).unwrap() };
+    assert_eq!(data, value);
+  }
+
+  let allocated = log.allocator().allocated();
+  let err = log.insert_all(&[&[0; 512], &[0; 512]]).unwrap_err();
+  assert!(matches!(err, Error::InsufficientSpace { .. }));
+  assert_eq!(log.allocator().allocated(), allocated);
+
+  assert!(log
+    .insert_all(&[b"", b""])
+    .unwrap()
+    .iter()
+    .all(|vp| vp.size() == 0));
+}
+
+#[test]
+#[cfg(any(feature = "alloc", feature = "std"))]
+fn test_read_owned() {
+  use std::vec::Vec;
+
+  // uses `unsync` and `std::vec::Vec` only, so that it also builds with `alloc` but without `std`.
+  let log = Builder::new()
+    .with_capacity(100)
+    .alloc::<crate::unsync::ValueLog>(0)
+    .unwrap();
+
+  let vp = log.insert(b"Hello, valog!").unwrap();
+  let data = unsafe { log.read_owned(log.id(), vp.offset(), vp.size()).unwrap() };
+  assert_eq!(data, b"Hello, valog!");
+
+  let mut buf = Vec::from(&b"> "[..]);
+  unsafe {
+    log
+      .read_to_owned(log.id(), vp.offset(), vp.size(), &mut buf)
+      .unwrap()
+  };
+  assert_eq!(buf, b"> Hello, valog!");
+
+  let err = unsafe {
+    log
+      .read_to_owned(&1, vp.offset(), vp.size(), &mut buf)
+      .unwrap_err()
+  };
+  assert!(matches!(err, error::Error::IdMismatch));
+  assert_eq!(buf, b"> Hello, valog!");
+}
+
+#[test]
+#[cfg(any(feature = "alloc", feature = "std"))]
+fn test_read_shared() {
+  let log = Builder::new()
+    .with_capacity(100)
+    .alloc::<crate::unsync::ValueLog>(0)
+    .unwrap();
+
+  let vp = log.insert(b"Hello, valog!").unwrap();
+  let data = unsafe { log.read_shared(log.id(), vp.offset(), vp.size()).unwrap() };
+  let shared = data.clone();
+  assert_eq!(std::sync::Arc::strong_count(&data), 2);
+  assert_eq!(&*shared, b"Hello, valog!");
+
+  let empty = unsafe { log.read_shared(log.id(), 0, 0).unwrap() };
+  assert!(empty.is_empty());
+
+  let err = unsafe { log.read_shared(&1, vp.offset(), vp.size()).unwrap_err() };
+  assert!(matches!(err, error::Error::IdMismatch));
+}
+
+#[test]
+#[cfg(feature = "sync")]
+fn test_little_endian_format() {
+  let log = Builder::new()
+    .with_capacity(100)
+    .with_unify(true)
+    .with_magic_version(0x0102)
+    .alloc::<crate::sync::ValueLog>(0)
+    .unwrap();
+
+  let header = log.allocator().reserved_slice();
+  assert_eq!(&header[6..8], &[0x02, 0x01]);
+
+  for cks_len in [options::ChecksumLen::Four, options::ChecksumLen::Eight] {
+    let log = Builder::new()
+      .with_capacity(100)
+      .with_checksum_len(cks_len)
+      .alloc::<crate::sync::ValueLog>(0)
+      .unwrap();
+
+    let vp = log.insert(b"Hello, valog!").unwrap();
+    let start = vp.offset() as usize + 13;
+    let stored = &log.allocator().memory()[start..start + cks_len.len()];
+    assert_eq!(
+      stored,
+      &log.checksum(b"Hello, valog!").to_le_bytes()[..cks_len.len()]
+    );
+  }
+}
+
+#[test]
+#[cfg(feature = "sync")]
+fn test_set_id() {
+  let mut log = Builder::new()
+    .with_capacity(100)
+    .alloc::<crate::sync::GenericValueLog<String>>(0)
+    .unwrap();
+
+  let vp = log.insert(&"Hello, valog!".to_string()).unwrap();
+  log.set_id(1);
+  assert_eq!(*log.id(), 1);
+
+  let err = unsafe { log.read(vp.id(), vp.offset(), vp.size()).unwrap_err() };
+  assert!(matches!(err, Error::IdMismatch));
+
+  let vp = ValuePointer::new(1, vp.offset(), vp.size());
+  let data = unsafe { log.read(vp.id(), vp.offset(), vp.size()).unwrap() };
+  assert_eq!(data, "Hello, valog!");
+}
+
+#[test]
+#[cfg(feature = "sync")]
+fn test_read_generic_many() {
+  let log = Builder::new()
+    .with_capacity(1024)
+    .alloc::<crate::sync::GenericValueLog<String>>(0)
+    .unwrap();
+
+  let mut ptrs = (0..10)
+    .map(|i| log.insert(&i.to_string()).unwrap())
+    .collect::<Vec<_>>();
+  ptrs.push(ValuePointer::new(1, ptrs[0].offset(), ptrs[0].size()));
+
+  let mut iter = unsafe { log.read_generic_many(&ptrs) };
+  for i in 0..10 {
+    assert_eq!(iter.next().unwrap().unwrap(), i.to_string());
+  }
+  assert!(matches!(iter.next().unwrap(), Err(Error::IdMismatch)));
+  assert!(iter.next().is_none());
+}
+
+#[test]
+#[cfg(feature = "sync")]
+#[cfg(any(feature = "alloc", feature = "std"))]
+fn test_insert_stream() {
+  use core::ops::ControlFlow;
+
+  let log = Builder::new()
+    .with_capacity(1024)
+    .alloc::<crate::sync::ValueLog>(0)
+    .unwrap();
+
+  let mut progress = std::vec::Vec::new();
+  let ptrs = log
+    .insert_stream(["a", "bb", "ccc"], |done, written| {
+      progress.push((done, written));
+      ControlFlow::Continue(())
+    })
+    .unwrap();
+  assert_eq!(ptrs.len(), 3);
+  assert_eq!(progress, [(1, 1), (2, 3), (3, 6)]);
+
+  let ptrs = log
+    .insert_stream(["a", "bb", "ccc"], |done, _| {
+      if done == 1 {
+        ControlFlow::Break(())
+      } else {
+        ControlFlow::Continue(())
+      }
+    })
+    .unwrap();
+  assert_eq!(ptrs.len(), 1);
+
+  let big = [0u8; 512];
+  let (ptrs, err) = log
+    .insert_stream([&big[..], &big[..], &big[..]], |_, _| {
+      ControlFlow::Continue(())
+    })
+    .unwrap_err();
+  assert_eq!(ptrs.len(), 1);
+  assert!(matches!(err, Error::InsufficientSpace { .. }));
+  let data = unsafe {
+    log
+      .read(log.id(), ptrs[0].offset(), ptrs[0].size())
+      .unwrap()
+  };
+  assert_eq!(data, big);
+}
+
+#[test]
+#[cfg(feature = "sync")]
+fn test_external_arena() {
+  let log = Builder::new()
+    .with_capacity(1024)
+    .alloc::<crate::sync::ValueLog>(0)
+    .unwrap();
+  let arena = log.allocator().clone();
+  let start = arena.allocated() as u32;
+  let mid = start + 64;
+
+  let err = Builder::new()
+    .with_external_arena::<crate::sync::ValueLog>(arena.clone(), start..2048, 1)
+    .unwrap_err();
+  assert!(matches!(err, Error::OutOfBounds { .. }));
+
+  let a = Builder::new()
+    .with_external_arena::<crate::sync::ValueLog>(arena.clone(), start..mid, 1)
+    .unwrap();
+  let b = Builder::new()
+    .with_external_arena::<crate::sync::ValueLog>(arena, mid..1024, 2)
+    .unwrap();
+
+  let vp = a.insert(b"Hello, valog!").unwrap();
+  assert!(vp.offset() >= start && vp.offset() < mid);
+  let data = unsafe { a.read(a.id(), vp.offset(), vp.size()).unwrap() };
+  assert_eq!(data, b"Hello, valog!");
+
+  // the value of `a` is out of the range of `b`
+  let err = unsafe { b.read(b.id(), vp.offset(), vp.size()).unwrap_err() };
+  assert!(matches!(err, Error::OutOfBounds { .. }));
+
+  // the next value does not fit in the range of `a`
+  let err = a.insert(&[0; 64]).unwrap_err();
+  assert!(matches!(err, Error::InsufficientSpace { .. }));
+
+  let vp = b.insert(&[1; 64]).unwrap();
+  assert!(vp.offset() >= mid);
+  let data = unsafe { b.read(b.id(), vp.offset(), vp.size()).unwrap() };
+  assert_eq!(data, [1; 64]);
+
+  // the inserts of `b` do not take the tail of the range of `a`
+  let vp = a.insert(b"a").unwrap();
+  assert!(vp.offset() >= start && vp.offset() < mid);
+  let data = unsafe { a.read(a.id(), vp.offset(), vp.size()).unwrap() };
+  assert_eq!(data, b"a");
+  let a2 = a.clone();
+  let vp = a2.insert(b"b").unwrap();
+  assert!(vp.offset() >= start && vp.offset() < mid);
+  assert_eq!(a.usage(), a2.usage());
+
+  // the values of a log end at its own cursor
+  let err = unsafe { a.read(a.id(), vp.offset() + 9, 1).unwrap_err() };
+  assert!(matches!(err, Error::OutOfBounds { .. }));
+
+  // the whole arena is claimed by the sub-ranges
+  let err = log.insert(b"c").unwrap_err();
+  assert!(matches!(err, Error::InsufficientSpace { .. }));
+  let err = Builder::new()
+    .with_external_arena::<crate::sync::ValueLog>(log.allocator().clone(), start..mid, 3)
+    .unwrap()
+    .insert(b"c")
+    .unwrap_err();
+  assert!(matches!(err, Error::InsufficientSpace { .. }));
+}
+
+#[test]
+#[cfg(feature = "sync")]
+fn test_usage() {
+  let log = Builder::new()
+    .with_capacity(100)
+    .alloc::<crate::sync::ValueLog>(0)
+    .unwrap();
+  let unsync = Builder::new()
+    .with_capacity(100)
+    .alloc::<crate::unsync::ValueLog>(0)
+    .unwrap();
+  assert_eq!(log.usage(), unsync.usage());
+
+  log.insert(&[0; 26]).unwrap();
+  unsync.insert(&[0; 26]).unwrap();
+  assert_eq!(log.usage(), 0.7);
+  assert_eq!(unsync.usage(), 0.7);
+  assert!(log.near_full(0.5) && unsync.near_full(0.5));
+  assert!(!log.near_full(0.7) && !unsync.near_full(0.7));
+
+  // the usage of a log in an external arena is relative to its range
+  let arena = Builder::new()
+    .with_capacity(1024)
+    .alloc::<crate::sync::ValueLog>(0)
+    .unwrap()
+    .allocator()
+    .clone();
+  let start = arena.allocated() as u32;
+  let sub = Builder::new()
+    .with_external_arena::<crate::sync::ValueLog>(arena, start..start + 100, 1)
+    .unwrap();
+  assert_eq!(sub.usage(), 0.0);
+  sub.insert(&[0; 42]).unwrap();
+  assert_eq!(sub.usage(), 0.5);
+}
+
+#[test]
+#[cfg(all(feature = "std", feature = "sync"))]
+fn test_flush_to_range() {
+  let log = Builder::new()
+    .with_capacity(1024)
+    .alloc::<crate::sync::ValueLog>(0)
+    .unwrap();
+
+  let mut backup = std::vec::Vec::new();
+  log.flush_to(&mut backup).unwrap();
+
+  let mut last = backup.len();
+  for value in [&b"foo"[..], b"bar", b"baz"] {
+    log.insert(value).unwrap();
+    let allocated = log.allocator().allocated();
+    log.flush_to_range(&mut backup, last, allocated).unwrap();
+    last = allocated;
+  }
+
+  let mut full = std::vec::Vec::new();
+  log.flush_to(&mut full).unwrap();
+  assert_eq!(backup, full);
+
+  let err = log.flush_to_range(&mut backup, last, last + 1).unwrap_err();
+  assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+  let err = log.flush_to_range(&mut backup, last, 0).unwrap_err();
+  assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+}
+
+#[test]
+#[cfg(all(feature = "std", feature = "sync"))]
+fn test_flush_to_chunked() {
+  /// A writer which records the size of each write and the number of flushes.
+  #[derive(Default)]
+  struct Recorder {
+    data: std::vec::Vec<u8>,
+    writes: std::vec::Vec<usize>,
+    flushes: usize,
+  }
+
+  impl std::io::Write for Recorder {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+      self.data.extend_from_slice(buf);
+      self.writes.push(buf.len());
+      Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+      self.flushes += 1;
+      Ok(())
+    }
+  }
+
+  let log = Builder::new()
+    .with_capacity(1024)
+    .alloc::<crate::sync::ValueLog>(0)
+    .unwrap();
+  for value in [&b"foo"[..], b"bar", b"baz"] {
+    log.insert(value).unwrap();
+  }
+
+  let mut full = std::vec::Vec::new();
+  log.flush_to(&mut full).unwrap();
+
+  let mut w = Recorder::default();
+  log.flush_to_chunked(&mut w, 16).unwrap();
+  assert_eq!(w.data, full);
+  assert!(w.writes.iter().all(|n| *n <= 16));
+  assert_eq!(w.flushes, full.len().div_ceil(16));
+
+  let err = log.flush_to_chunked(&mut w, 0).unwrap_err();
+  assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+}
+
+#[test]
+#[cfg(feature = "sync")]
+fn test_buffered_writer() {
+  let log = Builder::new()
+    .with_capacity(1024)
+    .with_write_buffer(64)
+    .alloc::<crate::sync::ValueLog>(0)
+    .unwrap();
+  let start = log.allocator().allocated();
+
+  let mut writer = log.buffered();
+  let mut ptrs = std::vec::Vec::new();
+  for i in 0..10u8 {
+    ptrs.push(writer.insert(&[i; 5]).unwrap());
+  }
+  // bigger than the write buffer, inserted directly
+  let big = writer.insert(&[42; 100]).unwrap();
+  writer.flush().unwrap();
+  drop(writer);
+
+  for (i, vp) in ptrs.iter().enumerate() {
+    let data = unsafe { log.read(vp.id(), vp.offset(), vp.size()).unwrap() };
+    assert_eq!(data, [i as u8; 5]);
+  }
+  let data = unsafe { log.read(big.id(), big.offset(), big.size()).unwrap() };
+  assert_eq!(data, [42; 100]);
+
+  // the unused tails of the reservations are given back, so the entries are back-to-back
+  assert_eq!(log.allocator().allocated(), start + 10 * 13 + 108);
+  assert_eq!(log.discarded(), 0);
+
+  // a nearly full log cannot reserve a whole write buffer, the values are inserted directly
+  let log = Builder::new()
+    .with_capacity(1024)
+    .with_write_buffer(64)
+    .alloc::<crate::sync::ValueLog>(0)
+    .unwrap();
+  let free = log.allocator().remaining();
+  log.insert(&vec![0; free - 8 - 40]).unwrap();
+  let mut writer = log.buffered();
+  let vp = writer.insert(&[7; 5]).unwrap();
+  assert_eq!(writer.remaining(), 0);
+  let data = unsafe { log.read(vp.id(), vp.offset(), vp.size()).unwrap() };
+  assert_eq!(data, [7; 5]);
+  drop(writer);
+  assert_eq!(log.allocator().remaining(), 40 - 13);
+}
+
+#[test]
+#[cfg(feature = "sync")]
+fn test_as_immutable() {
+  fn scrub_frozen<L: LogReader<Id = u32> + Frozen>(log: &L, ptrs: &[ValuePointer<u32>]) {
+    unsafe { log.scrub(ptrs).unwrap() };
+  }
+
+  let log = Builder::new()
+    .with_capacity(1024)
+    .alloc::<crate::sync::ValueLog>(0)
+    .unwrap();
+
+  let vp = log.insert(b"foo").unwrap();
+  {
+    let view: crate::sync::ImmutableValueLogRef<'_> = log.as_immutable();
+    assert_eq!(view.id(), log.id());
+    scrub_frozen(&view, &[vp]);
+    let data = unsafe { view.read(view.id(), vp.offset(), vp.size()).unwrap() };
+    assert_eq!(data, b"foo");
+  }
+
+  let vp = log.insert(b"bar").unwrap();
+  let view = log.as_immutable();
+  let data = unsafe { view.read(view.id(), vp.offset(), vp.size()).unwrap() };
+  assert_eq!(data, b"bar");
+}
+
+#[test]
+#[cfg(feature = "sync")]
+fn test_insert_if_fits() {
+  let log = Builder::new()
+    .with_capacity(57)
+    .alloc::<crate::sync::ValueLog>(0)
+    .unwrap();
+
+  let vp = log.insert_if_fits(b"Hello, valog!").unwrap();
+  let err = log
+    .insert_if_fits(b"Hello, valog!")
+    .unwrap_err()
+    .unwrap_left();
+  assert_eq!(err.requested(), 13 + 8);
+  assert_eq!(err.available() as usize, log.allocator().remaining());
+  assert!(matches!(Error::from(err), Error::InsufficientSpace { .. }));
+
+  let data = unsafe { log.read(log.id(), vp.offset(), vp.size()).unwrap() };
+  assert_eq!(data, b"Hello, valog!");
+
+  let err = Builder::new()
+    .with_capacity(100)
+    .with_maximum_value_size(4)
+    .alloc::<crate::sync::ValueLog>(0)
+    .unwrap()
+    .insert_if_fits(b"Hello, valog!")
+    .unwrap_err();
+  assert!(matches!(
+    err,
+    rarena_allocator::either::Either::Right(Error::ValueTooLarge { .. })
+  ));
+}
+
+#[test]
+#[cfg(feature = "sync")]
+#[cfg(feature = "std")]
+fn test_from_bytes() {
+  let log = Builder::new()
+    .with_capacity(100)
+    .with_unify(true)
+    .alloc::<crate::sync::ValueLog>(0)
+    .unwrap();
+  let vp = log.insert(b"Hello, valog!").unwrap();
+
+  let mut buf = Vec::new();
+  log.flush_to(&mut buf).unwrap();
+
+  let log = Builder::new()
+    .from_bytes::<crate::sync::ValueLog>(&buf, 1)
+    .unwrap();
+  assert_eq!(*log.id(), 1);
+  let data = unsafe { log.read(log.id(), vp.offset(), vp.size()).unwrap() };
+  assert_eq!(data, b"Hello, valog!");
+
+  // the header, the reserved bytes and the values are kept, the allocator metadata is not.
+  let reserved_log = Builder::new()
+    .with_capacity(100)
+    .with_unify(true)
+    .with_reserved(4)
+    .alloc::<crate::sync::ValueLog>(0)
+    .unwrap();
+  unsafe { reserved_log.reserved_slice_mut() }.copy_from_slice(b"vlog");
+  reserved_log.insert(b"Hello, valog!").unwrap();
+  let mut reserved_buf = Vec::new();
+  reserved_log.flush_to(&mut reserved_buf).unwrap();
+
+  let frozen = Builder::new()
+    .with_reserved(4)
+    .from_bytes::<crate::sync::ImmutableValueLog>(&reserved_buf, 0)
+    .unwrap();
+  let prefix = crate::options::HEADER_SIZE + 4;
+  let data_offset = frozen.data_offset();
+  assert_eq!(frozen.as_slice().len(), reserved_buf.len());
+  assert_eq!(frozen.as_slice()[..prefix], reserved_buf[..prefix]);
+  assert_eq!(
+    frozen.as_slice()[data_offset..],
+    reserved_buf[data_offset..]
+  );
+  assert_eq!(unsafe { frozen.reserved_slice() }, b"vlog");
+
+  let err = Builder::new()
+    .from_bytes::<crate::sync::ValueLog>(&buf[..4], 1)
+    .unwrap_err();
+  assert!(matches!(err, Error::InvalidLayout));
+
+  buf[0] = 0;
+  let err = Builder::new()
+    .from_bytes::<crate::sync::ValueLog>(&buf, 1)
+    .unwrap_err();
+  assert!(matches!(err, Error::BadMagicText));
+}
+
+#[test]
+#[cfg(feature = "sync")]
+#[cfg(feature = "std")]
+fn test_immutable_read_bounds() {
+  let log = Builder::new()
+    .with_capacity(100)
+    .with_unify(true)
+    .alloc::<crate::sync::ValueLog>(0)
+    .unwrap();
+  let vp = log.insert(b"Hello, valog!").unwrap();
+
+  let mut buf = Vec::new();
+  log.flush_to(&mut buf).unwrap();
+
+  let log = Builder::new()
+    .from_bytes::<crate::sync::ImmutableValueLog>(&buf, 0)
+    .unwrap();
+  let data = unsafe { log.read(log.id(), vp.offset(), vp.size()).unwrap() };
+  assert_eq!(data, b"Hello, valog!");
+
+  let err = unsafe { log.read(log.id(), vp.offset(), vp.size() + 1).unwrap_err() };
+  assert!(matches!(err, Error::OutOfBounds { .. }));
+  let err = unsafe { log.read(log.id(), 0, vp.size()).unwrap_err() };
+  assert!(matches!(err, Error::OutOfBounds { .. }));
+  let err = unsafe { log.read(&1, vp.offset(), vp.size()).unwrap_err() };
+  assert!(matches!(err, Error::IdMismatch));
+}
+
+#[cfg(all(feature = "std", feature = "sync"))]
+#[derive(Debug, Default)]
+struct Adler;
+
+#[cfg(all(feature = "std", feature = "sync"))]
+impl checksum::BuildChecksumer for Adler {
+  type Checksumer = checksum::Crc32;
+
+  fn build_checksumer(&self) -> Self::Checksumer {
+    checksum::Crc32::new()
+  }
+
+  fn checksum_one(&self, src: &[u8]) -> u64 {
+    src.iter().fold(1u64, |acc, b| (acc + *b as u64) % 65521)
+  }
+}
+
+#[cfg(all(feature = "std", feature = "sync"))]
+impl options::ChecksumAlgo for Adler {
+  fn algorithm() -> u8 {
+    128
+  }
+}
+
+#[test]
+#[cfg(all(feature = "std", feature = "sync"))]
+fn test_read_with_checksumer() {
+  let log = Builder::new()
+    .with_capacity(1024)
+    .alloc::<crate::sync::ValueLog>(0)
+    .unwrap();
+  let vp = log.insert(b"Hello, valog!").unwrap();
+
+  let err = unsafe {
+    log
+      .read_with_checksumer(log.id(), vp.offset(), vp.size(), &Adler)
+      .unwrap_err()
+  };
+  assert!(matches!(err, Error::ChecksumMismatch));
+  let data = unsafe {
+    log
+      .read_with_checksumer(log.id(), vp.offset(), vp.size(), &checksum::Crc32::new())
+      .unwrap()
+  };
+  assert_eq!(data, b"Hello, valog!");
+
+  // the entries written by another checksum algorithm
+  let log = Builder::new()
+    .with_checksumer(Adler)
+    .with_capacity(1024)
+    .alloc::<crate::sync::ValueLog<u32, Adler>>(0)
+    .unwrap();
+  let vp = log.insert(b"migrated").unwrap();
+  let err = unsafe {
+    log
+      .read_with_checksumer(log.id(), vp.offset(), vp.size(), &checksum::Crc32::new())
+      .unwrap_err()
+  };
+  assert!(matches!(err, Error::ChecksumMismatch));
+  let data = unsafe {
+    log
+      .read_with_checksumer(log.id(), vp.offset(), vp.size(), &Adler)
+      .unwrap()
+  };
+  assert_eq!(data, b"migrated");
+
+  let err = unsafe {
+    log
+      .read_with_checksumer(log.id(), vp.offset(), vp.size() + 1024, &Adler)
+      .unwrap_err()
+  };
+  assert!(matches!(err, Error::OutOfBounds { .. }));
+}
+
+#[test]
+#[cfg(all(feature = "paranoid", feature = "sync"))]
+#[should_panic(expected = "paranoid")]
+fn test_paranoid_insert() {
+  use core::sync::atomic::{AtomicU64, Ordering};
+
+  /// A buggy checksumer which returns a different checksum for every call.
+  #[derive(Debug, Default)]
+  struct Flaky(AtomicU64);
+
+  impl checksum::BuildChecksumer for Flaky {
+    type Checksumer = checksum::Crc32;
+
+    fn build_checksumer(&self) -> Self::Checksumer {
+      checksum::Crc32::new()
+    }
+
+    fn checksum_one(&self, _src: &[u8]) -> u64 {
+      self.0.fetch_add(1, Ordering::Relaxed)
+    }
+  }
+
+  impl options::ChecksumAlgo for Flaky {
+    fn algorithm() -> u8 {
+      0
+    }
+  }
+
+  let log = Builder::new()
+    .with_checksumer(Flaky::default())
+    .with_capacity(1024)
+    .alloc::<crate::sync::ValueLog<u32, Flaky>>(0)
+    .unwrap();
+  let _ = log.insert(b"Hello, valog!");
+}
+
+#[test]
+#[cfg(all(feature = "std", feature = "sync"))]
+fn test_config_fingerprint() {
+  use crate::{options::ChecksumLen, sync::ValueLog};
+
+  let fingerprint = |builder: Builder| builder.alloc::<ValueLog>(0).unwrap().config_fingerprint();
+  let base = fingerprint(Builder::new().with_capacity(1024));
+  // the fingerprint of the default configuration must never change
+  assert_eq!(base, 6229383921017996826);
+
+  // the in-memory only options are not hashed
+  assert_eq!(
+    base,
+    fingerprint(Builder::new().with_capacity(2048).with_sync(false))
+  );
+
+  for other in [
+    fingerprint(Builder::new().with_capacity(1024).with_reserved(8)),
+    fingerprint(Builder::new().with_capacity(1024).with_magic_version(1)),
+    fingerprint(
+      Builder::new()
+        .with_capacity(1024)
+        .with_checksum_len(ChecksumLen::Four),
+    ),
+    fingerprint(Builder::new().with_capacity(1024).with_value_alignment(8)),
+    Builder::new()
+      .with_capacity(1024)
+      .alloc::<ValueLog<u64>>(0)
+      .unwrap()
+      .config_fingerprint(),
+    Builder::new()
+      .with_checksumer(Adler)
+      .with_capacity(1024)
+      .alloc::<ValueLog<u32, Adler>>(0)
+      .unwrap()
+      .config_fingerprint(),
+  ] {
+    assert_ne!(base, other);
+  }
+}
+
+#[test]
+#[cfg(feature = "sync")]
+#[cfg(feature = "std")]
+fn test_checksum_algorithm_mismatch() {
+  let log = Builder::new()
+    .with_capacity(100)
+    .with_unify(true)
+    .alloc::<crate::sync::ValueLog>(0)
+    .unwrap();
+  log.insert(b"Hello, valog!").unwrap();
+
+  let mut buf = Vec::new();
+  log.flush_to(&mut buf).unwrap();
+
+  let err = Builder::new()
+    .with_checksumer(Adler)
+    .from_bytes::<crate::sync::ValueLog<u32, Adler>>(&buf, 1)
+    .unwrap_err();
+  assert!(matches!(
+    err,
+    Error::ChecksumAlgorithmMismatch {
+      expected: 128,
+      found: 1
+    }
+  ));
+}
+
+#[test]
+#[cfg(feature = "sync")]
+#[cfg(feature = "std")]
+fn test_id_type_mismatch() {
+  let log = Builder::new()
+    .with_capacity(100)
+    .with_unify(true)
+    .alloc::<crate::sync::ValueLog>(0)
+    .unwrap();
+  log.insert(b"Hello, valog!").unwrap();
+
+  let mut buf = Vec::new();
+  log.flush_to(&mut buf).unwrap();
+
+  let err = Builder::new()
+    .from_bytes::<crate::sync::ValueLog<u64>>(&buf, 1)
+    .unwrap_err();
+  assert!(matches!(
+    err,
+    Error::IdTypeMismatch {
+      expected: 8,
+      found: 4
+    }
+  ));
+}
+
+#[test]
+#[cfg(all(feature = "std", feature = "sync"))]
+fn test_header_corrupt() {
+  let log = Builder::new()
+    .with_capacity(100)
+    .with_unify(true)
+    .alloc::<crate::sync::ValueLog>(0)
+    .unwrap();
+  log.insert(b"Hello, valog!").unwrap();
+
+  let mut buf = Vec::new();
+  log.flush_to(&mut buf).unwrap();
+  let start = buf
+    .windows(options::MAGIC_TEXT_SIZE)
+    .position(|w| w == options::MAGIC_TEXT)
+    .unwrap();
+
+  Builder::new()
+    .from_bytes::<crate::sync::ValueLog>(&buf, 1)
+    .unwrap();
+
+  for offset in [
+    options::CHECKSUM_LEN_OFFSET,
+    options::ID_WIDTH_OFFSET,
+    options::HEADER_CHECKSUM_OFFSET,
+  ] {
+    let mut corrupted = buf.clone();
+    corrupted[start + offset] ^= 0xFF;
+    let err = Builder::new()
+      .from_bytes::<crate::sync::ValueLog>(&corrupted, 1)
+      .unwrap_err();
+    assert!(matches!(err, Error::HeaderCorrupt));
+  }
+}
+
+#[test]
+#[cfg(feature = "sync")]
+fn test_insert_slice_matches_builder() {
+  for align in [1, 8] {
+    let a = Builder::new()
+      .with_capacity(1024)
+      .with_value_alignment(align)
+      .alloc::<crate::sync::ValueLog>(0)
+      .unwrap();
+    let b = Builder::new()
+      .with_capacity(1024)
+      .with_value_alignment(align)
+      .alloc::<crate::sync::ValueLog>(0)
+      .unwrap();
+
+    for value in [&b"a"[..], b"", b"Hello, valog!", &[7; 100]] {
+      let vp = a.insert(value).unwrap();
+      let vb = ValueBuilder::new(value.len(), |buf: &mut VacantBuffer<'_>| {
+        buf.put_slice_unchecked(value);
+        Ok::<_, ()>(())
+      });
+      assert_eq!(vp, b.insert_with(vb).unwrap());
+    }
+
+    assert_eq!(a.discarded(), b.discarded());
+    let allocated = a.allocator().allocated();
+    assert_eq!(allocated, b.allocator().allocated());
+    unsafe {
+      assert_eq!(
+        a.allocator().get_bytes(0, allocated),
+        b.allocator().get_bytes(0, allocated)
+      );
+    }
+  }
+}
+
+#[test]
+#[cfg(feature = "sync")]
+fn test_read_scalar() {
+  let log = Builder::new()
+    .with_capacity(1024)
+    .alloc::<crate::sync::ValueLog>(0)
+    .unwrap();
+
+  let ts = log.insert(&u64::MAX.to_le_bytes()).unwrap();
+  let counter = log.insert(&(-7i32).to_le_bytes()).unwrap();
+  unsafe {
+    assert_eq!(log.read_u64(log.id(), ts.offset()).unwrap(), u64::MAX);
+    assert_eq!(log.read_i32(log.id(), counter.offset()).unwrap(), -7);
+    assert!(matches!(
+      log.read_u16(log.id(), counter.offset()).unwrap_err(),
+      Error::ChecksumMismatch
+    ));
+    assert!(matches!(
+      log.read_u64(log.id(), 1020).unwrap_err(),
+      Error::OutOfBounds { .. }
+    ));
+  }
+}
+
+#[test]
+#[cfg(feature = "sync")]
+fn test_insert_with_flags() {
+  let log = Builder::new()
+    .with_capacity(1024)
+    .alloc::<crate::sync::ValueLog>(0)
+    .unwrap();
+
+  let vp = log.insert_with_flags(b"foo", 0xAB).unwrap();
+  assert_eq!(vp.size(), 4);
+  let (data, flags) = unsafe {
+    log
+      .read_with_flags(log.id(), vp.offset(), vp.size())
+      .unwrap()
+  };
+  assert_eq!(data, b"foo");
+  assert_eq!(flags, 0xAB);
+
+  let empty = log.insert_with_flags(b"", 1).unwrap();
+  let (data, flags) = unsafe {
+    log
+      .read_with_flags(log.id(), empty.offset(), empty.size())
+      .unwrap()
+  };
+  assert!(data.is_empty());
+  assert_eq!(flags, 1);
+
+  // the flags byte is covered by the checksum
+  unsafe {
+    log
+      .allocator()
+      .raw_mut_ptr()
+      .add((vp.offset() + 3) as usize)
+      .write(0xCD);
+  }
+  let err = unsafe {
+    log
+      .read_with_flags(log.id(), vp.offset(), vp.size())
+      .unwrap_err()
+  };
+  assert!(matches!(err, Error::ChecksumMismatch));
+}
+
+#[test]
+#[cfg(feature = "sync")]
+fn test_read_entry() {
+  for (align, cks_len) in [
+    (1, options::ChecksumLen::Eight),
+    (8, options::ChecksumLen::Four),
+  ] {
+    let log = Builder::new()
+      .with_capacity(1024)
+      .with_value_alignment(align)
+      .with_checksum_len(cks_len)
+      .alloc::<crate::sync::ValueLog>(0)
+      .unwrap();
+
+    let ptrs = [
+      log.insert(b"foo").unwrap(),
+      log.insert(b"").unwrap(),
+      log.insert(b"hello").unwrap(),
+    ];
+
+    let (data, entry_len) = unsafe { log.read_entry(log.id(), ptrs[0].offset(), 3).unwrap() };
+    assert_eq!(data, b"foo");
+    assert_eq!(entry_len as usize, 3 + cks_len.len());
+    let end = ptrs[0].offset() + entry_len;
+    if align == 1 {
+      assert_eq!(end, ptrs[2].offset());
+    } else {
+      assert!(end <= ptrs[2].offset());
+    }
+
+    let (data, entry_len) = unsafe { log.read_entry(log.id(), ptrs[1].offset(), 0).unwrap() };
+    assert!(data.is_empty());
+    assert_eq!(entry_len, 0);
+  }
+}
+
+#[test]
+#[cfg(feature = "sync")]
+fn test_validate_options() {
+  use options::Options;
+
+  assert!(Options::new().validate().is_ok());
+  assert!(Options::new().with_capacity(1024).validate().is_ok());
+  assert!(Options::new()
+    .with_capacity(1024)
+    .with_maximum_value_size(512)
+    .validate()
+    .is_ok());
+
+  let invalid = [
+    Options::new().with_capacity(8),
+    Options::new().with_capacity(1024).with_reserved(1024),
+    Options::new()
+      .with_capacity(1024)
+      .with_maximum_value_size(2048),
+  ];
+  for opts in invalid {
+    assert!(matches!(
+      opts.validate().unwrap_err(),
+      Error::InvalidOptions { .. }
+    ));
+  }
+
+  let err = Builder::new()
+    .with_capacity(1024)
+    .with_reserved(1024)
+    .alloc::<crate::sync::ValueLog>(0)
+    .unwrap_err();
+  assert!(matches!(err, Error::InvalidOptions { .. }));
+
+  #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+  {
+    assert!(matches!(
+      Options::new()
+        .with_create_new(true)
+        .with_truncate(true)
+        .validate()
+        .unwrap_err(),
+      Error::InvalidOptions { .. }
+    ));
+
+    let dir = tempfile::tempdir().unwrap();
+    let err = unsafe {
+      Builder::new()
+        .with_capacity(1024)
+        .with_create_new(true)
+        .with_truncate(true)
+        .with_read(true)
+        .with_write(true)
+        .map_mut::<crate::sync::ValueLog, _>(dir.path().join("test_validate_options"), 0)
+        .unwrap_err()
+    };
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+  }
+}
+
+#[test]
+#[cfg(feature = "sync")]
+fn test_try_with_capacity() {
+  let log = Builder::new()
+    .try_with_capacity(1024)
+    .unwrap()
+    .alloc::<crate::sync::ValueLog>(0)
+    .unwrap();
+  assert_eq!(log.allocator().capacity(), 1024);
+
+  let capacity = 1u64 << 33;
+  let err = Builder::new().try_with_capacity(capacity).unwrap_err();
+  assert!(matches!(err, Error::CapacityOverflow { capacity: c } if c == capacity));
+  assert!(err.to_string().contains(&capacity.to_string()));
+
+  assert!(Builder::new().try_with_capacity(u32::MAX as u64).is_ok());
+  assert!(Builder::new()
+    .try_with_capacity(u32::MAX as u64 + 1)
+    .is_err());
+}
+
+#[test]
+#[cfg(feature = "sync")]
+fn test_insert_generic_encode_length_mismatch() {
+  use dbutils::{buffer::VacantBuffer, error::InsufficientBuffer, types::Type};
+  use rarena_allocator::either::Either;
+
+  /// A buggy type which writes less bytes than its `encoded_len`.
+  #[derive(Debug)]
+  struct Short;
+
+  impl Type for Short {
+    type Ref<'a> = &'a [u8];
+    type Error = InsufficientBuffer;
+
+    fn encoded_len(&self) -> usize {
+      8
+    }
+
+    fn encode_to_buffer(&self, buf: &mut VacantBuffer<'_>) -> Result<usize, Self::Error> {
+      buf.put_slice(&[1, 2, 3, 4])?;
+      Ok(4)
+    }
+  }
+
+  let log = Builder::new()
+    .with_capacity(1024)
+    .alloc::<crate::sync::ValueLog>(0)
+    .unwrap();
+  let allocated = log.allocator().allocated();
+
+  let err = log.insert_generic(&Short).unwrap_err();
+  assert!(matches!(
+    err,
+    Either::Right(Error::EncodeLengthMismatch {
+      expected: 8,
+      actual: 4
+    })
+  ));
+  let err = log.insert_generic_tombstone(&Short).unwrap_err();
+  assert!(matches!(
+    err,
+    Either::Right(Error::EncodeLengthMismatch {
+      expected: 8,
+      actual: 4
+    })
+  ));
+  assert_eq!(log.allocator().allocated(), allocated);
+
+  let vp = log.insert_generic(&"valog".to_string()).unwrap();
+  let data = unsafe { log.read(log.id(), vp.offset(), vp.size()).unwrap() };
+  assert_eq!(data, b"valog");
+}
+
+#[test]
+#[cfg(feature = "sync")]
+fn test_insert_pair() {
+  let log = Builder::new()
+    .with_capacity(1024)
+    .alloc::<crate::sync::ValueLog>(0)
+    .unwrap();
+
+  let vp = log
+    .insert_pair(&"meta".to_string(), &"payload".to_string())
+    .unwrap();
+  assert_eq!(vp.size(), 4 + 4 + 7);
+  let (first, second) = unsafe {
+    log
+      .read_pair::<String, String>(log.id(), vp.offset(), vp.size())
+      .unwrap()
+  };
+  assert_eq!(first, "meta");
+  assert_eq!(second, "payload");
+
+  // the empty values are kept
+  let empty = log
+    .insert_pair(&String::new(), &"payload".to_string())
+    .unwrap();
+  let (first, second) = unsafe {
+    log
+      .read_pair::<String, String>(log.id(), empty.offset(), empty.size())
+      .unwrap()
+  };
+  assert_eq!(first, "");
+  assert_eq!(second, "payload");
+
+  // the checksum covers the second value as well
+  unsafe {
+    log
+      .allocator()
+      .raw_mut_ptr()
+      .add((vp.offset() + vp.size() - 1) as usize)
+      .write(0);
+  }
+  let err = unsafe {
+    log
+      .read_pair::<String, String>(log.id(), vp.offset(), vp.size())
+      .unwrap_err()
+  };
+  assert!(matches!(err, Error::ChecksumMismatch));
+
+  // the entry which is not a pair
+  for value in [&b"ab"[..], &[8, 0, 0, 0, 1]] {
+    let vp = log.insert(value).unwrap();
+    let err = unsafe {
+      log
+        .read_pair::<String, String>(log.id(), vp.offset(), vp.size())
+        .unwrap_err()
+    };
+    assert!(matches!(err, Error::InvalidLayout));
+  }
+}
+
+#[test]
+#[cfg(all(feature = "cache", feature = "sync"))]
+fn test_cached_log() {
+  use core::cell::Cell;
+
+  let log = Builder::new()
+    .with_capacity(1024)
+    .alloc::<crate::sync::ValueLog>(0)
+    .unwrap();
+  let log = CachedLog::new(log).with_capacity(2);
+  assert_eq!(log.capacity(), 2);
+  assert!(log.is_empty());
+
+  let vps = [b"foo", b"bar", b"baz"].map(|v| log.log().insert(v).unwrap());
+
+  let decoded = Cell::new(0);
+  let read = |vp: &ValuePointer<u32>| unsafe {
+    log
+      .read_with(vp.id(), vp.offset(), vp.size(), |v| {
+        decoded.set(decoded.get() + 1);
+        v.to_ascii_uppercase()
+      })
+      .unwrap()
+  };
+
+  assert_eq!(&*read(&vps[0]), b"FOO");
+  assert_eq!(&*read(&vps[1]), b"BAR");
+  assert_eq!(&*read(&vps[0]), b"FOO");
+  assert_eq!(decoded.get(), 2);
+  assert_eq!(log.len(), 2);
+
+  // `bar` is the least recently used one
+  assert_eq!(&*read(&vps[2]), b"BAZ");
+  assert_eq!(decoded.get(), 3);
+  assert_eq!(log.len(), 2);
+  assert_eq!(&*read(&vps[0]), b"FOO");
+  assert_eq!(decoded.get(), 3);
+  assert_eq!(&*read(&vps[1]), b"BAR");
+  assert_eq!(decoded.get(), 4);
+
+  // reads through `LogReader` are delegated to the inner log
+  let data = unsafe { log.read(log.id(), vps[1].offset(), vps[1].size()).unwrap() };
+  assert_eq!(data, b"bar");
+
+  assert!(matches!(
+    unsafe { log.read_with(&1, vps[0].offset(), vps[0].size(), |v| v.to_vec()) },
+    Err(Error::IdMismatch)
+  ));
+
+  log.invalidate(vps[1].offset(), vps[1].size());
+  assert_eq!(log.len(), 1);
+  log.clear();
+  assert!(log.is_empty());
+}
+
+#[test]
+#[cfg(feature = "sync")]
+#[cfg(any(feature = "alloc", feature = "std"))]
+fn test_compact_into() {
+  let src = Builder::new()
+    .with_capacity(1024)
+    .alloc::<crate::sync::ValueLog>(0)
+    .unwrap();
+  let ptrs = [
+    src.insert_tombstone(b"a").unwrap(),
+    src.insert(b"foo").unwrap(),
+    src.insert_tombstone(b"b").unwrap(),
+    src.insert(b"bar").unwrap(),
+  ];
+
+  let dst = Builder::new()
+    .with_capacity(1024)
+    .alloc::<crate::sync::ValueLog>(1)
+    .unwrap();
+  let mappings = unsafe { src.compact_into(&dst, &ptrs).unwrap() };
+  assert_eq!(mappings.len(), 2);
+  assert_eq!(dst.discarded(), 0);
+
+  for ((old, new), expected) in mappings.iter().zip([&ptrs[1], &ptrs[3]]) {
+    assert_eq!(old, expected);
+    assert_eq!(*new.id(), 1);
+    let data = unsafe { dst.read(new.id(), new.offset(), new.size()).unwrap() };
+    let old_data = unsafe { src.read(old.id(), old.offset(), old.size()).unwrap() };
+    assert_eq!(data, old_data);
+  }
+}
+
+#[test]
+#[cfg(feature = "sync")]
+fn test_contents_eq() {
+  let a = Builder::new()
+    .with_capacity(1024)
+    .alloc::<crate::sync::ValueLog>(0)
+    .unwrap();
+  let b = Builder::new()
+    .with_capacity(1024)
+    .with_checksum_len(options::ChecksumLen::Four)
+    .alloc::<crate::sync::ValueLog>(1)
+    .unwrap();
+
+  // shift the offsets of `b`, only the payloads are compared
+  b.insert(b"padding").unwrap();
+  let a_ptrs = [
+    a.insert(b"foo").unwrap(),
+    a.insert_tombstone(b"bar").unwrap(),
+    a.insert(b"").unwrap(),
+  ];
+  let b_ptrs = [
+    b.insert(b"foo").unwrap(),
+    b.insert_tombstone(b"bar").unwrap(),
+    b.insert(b"").unwrap(),
+  ];
+
+  unsafe {
+    assert!(a.contents_eq(&a_ptrs, &b, &b_ptrs));
+    assert!(a.contents_eq(&[], &b, &[]));
+    assert!(!a.contents_eq(&a_ptrs, &b, &b_ptrs[..2]));
+
+    // the tombstone marks are compared
+    let live = b.insert(b"bar").unwrap();
+    assert!(!a.contents_eq(&a_ptrs[1..2], &b, &[live]));
+
+    // the payloads are compared
+    let other = b.insert(b"baz").unwrap();
+    assert!(!a.contents_eq(&a_ptrs[..1], &b, &[other]));
+
+    // unreadable values are never equal
+    let bad = ValuePointer::new(1u32, 1024, 3);
+    assert!(!a.contents_eq(&a_ptrs[..1], &b, &[bad]));
+  }
+}
+
+#[test]
+#[cfg(feature = "sync")]
+#[cfg_attr(miri, ignore)]
+#[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+fn test_reopen_with_different_id_type() {
+  let dir = tempfile::tempdir().unwrap();
+  let p = dir.path().join("test_reopen_with_different_id_type");
+
+  unsafe {
+    Builder::new()
+      .with_capacity(100)
+      .with_create_new(true)
+      .with_read(true)
+      .with_write(true)
+      .map_mut::<crate::sync::ValueLog<u64>, _>(&p, 0)
+      .unwrap();
+  }
+
+  let err = unsafe {
+    Builder::new()
+      .with_read(true)
+      .with_write(true)
+      .map_mut::<crate::sync::ValueLog<u32>, _>(&p, 0)
+      .unwrap_err()
+  };
+  assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+
+  let log = unsafe {
+    Builder::new()
+      .with_read(true)
+      .map::<crate::sync::ImmutableValueLog<u64>, _>(&p, 0)
+      .unwrap()
+  };
+  assert_eq!(*log.id(), 0);
+}
+
+#[test]
+#[cfg(feature = "sync")]
+#[cfg_attr(miri, ignore)]
+#[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+fn test_reopen_with_different_checksumer() {
+  let dir = tempfile::tempdir().unwrap();
+  let p = dir.path().join("test_reopen_with_different_checksumer");
+
+  unsafe {
+    Builder::new()
+      .with_capacity(100)
+      .with_create_new(true)
+      .with_read(true)
+      .with_write(true)
+      .map_mut::<crate::sync::ValueLog, _>(&p, 0)
+      .unwrap();
+  }
+
+  let err = unsafe {
+    Builder::new()
+      .with_checksumer(Adler)
+      .with_read(true)
+      .map::<crate::sync::ImmutableValueLog<u32, Adler>, _>(&p, 0)
+      .unwrap_err()
+  };
+  assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+}
+
+#[test]
+#[cfg(feature = "sync")]
+#[cfg_attr(miri, ignore)]
+#[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+fn test_reopen_and_concurrent_read() {
+  use crate::sync::{ImmutableValueLog, ValueLog};
+
+  let dir = tempfile::tempdir().unwrap();
+  let p = dir.path().join("test_reopen_and_concurrent_read");
+
+  let log = unsafe {
+    Builder::new()
+      .with_capacity(MB)
+      .with_create_new(true)
+      .with_read(true)
+      .with_write(true)
+      .map_mut::<ValueLog, _>(&p, 0)
+      .unwrap()
+  };
+
+  let ptrs = (0..1000u32)
+    .map(|i| log.insert(i.to_string().as_bytes()).unwrap())
+    .collect::<Vec<_>>();
+
+  drop(log);
+
+  let log = unsafe {
+    Builder::new()
+      .with_read(true)
+      .with_lock_meta(true)
+      .map::<ImmutableValueLog, _>(&p, 0)
+      .unwrap()
+  };
+  assert_eq!(*log.id(), 0);
+
+  let (tx, rx) = crossbeam_channel::bounded(1000);
+
+  ptrs.into_iter().for_each(|vp| {
+    let l = log.clone();
+    let tx = tx.clone();
+
+    std::thread::spawn(move || {
+      let bytes = unsafe { l.read(l.id(), vp.offset(), vp.size()).unwrap() };
+      let val: u32 = std::str::from_utf8(bytes).unwrap().parse().unwrap();
+      tx.send(val).unwrap();
+    });
+  });
+
+  let mut data = Vec::with_capacity(1000);
+  for _ in 0..1000 {
+    data.push(rx.recv().unwrap());
+  }
+
+  data.sort_unstable();
+  assert_eq!(data, (0..1000).collect::<Vec<_>>());
+}
+
+#[test]
+#[cfg(feature = "sync")]
+#[cfg_attr(miri, ignore)]
+#[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+fn test_anon_grow() {
+  for unify in [true, false] {
+    let mut log = Builder::new()
+      .with_capacity(64)
+      .with_unify(unify)
+      .with_reserved(4)
+      .with_value_alignment(4)
+      .with_anon_grow(true)
+      .map_anon::<crate::sync::ValueLog>(0)
+      .unwrap();
+    unsafe { log.reserved_slice_mut().copy_from_slice(b"test") };
+
+    let cap = log.allocator().capacity();
+    let ptrs = (0..1000u32)
+      .map(|i| log.insert_or_grow(i.to_string().as_bytes()).unwrap())
+      .collect::<Vec<_>>();
+    assert!(log.allocator().capacity() > cap);
+    assert_eq!(
+      log.options().capacity() as usize,
+      log.allocator().capacity()
+    );
+    assert_eq!(unsafe { log.reserved_slice() }, b"test");
+    assert_eq!(log.discarded(), 1000 * 3);
+
+    for (i, vp) in ptrs.iter().enumerate() {
+      let data = unsafe { log.read(log.id(), vp.offset(), vp.size()).unwrap() };
+      assert_eq!(data, i.to_string().as_bytes());
+    }
+  }
+
+  let mut log = Builder::new()
+    .with_capacity(64)
+    .map_anon::<crate::sync::ValueLog>(0)
+    .unwrap();
+  let err = (0..1000u32)
+    .try_for_each(|i| log.insert_or_grow(&i.to_le_bytes()).map(|_| ()))
+    .unwrap_err();
+  assert!(matches!(err, Error::InsufficientSpace { .. }));
+
+  // the memory map shared with a live clone is not replaced
+  let mut log = Builder::new()
+    .with_capacity(64)
+    .with_anon_grow(true)
+    .map_anon::<crate::sync::ValueLog>(0)
+    .unwrap();
+  let clone = log.clone();
+  let cap = log.allocator().capacity();
+  let err = (0..1000u32)
+    .try_for_each(|i| log.insert_or_grow(&i.to_le_bytes()).map(|_| ()))
+    .unwrap_err();
+  assert!(matches!(err, Error::InsufficientSpace { .. }));
+  assert_eq!(log.allocator().capacity(), cap);
+
+  drop(clone);
+  log.insert_or_grow(&[0; 64]).unwrap();
+  assert!(log.allocator().capacity() > cap);
+}
+
+#[test]
+#[cfg(feature = "sync")]
+#[cfg_attr(miri, ignore)]
+#[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+fn test_capacity_opt() {
+  let log = Builder::new()
+    .with_capacity_opt(None)
+    .map_anon::<crate::sync::ValueLog>(0)
+    .unwrap();
+  assert_eq!(log.options().capacity() as usize, log.page_size());
+  log.insert(b"Hello, valog!").unwrap();
+
+  let dir = tempfile::tempdir().unwrap();
+  let p = dir.path().join("test_capacity_opt");
+  let cap = unsafe {
+    Builder::new()
+      .with_capacity(1000)
+      .with_create_new(true)
+      .with_read(true)
+      .with_write(true)
+      .map_mut::<crate::sync::ValueLog, _>(&p, 0)
+      .unwrap()
+      .allocator()
+      .capacity()
+  };
+
+  let log = unsafe {
+    Builder::new()
+      .with_capacity_opt(None)
+      .with_read(true)
+      .with_write(true)
+      .map_mut::<crate::sync::ValueLog, _>(&p, 0)
+      .unwrap()
+  };
+  assert_eq!(log.allocator().capacity(), cap);
+}
+
+#[test]
+#[cfg(feature = "sync")]
+#[cfg_attr(miri, ignore)]
+#[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+fn test_reuse_builder() {
+  let dir = tempfile::tempdir().unwrap();
+  let builder = Builder::new()
+    .with_capacity(1024)
+    .with_create_new(true)
+    .with_read(true)
+    .with_write(true);
+
+  for fid in 0..8u32 {
+    let log = unsafe {
+      builder
+        .clone()
+        .map_mut::<crate::sync::ValueLog, _>(dir.path().join(std::format!("{fid}.vlog")), fid)
+        .unwrap()
+    };
+    assert_eq!(*log.id(), fid);
+    log.insert(b"Hello, valog!").unwrap();
+  }
+}
+
+#[test]
+#[cfg(feature = "sync")]
+#[cfg_attr(miri, ignore)]
+#[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+fn test_open_pread() {
+  use std::io::{Seek, SeekFrom, Write};
+
+  let dir = tempfile::tempdir().unwrap();
+  let p = dir.path().join("test_open_pread");
+
+  let ptrs = unsafe {
+    let log = Builder::new()
+      .with_capacity(MB)
+      .with_reserved(4)
+      .with_checksum_len(options::ChecksumLen::Four)
+      .with_create_new(true)
+      .with_read(true)
+      .with_write(true)
+      .map_mut::<crate::sync::ValueLog, _>(&p, 0)
+      .unwrap();
+    (0..100u32)
+      .map(|i| log.insert(i.to_string().as_bytes()).unwrap())
+      .collect::<Vec<_>>()
+  };
+
+  let log: PreadValueLog<u32> = Builder::new().with_reserved(4).open_pread(&p, 0).unwrap();
+  assert_eq!(log.options().checksum_len(), options::ChecksumLen::Four);
+  for (i, vp) in ptrs.iter().enumerate() {
+    let data = log.read(log.id(), vp.offset(), vp.size()).unwrap();
+    assert_eq!(data, i.to_string().as_bytes());
+  }
+
+  assert!(matches!(
+    log.read(&1, ptrs[0].offset(), ptrs[0].size()).unwrap_err(),
+    Error::IdMismatch
+  ));
+  assert!(matches!(
+    log.read(log.id(), 0, 1).unwrap_err(),
+    Error::OutOfBounds { .. }
+  ));
+  assert!(matches!(
+    log.read(log.id(), log.len() as u32, 1).unwrap_err(),
+    Error::OutOfBounds { .. }
+  ));
+
+  let mut file = std::fs::OpenOptions::new().write(true).open(&p).unwrap();
+  file.seek(SeekFrom::Start(ptrs[0].offset() as u64)).unwrap();
+  file.write_all(b"x").unwrap();
+  drop(file);
+  assert!(matches!(
+    log
+      .read(log.id(), ptrs[0].offset(), ptrs[0].size())
+      .unwrap_err(),
+    Error::ChecksumMismatch
+  ));
+
+  let err = Builder::new()
+    .with_reserved(4)
+    .open_pread::<u64, _>(&p, 0)
+    .unwrap_err();
+  assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+}
+
+#[test]
+#[cfg(feature = "sync")]
+#[cfg_attr(miri, ignore)]
+#[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+fn test_open_pread_layout() {
+  let dir = tempfile::tempdir().unwrap();
+  let p = dir.path().join("test_open_pread_layout");
+
+  let (ptrs, data_offset, allocated) = unsafe {
+    let log = Builder::new()
+      .with_capacity(MB)
+      .with_offset(4096)
+      .with_reserved(5)
+      .with_create_new(true)
+      .with_read(true)
+      .with_write(true)
+      .map_mut::<crate::sync::ValueLog, _>(&p, 0)
+      .unwrap();
+    let ptrs = (0..10u32)
+      .map(|i| log.insert(i.to_string().as_bytes()).unwrap())
+      .collect::<Vec<_>>();
+    (ptrs, log.data_offset(), log.allocator().allocated())
+  };
+
+  let log: PreadValueLog<u32> = Builder::new()
+    .with_offset(4096)
+    .with_reserved(5)
+    .open_pread(&p, 0)
+    .unwrap();
+  assert_eq!(log.data_offset(), data_offset);
+  assert_eq!(log.committed(), allocated);
+  for (i, vp) in ptrs.iter().enumerate() {
+    let data = log.read(log.id(), vp.offset(), vp.size()).unwrap();
+    assert_eq!(data, i.to_string().as_bytes());
+  }
+
+  // the bytes after the allocated values are in the file, but not in the log.
+  assert!(allocated + 8 < log.len() as usize);
+  assert!(matches!(
+    log.read(log.id(), allocated as u32, 4).unwrap_err(),
+    Error::OutOfBounds { .. }
+  ));
+
+  let err = Builder::new()
+    .with_offset(4096)
+    .open_pread::<u32, _>(&p, 0)
+    .unwrap_err();
+  assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+  // the values are visible after they are committed.
+  let log = unsafe {
+    Builder::new()
+      .with_offset(4096)
+      .with_reserved(5)
+      .with_sync(false)
+      .with_read(true)
+      .with_write(true)
+      .map_mut::<crate::sync::ValueLog, _>(&p, 0)
+      .unwrap()
+  };
+  let vp = log.insert(b"uncommitted").unwrap();
+  let pread = |p: &std::path::Path| -> PreadValueLog<u32> {
+    Builder::new()
+      .with_offset(4096)
+      .with_reserved(5)
+      .open_pread(p, 0)
+      .unwrap()
+  };
+  assert_eq!(pread(&p).committed(), allocated);
+  assert!(matches!(
+    pread(&p).read(&0, vp.offset(), vp.size()).unwrap_err(),
+    Error::OutOfBounds { .. }
+  ));
+
+  log.flush().unwrap();
+  assert_eq!(pread(&p).committed(), log.allocator().allocated());
+  assert_eq!(
+    pread(&p).read(&0, vp.offset(), vp.size()).unwrap(),
+    b"uncommitted"
+  );
+}
+
+#[test]
+#[cfg(feature = "sync")]
+#[cfg_attr(miri, ignore)]
+#[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+fn test_pread_read_ahead() {
+  let dir = tempfile::tempdir().unwrap();
+  let p = dir.path().join("test_pread_read_ahead");
+
+  let (ptrs, big) = unsafe {
+    let log = Builder::new()
+      .with_capacity(MB)
+      .with_create_new(true)
+      .with_read(true)
+      .with_write(true)
+      .map_mut::<crate::sync::ValueLog, _>(&p, 0)
+      .unwrap();
+    let ptrs = (0..100u32)
+      .map(|i| log.insert(i.to_string().as_bytes()).unwrap())
+      .collect::<Vec<_>>();
+    (ptrs, log.insert(&[7; 1000]).unwrap())
+  };
+
+  let log: PreadValueLog<u32> = Builder::new()
+    .with_read_ahead(64)
+    .open_pread(&p, 0)
+    .unwrap();
+  assert_eq!(log.options().read_ahead(), 64);
+
+  // forward, backward and the value larger than the window
+  for (i, vp) in ptrs.iter().enumerate().chain(ptrs.iter().enumerate().rev()) {
+    let data = log.read(log.id(), vp.offset(), vp.size()).unwrap();
+    assert_eq!(data, i.to_string().as_bytes());
+  }
+  let data = log.read(log.id(), big.offset(), big.size()).unwrap();
+  assert_eq!(data, [7; 1000]);
+}
+
+#[test]
+#[cfg(feature = "sync")]
+#[cfg_attr(miri, ignore)]
+#[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+fn test_open_range() {
+  let dir = tempfile::tempdir().unwrap();
+  let p = dir.path().join("test_open_range");
+
+  let (ptrs, data_offset) = unsafe {
+    let log = Builder::new()
+      .with_capacity(MB)
+      .with_create_new(true)
+      .with_read(true)
+      .with_write(true)
+      .map_mut::<crate::sync::ValueLog, _>(&p, 0)
+      .unwrap();
+    let ptrs = (0..200u8)
+      .map(|i| log.insert(&[i; 1000]).unwrap())
+      .collect::<Vec<_>>();
+    (ptrs, log.data_offset())
+  };
+
+  let start = ptrs[100].offset();
+  let end = ptrs[150].offset();
+  let log: RangeValueLog<u32> = unsafe { Builder::new().open_range(&p, start..end, 0).unwrap() };
+  assert_eq!(log.range(), start..end);
+  assert_eq!(log.data_offset(), data_offset);
+  for (i, vp) in ptrs.iter().enumerate() {
+    let res = log.read(log.id(), vp.offset(), vp.size());
+    if (100..150).contains(&i) {
+      assert_eq!(res.unwrap(), &[i as u8; 1000]);
+    } else {
+      assert!(matches!(res.unwrap_err(), Error::OutOfBounds { .. }));
+    }
+  }
+  assert!(matches!(
+    log.read(&1, start, 1000).unwrap_err(),
+    Error::IdMismatch
+  ));
+
+  // the end of the range is clamped to the length of the file
+  let log: RangeValueLog<u32> =
+    unsafe { Builder::new().open_range(&p, start..u32::MAX, 0).unwrap() };
+  assert_eq!(log.range(), start..MB);
+  let last = ptrs.last().unwrap();
+  assert_eq!(
+    log.read(log.id(), last.offset(), last.size()).unwrap(),
+    &[199; 1000]
+  );
+
+  // the mapped bytes after the committed length are out of bounds.
+  let committed = last.offset() + last.size() + 8;
+  assert_eq!(log.committed(), committed as usize);
+  assert!(log.range().contains(&(committed + 4)));
+  assert!(matches!(
+    log.read(log.id(), committed, 4).unwrap_err(),
+    Error::OutOfBounds { .. }
+  ));
+  assert!(matches!(
+    log
+      .read(log.id(), last.offset(), last.size() + 1)
+      .unwrap_err(),
+    Error::OutOfBounds { .. }
+  ));
+
+  let err = unsafe { Builder::new().open_range::<u32, _>(&p, MB..MB + 1, 0) }.unwrap_err();
+  assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+  let err = unsafe { Builder::new().open_range::<u64, _>(&p, start..end, 0) }.unwrap_err();
+  assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+}
+
+#[test]
+#[cfg(feature = "sync")]
+#[cfg_attr(miri, ignore)]
+#[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+fn test_reopen_and_append() {
+  use crate::sync::ValueLog;
+
+  let dir = tempfile::tempdir().unwrap();
+  let p = dir.path().join("test_reopen_and_append");
+
+  let log = unsafe {
+    Builder::new()
+      .with_capacity(MB)
+      .with_create_new(true)
+      .with_read(true)
+      .with_write(true)
+      .map_mut::<ValueLog, _>(&p, 0)
+      .unwrap()
+  };
+
+  let old = (0..100u32)
+    .map(|i| log.insert(i.to_string().as_bytes()).unwrap())
+    .collect::<Vec<_>>();
+  let allocated = log.allocator().allocated();
+  drop(log);
+
+  let log = unsafe {
+    Builder::new()
+      .with_read(true)
+      .with_write(true)
+      .map_mut::<ValueLog, _>(&p, 0)
+      .unwrap()
+  };
+  assert_eq!(log.allocator().allocated(), allocated);
+
+  let new = (100..200u32)
+    .map(|i| log.insert(i.to_string().as_bytes()).unwrap())
+    .collect::<Vec<_>>();
+  assert!(new.iter().all(|vp| vp.offset() as usize >= allocated));
+
+  for (i, vp) in old.iter().chain(new.iter()).enumerate() {
+    let data = unsafe { log.read(log.id(), vp.offset(), vp.size()).unwrap() };
+    assert_eq!(data, i.to_string().as_bytes());
+  }
+}
+
+#[test]
+#[cfg(feature = "sync")]
+#[cfg_attr(miri, ignore)]
+#[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+fn test_header_sync() {
+  use crate::sync::{ImmutableValueLog, ValueLog};
+  use options::HeaderSync;
+
+  assert_eq!(Builder::new().header_sync(), HeaderSync::Always);
+  assert!(HeaderSync::Always.should_flush(0, 1));
+  assert!(!HeaderSync::OnClose.should_flush(0, MB as usize));
+  assert!(!HeaderSync::Periodic(64).should_flush(0, 63));
+  assert!(HeaderSync::Periodic(64).should_flush(60, 8));
+  assert!(HeaderSync::Periodic(0).should_flush(60, 1));
+
+  let dir = tempfile::tempdir().unwrap();
+  for (i, header_sync) in [
+    HeaderSync::Always,
+    HeaderSync::Periodic(64),
+    HeaderSync::OnClose,
+  ]
+  .into_iter()
+  .enumerate()
+  {
+    let path = dir.path().join(std::format!("test_header_sync_{i}"));
+    let log = unsafe {
+      Builder::new()
+        .with_capacity(MB)
+        .with_header_sync(header_sync)
+        .with_create_new(true)
+        .with_read(true)
+        .with_write(true)
+        .map_mut::<ValueLog, _>(&path, 0)
+        .unwrap()
+    };
+    let ptrs = (0..100u32)
+      .map(|i| log.insert(i.to_string().as_bytes()).unwrap())
+      .collect::<Vec<_>>();
+    drop(log);
+
+    // without a crash, the header in the page cache is seen by the reopened log, flushed or not
+    let log = unsafe {
+      Builder::new()
+        .map::<ImmutableValueLog, _>(&path, 0)
+        .unwrap()
+    };
+    for (i, vp) in ptrs.iter().enumerate() {
+      let data = unsafe { log.read(log.id(), vp.offset(), vp.size()).unwrap() };
+      assert_eq!(data, i.to_string().as_bytes());
+    }
+  }
+}
+
+#[test]
+#[cfg(feature = "sync")]
+#[cfg_attr(miri, ignore)]
+#[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+fn test_sync_strategy() {
+  use crate::sync::{ImmutableValueLog, ValueLog};
+  use options::{HeaderSync, SyncStrategy};
 
+  assert_eq!(Builder::new().sync_strategy(), SyncStrategy::Msync);
+
+  // the logs on the memory always flush through the allocator
   let log = Builder::new()
-    .with_capacity(100)
-    .alloc::<crate::sync::GenericValueLog<String>>(0)
+    .with_capacity(1024)
+    .with_sync_strategy(SyncStrategy::Fdatasync)
+    .alloc::<ValueLog>(0)
     .unwrap();
+  assert!(log.sync_file().is_none());
+  log.insert(b"Hello, valog!").unwrap();
 
-  let vp = log.insert(&"Hello, valog!".to_string()).unwrap();
-  assert_eq!(*vp.id(), 0);
-  unsafe {
-    log
-      .allocator()
-      .raw_mut_ptr()
-      .add(vp.offset() as usize)
-      .write(0);
+  let dir = tempfile::tempdir().unwrap();
+  for (i, strategy) in [
+    SyncStrategy::Msync,
+    SyncStrategy::SyncFileRange,
+    SyncStrategy::Fdatasync,
+  ]
+  .into_iter()
+  .enumerate()
+  {
+    let path = dir.path().join(std::format!("test_sync_strategy_{i}"));
+    let log = unsafe {
+      Builder::new()
+        .with_capacity(MB)
+        .with_sync_strategy(strategy)
+        .with_header_sync(HeaderSync::Periodic(64))
+        .with_create_new(true)
+        .with_read(true)
+        .with_write(true)
+        .map_mut::<ValueLog, _>(&path, 0)
+        .unwrap()
+    };
+    assert_eq!(log.sync_file().is_some(), strategy != SyncStrategy::Msync);
+
+    let mut ptrs = (0..100u32)
+      .map(|i| log.insert(i.to_string().as_bytes()).unwrap())
+      .collect::<Vec<_>>();
+    ptrs.extend(log.insert_all(&[b"100", b"101"]).unwrap());
+    let mut writer = log.buffered();
+    ptrs.push(writer.insert(b"102").unwrap());
+    writer.flush().unwrap();
+    drop(writer);
+    drop(log);
+
+    let log = unsafe {
+      Builder::new()
+        .map::<ImmutableValueLog, _>(&path, 0)
+        .unwrap()
+    };
+    for (i, vp) in ptrs.iter().enumerate() {
+      let data = unsafe { log.read(log.id(), vp.offset(), vp.size()).unwrap() };
+      assert_eq!(data, i.to_string().as_bytes());
+    }
   }
-  let err = unsafe { log.read(log.id(), vp.offset(), vp.size()).unwrap_err() };
-  assert!(matches!(err, Error::ChecksumMismatch));
 }
 
 #[test]
-fn test_insert_big_value() {
+#[cfg(feature = "sync")]
+#[cfg_attr(miri, ignore)]
+#[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+fn test_is_readonly() {
+  use crate::sync::{ImmutableValueLog, ValueLog};
+
+  fn readonly<L: Log>(l: &L) -> bool {
+    l.is_readonly()
+  }
+
+  let dir = tempfile::tempdir().unwrap();
+  let path = dir.path().join("test_is_readonly");
+  let log = unsafe {
+    Builder::new()
+      .with_capacity(1024)
+      .with_create_new(true)
+      .with_read(true)
+      .with_write(true)
+      .map_mut::<ValueLog, _>(&path, 0)
+      .unwrap()
+  };
+  assert!(!readonly(&log));
+  assert!(readonly(&log.as_immutable()));
+  drop(log);
+
+  let log = unsafe {
+    Builder::new()
+      .map::<ImmutableValueLog, _>(&path, 0)
+      .unwrap()
+  };
+  assert!(readonly(&log));
+
   let log = Builder::new()
-    .with_capacity(100)
-    .with_maximum_value_size(3)
-    .alloc::<crate::sync::ValueLog>(0)
+    .with_capacity(1024)
+    .map_anon::<ValueLog>(0)
     .unwrap();
-
-  let err = log.insert(&[0; 10]).unwrap_err();
-  assert!(matches!(err, Error::ValueTooLarge { .. }));
+  assert!(!readonly(&log));
 }
 
 #[test]
-fn test_insert_insufficient() {
+#[cfg(feature = "sync")]
+#[cfg_attr(miri, ignore)]
+#[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+fn test_lock_all() {
+  use crate::sync::{ImmutableValueLog, ValueLog};
+
   let log = Builder::new()
-    .with_capacity(100)
-    .alloc::<crate::sync::ValueLog>(0)
+    .with_capacity(4096)
+    .with_lock_all(true)
+    .with_lock_meta(true)
+    .map_anon::<ValueLog>(0)
     .unwrap();
+  assert!(log.options().lock_all());
+  let vp = log.insert(b"Hello, valog!").unwrap();
+  let data = unsafe { log.read(log.id(), vp.offset(), vp.size()).unwrap() };
+  assert_eq!(data, b"Hello, valog!");
 
-  let err = log.insert(&[0; 100]).unwrap_err();
-  assert!(matches!(err, Error::InsufficientSpace { .. }));
+  let dir = tempfile::tempdir().unwrap();
+  let path = dir.path().join("test_lock_all");
+  let vp = unsafe {
+    let log = Builder::new()
+      .with_capacity(4096)
+      .with_lock_all(true)
+      .with_create_new(true)
+      .with_read(true)
+      .with_write(true)
+      .map_mut::<ValueLog, _>(&path, 0)
+      .unwrap();
+    log.insert(b"Hello, valog!").unwrap()
+  };
+
+  let log = unsafe {
+    Builder::new()
+      .with_lock_all(true)
+      .map::<ImmutableValueLog, _>(&path, 0)
+      .unwrap()
+  };
+  let data = unsafe { log.read(log.id(), vp.offset(), vp.size()).unwrap() };
+  assert_eq!(data, b"Hello, valog!");
 }
 
 #[test]
-fn test_insert_empty_value() {
-  let log = Builder::new()
-    .with_capacity(100)
-    .alloc::<crate::sync::ValueLog>(0)
-    .unwrap();
+#[cfg(feature = "sync")]
+#[cfg_attr(miri, ignore)]
+#[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+fn test_repair() {
+  use crate::sync::ValueLog;
 
-  let vp = log.insert(&[]).unwrap();
-  assert_eq!(*vp.id(), 0);
-  assert_eq!(vp.offset(), 0);
-  assert_eq!(vp.size(), 0);
+  let dir = tempfile::tempdir().unwrap();
+  let path = dir.path().join("test_repair");
+  let open = |sync: bool| unsafe {
+    Builder::new()
+      .with_capacity(MB)
+      .with_sync(sync)
+      .with_create(true)
+      .with_read(true)
+      .with_write(true)
+      .map_mut::<ValueLog, _>(&path, 0)
+      .unwrap()
+  };
+
+  let mut log = open(false);
+  assert_eq!(log.repair().unwrap() as usize, log.data_offset());
+
+  let foo = log.insert(b"foo").unwrap();
+  log.flush().unwrap();
+  let bar = log.insert(b"bar").unwrap();
+  drop(log);
+
+  // the value inserted after the last flush is reclaimed.
+  let mut log = open(false);
+  let committed = log.repair().unwrap();
+  assert_eq!(committed, foo.offset() + foo.size() + 8);
+  assert_eq!(log.allocator().allocated(), committed as usize);
+  let data = unsafe { log.read(log.id(), foo.offset(), foo.size()).unwrap() };
+  assert_eq!(data, b"foo");
+  assert!(matches!(
+    unsafe { log.read(log.id(), bar.offset(), bar.size()) }.unwrap_err(),
+    Error::OutOfBounds { .. }
+  ));
+  let baz = log.insert(b"baz").unwrap();
+  assert_eq!(baz.offset(), bar.offset());
+
+  // the reclaimed values cannot be dropped under the clones of the log.
+  let clone = log.clone();
+  assert!(matches!(
+    log.repair().unwrap_err(),
+    Error::InvalidOptions { .. }
+  ));
+  drop(clone);
+  drop(log);
+
+  // the synced values are committed by default.
+  let log = open(true);
+  let qux = log.insert(b"qux").unwrap();
+  drop(log);
+  let mut log = open(true);
+  assert_eq!(log.repair().unwrap(), qux.offset() + qux.size() + 8);
+  let data = unsafe { log.read(log.id(), qux.offset(), qux.size()).unwrap() };
+  assert_eq!(data, b"qux");
+
+  // the in-memory logs do not record the committed length.
+  let mut log = Builder::new()
+    .with_capacity(1024)
+    .alloc::<ValueLog>(0)
+    .unwrap();
+  log.insert(b"foo").unwrap();
+  assert_eq!(log.repair().unwrap() as usize, log.allocator().allocated());
 }
 
 #[test]
-fn test_basic() {
+#[cfg(feature = "sync")]
+#[cfg_attr(miri, ignore)]
+#[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+fn test_footer() {
+  use crate::{
+    options::OversizePolicy,
+    sync::{ImmutableValueLog, ValueLog},
+  };
+
+  // the footer offset is kept in the header, the reserved bytes are not touched
   let log = Builder::new()
-    .with_capacity(100)
-    .alloc::<crate::sync::ValueLog>(0)
+    .with_capacity(1024)
+    .with_reserved(8)
+    .with_maximum_value_size(32)
+    .with_oversize_policy(OversizePolicy::Truncate)
+    .alloc::<ValueLog>(0)
     .unwrap();
-  assert_eq!(*log.id(), 0);
-  assert_eq!(log.options().capacity(), 100);
+  unsafe { log.reserved_slice_mut().copy_from_slice(&[u8::MAX; 8]) };
+  assert!(log.footer().unwrap().is_none());
+  let ptrs = [log.insert(b"foo").unwrap(), log.insert(&[1; 32]).unwrap()];
+  assert!(ptrs[1].is_truncated());
+  log.write_footer(&ptrs).unwrap();
+  let footer = log.footer().unwrap().unwrap();
+  assert_eq!(footer, ptrs);
+  assert!(!footer[0].is_truncated() && footer[1].is_truncated());
+  assert_eq!(unsafe { log.reserved_slice() }, [u8::MAX; 8]);
 
-  let empty = unsafe { log.read(log.id(), 0, 0).unwrap() };
-  assert_eq!(empty, &[]);
+  // the footer is neither limited by the maximum value size nor by the maximum entries
+  let log = Builder::new()
+    .with_capacity(1024)
+    .with_maximum_value_size(16)
+    .with_max_entries(2)
+    .alloc::<ValueLog>(0)
+    .unwrap();
+  let ptrs = [log.insert(b"foo").unwrap(), log.insert(b"bar").unwrap()];
+  let err = log.insert(b"baz").unwrap_err();
+  assert!(matches!(err, Error::MaxEntriesReached { maximum: 2 }));
+  log.write_footer(&ptrs).unwrap();
+  assert_eq!(log.entries(), 2);
+  assert_eq!(log.footer().unwrap().unwrap(), ptrs);
 
   let log = Builder::new()
-    .with_capacity(100)
-    .with_lock_meta(true)
-    .alloc::<crate::sync::GenericValueLog<String>>(0)
+    .with_capacity(1024)
+    .alloc::<ValueLog>(0)
     .unwrap();
-  assert_eq!(*log.id(), 0);
-  assert_eq!(log.options().capacity(), 100);
+  let start = log.allocator().allocated() as u32;
+  let sub = Builder::new()
+    .with_external_arena::<ValueLog>(log.allocator().clone(), start..start + 64, 1)
+    .unwrap();
+  let vp = sub.insert(b"foo").unwrap();
+  let err = sub.write_footer(&[vp]).unwrap_err();
+  assert!(matches!(err, Error::InvalidOptions { .. }));
+  assert!(log.footer().unwrap().is_none());
+
+  let dir = tempfile::tempdir().unwrap();
+  let path = dir.path().join("test_footer");
+  let log = unsafe {
+    Builder::new()
+      .with_capacity(MB)
+      .with_reserved(8)
+      .with_create_new(true)
+      .with_read(true)
+      .with_write(true)
+      .map_mut::<ValueLog, _>(&path, 0)
+      .unwrap()
+  };
+  assert!(log.footer().unwrap().is_none());
+
+  let ptrs = (0..100u32)
+    .map(|i| match i % 3 {
+      0 => log.insert_tombstone(i.to_string().as_bytes()).unwrap(),
+      _ => log.insert(i.to_string().as_bytes()).unwrap(),
+    })
+    .collect::<Vec<_>>();
+  let offset = log.write_footer(&ptrs).unwrap();
+  assert!(offset > ptrs.last().unwrap().offset() as u64);
+  drop(log);
+
+  let log = unsafe {
+    Builder::new()
+      .with_reserved(8)
+      .map::<ImmutableValueLog, _>(&path, 0)
+      .unwrap()
+  };
+  let footer = log.footer().unwrap().unwrap();
+  assert_eq!(footer, ptrs);
+  for (i, vp) in footer.iter().enumerate() {
+    let data = unsafe { log.read(log.id(), vp.offset(), vp.size()).unwrap() };
+    assert_eq!(data, i.to_string().as_bytes());
+  }
 }
 
 #[test]
+#[cfg(feature = "sync")]
 #[cfg_attr(miri, ignore)]
 #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
-fn test_reopen_and_concurrent_read() {
-  use crate::sync::{ImmutableValueLog, ValueLog};
+fn test_map_from_file() {
+  use crate::sync::ValueLog;
 
   let dir = tempfile::tempdir().unwrap();
-  let p = dir.path().join("test_reopen_and_concurrent_read");
-
+  let p = dir.path().join("test_map_from_file");
   let log = unsafe {
     Builder::new()
       .with_capacity(MB)
+      .with_reserved(8)
       .with_create_new(true)
       .with_read(true)
       .with_write(true)
       .map_mut::<ValueLog, _>(&p, 0)
       .unwrap()
   };
-
-  let ptrs = (0..1000u32)
+  let ptrs = (0..100u32)
     .map(|i| log.insert(i.to_string().as_bytes()).unwrap())
     .collect::<Vec<_>>();
+  drop(log);
+
+  // the handle is mapped directly, the header is checked on the mapped bytes
+  let log: RangeValueLog<u32> = unsafe {
+    Builder::new()
+      .with_reserved(8)
+      .map_from_file(std::fs::File::open(&p).unwrap(), 0)
+      .unwrap()
+  };
+  assert_eq!(log.range().start, 0);
+  for (i, vp) in ptrs.iter().enumerate() {
+    let data = log.read(&0, vp.offset(), vp.size()).unwrap();
+    assert_eq!(data, i.to_string().as_bytes());
+  }
+
+  // the mapping stays valid after the file is removed
+  let handle = std::fs::File::open(&p).unwrap();
+  std::fs::remove_file(&p).unwrap();
+  let log: RangeValueLog<u32> = unsafe {
+    Builder::new()
+      .with_reserved(8)
+      .map_from_file(handle, 0)
+      .unwrap()
+  };
+  let data = log.read(&0, ptrs[0].offset(), ptrs[0].size()).unwrap();
+  assert_eq!(data, b"0");
+
+  let err = unsafe {
+    Builder::new()
+      .map_from_file::<u32>(tempfile::tempfile().unwrap(), 0)
+      .unwrap_err()
+  };
+  assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+}
+
+#[test]
+#[cfg(all(feature = "sync", feature = "encryption"))]
+#[cfg_attr(miri, ignore)]
+fn test_encryption() {
+  use options::Cipher;
+
+  const KEY: Cipher = Cipher::Aes256Gcm { key: [7; 32] };
+  const OVERHEAD: u32 = 12 + 16;
+
+  let log = Builder::new()
+    .with_capacity(4096)
+    .with_cipher(KEY)
+    .with_write_buffer(256)
+    .alloc::<crate::sync::ValueLog>(0)
+    .unwrap();
+
+  let a = log.insert(b"Hello, valog!").unwrap();
+  let b = log.insert(b"Hello, valog!").unwrap();
+  assert_eq!(a.size(), 13 + OVERHEAD);
+
+  // the values are stored as the nonce, the ciphertext and the tag, the nonces are unique
+  let sealed_a = unsafe { log.read(log.id(), a.offset(), a.size()).unwrap() };
+  let sealed_b = unsafe { log.read(log.id(), b.offset(), b.size()).unwrap() };
+  assert!(!sealed_a.windows(13).any(|w| w == b"Hello, valog!"));
+  assert_ne!(sealed_a[..12], sealed_b[..12]);
+  assert_ne!(sealed_a[12..], sealed_b[12..]);
+
+  // the owned reads decrypt the values
+  for vp in [a, b] {
+    let data = unsafe { log.read_owned(log.id(), vp.offset(), vp.size()).unwrap() };
+    assert_eq!(data, b"Hello, valog!");
+    let data = unsafe { log.read_shared(log.id(), vp.offset(), vp.size()).unwrap() };
+    assert_eq!(&*data, b"Hello, valog!");
+    let mut buf = b">".to_vec();
+    unsafe {
+      log
+        .read_to_owned(log.id(), vp.offset(), vp.size(), &mut buf)
+        .unwrap()
+    };
+    assert_eq!(buf, b">Hello, valog!");
+  }
+
+  let ptrs = log.insert_all(&[b"foo", b"", b"bar"]).unwrap();
+  let at = log.insert_at(2048, b"slot").unwrap();
+  let with = log
+    .insert_with(ValueBuilder::new(4, |buf: &mut VacantBuffer<'_>| {
+      buf.put_slice(b"with")
+    }))
+    .unwrap();
+  let mut writer = log.buffered();
+  let buffered = writer.insert(b"buffered").unwrap();
+  writer.flush().unwrap();
+  drop(writer);
+  for (vp, expected) in [
+    (ptrs[0], &b"foo"[..]),
+    (ptrs[1], b""),
+    (ptrs[2], b"bar"),
+    (at, b"slot"),
+    (with, b"with"),
+    (buffered, b"buffered"),
+  ] {
+    if !expected.is_empty() {
+      assert_eq!(vp.size(), expected.len() as u32 + OVERHEAD);
+    }
+    let data = unsafe { log.read_owned(log.id(), vp.offset(), vp.size()).unwrap() };
+    assert_eq!(data, expected);
+  }
+
+  let err = log
+    .insert_with_checksum(b"foo", log.checksum(b"foo"))
+    .unwrap_err();
+  assert!(matches!(err, Error::InvalidOptions { .. }));
+
+  // the checksum covers the sealed value, the tag authenticates it
+  unsafe {
+    *log.allocator().get_pointer_mut(a.offset() as usize + 12) ^= 1;
+  }
+  let err = unsafe { log.read_owned(log.id(), a.offset(), a.size()).unwrap_err() };
+  assert!(matches!(err, Error::ChecksumMismatch));
+
+  let unchecked = Builder::new()
+    .with_capacity(1024)
+    .with_cipher(KEY)
+    .with_validate_checksum(false)
+    .alloc::<crate::sync::ValueLog>(0)
+    .unwrap();
+  let vp = unchecked.insert(b"Hello, valog!").unwrap();
+  unsafe {
+    *unchecked
+      .allocator()
+      .get_pointer_mut(vp.offset() as usize + 12) ^= 1;
+  }
+  let err = unsafe {
+    unchecked
+      .read_owned(unchecked.id(), vp.offset(), vp.size())
+      .unwrap_err()
+  };
+  assert!(matches!(err, Error::AuthenticationFailed));
+
+  // the sealed values are copied as is between the logs with the same cipher, and decrypted otherwise
+  let same = Builder::new()
+    .with_capacity(1024)
+    .with_cipher(KEY)
+    .alloc::<crate::sync::ValueLog>(1)
+    .unwrap();
+  let plain = Builder::new()
+    .with_capacity(1024)
+    .alloc::<crate::sync::ValueLog>(2)
+    .unwrap();
+  let other = Builder::new()
+    .with_capacity(1024)
+    .with_cipher(Cipher::Aes256Gcm { key: [8; 32] })
+    .alloc::<crate::sync::ValueLog>(3)
+    .unwrap();
+  let src = [b, ptrs[1], ptrs[2]];
+  let copied = unsafe { same.merge_from(&log, &src).unwrap() };
+  assert_eq!(
+    unsafe {
+      same
+        .read(same.id(), copied[0].offset(), copied[0].size())
+        .unwrap()
+    },
+    sealed_b
+  );
+  let decrypted = unsafe { plain.merge_from(&log, &src).unwrap() };
+  assert_eq!(decrypted[0].size(), 13);
+  let resealed = unsafe { other.merge_from(&log, &src).unwrap() };
+  for (vps, l) in [(&copied, &same), (&decrypted, &plain), (&resealed, &other)] {
+    for (vp, expected) in vps.iter().zip([&b"Hello, valog!"[..], b"", b"bar"]) {
+      let data = unsafe { l.read_owned(l.id(), vp.offset(), vp.size()).unwrap() };
+      assert_eq!(data, expected);
+    }
+  }
+
+  let entry_len = b.size() + 8;
+  let vp = same.copy_raw_from(&log, b.offset(), entry_len).unwrap();
+  let data = unsafe { same.read_owned(same.id(), vp.offset(), vp.size()).unwrap() };
+  assert_eq!(data, b"Hello, valog!");
+  for l in [&plain, &other] {
+    let err = l.copy_raw_from(&log, b.offset(), entry_len).unwrap_err();
+    assert!(matches!(err, Error::IncompatibleLayout));
+  }
+
+  let arena = log.allocator().clone();
+  let err = Builder::new()
+    .with_cipher(KEY)
+    .with_external_arena::<crate::sync::ValueLog>(arena, 3072..4096, 4)
+    .unwrap_err();
+  assert!(matches!(err, Error::InvalidOptions { .. }));
+}
+
+#[test]
+#[cfg(all(feature = "sync", feature = "encryption"))]
+#[cfg_attr(miri, ignore)]
+#[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+fn test_encryption_reopen() {
+  use crate::sync::{ImmutableValueLog, ValueLog};
+  use options::Cipher;
 
+  const KEY: Cipher = Cipher::Aes256Gcm { key: [7; 32] };
+
+  let dir = tempfile::tempdir().unwrap();
+  let p = dir.path().join("test_encryption_reopen");
+  let open = |builder: Builder| unsafe {
+    builder
+      .with_read(true)
+      .with_write(true)
+      .map_mut::<ValueLog, _>(&p, 0)
+  };
+
+  let log = open(
+    Builder::new()
+      .with_capacity(MB)
+      .with_create_new(true)
+      .with_cipher(KEY),
+  )
+  .unwrap();
+  let first = log.insert(b"first").unwrap();
+  drop(log);
+
+  // the salt is renewed when the log is reopened, the old values are still decrypted
+  let log = open(Builder::new().with_cipher(KEY)).unwrap();
+  let second = log.insert(b"second").unwrap();
+  let sealed = |vp: &ValuePointer<u32>| unsafe {
+    log.read(log.id(), vp.offset(), vp.size()).unwrap()[..8].to_vec()
+  };
+  assert_ne!(sealed(&first), sealed(&second));
+  for (vp, expected) in [(first, &b"first"[..]), (second, b"second")] {
+    let data = unsafe { log.read_owned(log.id(), vp.offset(), vp.size()).unwrap() };
+    assert_eq!(data, expected);
+  }
   drop(log);
 
   let log = unsafe {
     Builder::new()
       .with_read(true)
-      .with_lock_meta(true)
+      .with_cipher(KEY)
       .map::<ImmutableValueLog, _>(&p, 0)
       .unwrap()
   };
-  assert_eq!(*log.id(), 0);
+  let data = unsafe {
+    log
+      .read_owned(log.id(), second.offset(), second.size())
+      .unwrap()
+  };
+  assert_eq!(data, b"second");
+  drop(log);
 
-  let (tx, rx) = crossbeam_channel::bounded(1000);
+  let log: PreadValueLog<u32> = Builder::new().with_cipher(KEY).open_pread(&p, 0).unwrap();
+  assert_eq!(
+    log.read(&0, first.offset(), first.size()).unwrap(),
+    b"first"
+  );
 
-  ptrs.into_iter().for_each(|vp| {
-    let l = log.clone();
-    let tx = tx.clone();
+  // reopening with a wrong key fails the authentication of the key check
+  let err = open(Builder::new().with_cipher(Cipher::Aes256Gcm { key: [8; 32] })).unwrap_err();
+  assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+  assert!(matches!(
+    *err.into_inner().unwrap().downcast::<Error>().unwrap(),
+    Error::AuthenticationFailed
+  ));
 
-    std::thread::spawn(move || {
-      let bytes = unsafe { l.read(l.id(), vp.offset(), vp.size()).unwrap() };
-      let val: u32 = std::str::from_utf8(bytes).unwrap().parse().unwrap();
-      tx.send(val).unwrap();
-    });
-  });
+  let err = open(Builder::new()).unwrap_err();
+  assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+  assert!(matches!(
+    *err.into_inner().unwrap().downcast::<Error>().unwrap(),
+    Error::OptionsMismatch { field: "cipher" }
+  ));
 
-  let mut data = Vec::with_capacity(1000);
-  for _ in 0..1000 {
-    data.push(rx.recv().unwrap());
+  // a plain log cannot be opened with a cipher
+  let plain = dir.path().join("test_encryption_reopen_plain");
+  unsafe {
+    Builder::new()
+      .with_capacity(MB)
+      .with_create_new(true)
+      .with_read(true)
+      .with_write(true)
+      .map_mut::<ValueLog, _>(&plain, 0)
+      .unwrap();
   }
-
-  data.sort_unstable();
-  assert_eq!(data, (0..1000).collect::<Vec<_>>());
+  let err = unsafe {
+    Builder::new()
+      .with_read(true)
+      .with_write(true)
+      .with_cipher(KEY)
+      .map_mut::<ValueLog, _>(&plain, 0)
+      .unwrap_err()
+  };
+  assert!(matches!(
+    *err.into_inner().unwrap().downcast::<Error>().unwrap(),
+    Error::OptionsMismatch { field: "cipher" }
+  ));
 }
 
 #[test]
+#[cfg(feature = "sync")]
 #[cfg_attr(miri, ignore)]
 #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
 fn test_generic_reopen_and_concurrent_read() {
@@ -228,6 +4237,7 @@ fn test_generic_reopen_and_concurrent_read() {
 }
 
 #[test]
+#[cfg(feature = "sync")]
 #[cfg_attr(miri, ignore)]
 #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
 fn test_reopen_and_read() {
@@ -275,6 +4285,7 @@ fn test_reopen_and_read() {
 }
 
 #[test]
+#[cfg(feature = "sync")]
 #[cfg_attr(miri, ignore)]
 #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
 fn test_generic_reopen_and_read() {
@@ -518,7 +4529,7 @@ where
   }
 }
 
-#[cfg(feature = "std")]
+#[cfg(all(feature = "std", feature = "sync"))]
 pub(crate) fn concurrent_basic<L>(l: L)
 where
   L: Clone + LogWriter + LogReader + Send + 'static,
@@ -606,7 +4617,7 @@ where
   assert_eq!(data.as_slice(), &(0..N).collect::<Vec<_>>());
 }
 
-#[cfg(feature = "std")]
+#[cfg(all(feature = "std", feature = "sync"))]
 pub(crate) fn generic_concurrent_basic<L>(l: L)
 where
   L: Clone + GenericLogWriter<Type = String> + GenericLogReader<Type = String> + Send + 'static,
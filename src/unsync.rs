@@ -10,6 +10,10 @@ pub type GenericValueLog<T, I = u32, C = Crc32> = super::GenericValueLog<T, I, A
 /// A value log that can only be used in single-threaded environments.
 pub type ImmutableValueLog<I = u32, C = Crc32> = super::ImmutableValueLog<I, Arena, C>;
 
+/// A borrowed read-only view of a [`ValueLog`], created by [`ValueLog::as_immutable`](super::ValueLog::as_immutable).
+pub type ImmutableValueLogRef<'a, I = u32, C = Crc32> =
+  super::ImmutableValueLogRef<'a, I, Arena, C>;
+
 /// A generic value log that can only be used in single-threaded environments.
 pub type ImmutableGenericValueLog<T, I = u32, C = Crc32> =
   super::ImmutableGenericValueLog<T, I, Arena, C>;
@@ -0,0 +1,158 @@
+use dbutils::checksum::{BuildChecksumer, Crc32};
+use rarena_allocator::unsync::Arena;
+
+use super::{
+  options::{ChecksumAlgo, ChecksumLen, Freelist, OversizePolicy},
+  sealed::Constructor,
+};
+
+pub use super::{
+  error::Error, options::Options, GenericLogReader, GenericLogWriter, Log, LogReader, LogReaderExt,
+  LogWriter, LogWriterExt, MutableLog, ValueBuilder, ValuePointer,
+};
+
+/// A value log which can be used on `wasm` targets.
+pub type ValueLog<I = u32, C = Crc32> = super::ValueLog<I, Arena, C>;
+
+/// A generic value log which can be used on `wasm` targets.
+pub type GenericValueLog<T, I = u32, C = Crc32> = super::GenericValueLog<T, I, Arena, C>;
+
+/// An immutable value log which can be used on `wasm` targets.
+pub type ImmutableValueLog<I = u32, C = Crc32> = super::ImmutableValueLog<I, Arena, C>;
+
+/// An immutable generic value log which can be used on `wasm` targets.
+pub type ImmutableGenericValueLog<T, I = u32, C = Crc32> =
+  super::ImmutableGenericValueLog<T, I, Arena, C>;
+
+/// The builder to build a value log on `wasm` targets.
+///
+/// This is a wrapper of [`Builder`](crate::Builder) which only exposes the in-memory constructors and
+/// the options they respect, the file and memory map backed constructors are not available.
+///
+/// ## Example
+///
+/// ```rust
+/// use valog::wasm::{Builder, Log, LogReader, LogWriter, ValueLog};
+///
+/// let log = Builder::new().with_capacity(1024).alloc::<ValueLog>(0).unwrap();
+/// let vp = log.insert(b"Hello, valog!").unwrap();
+/// let data = unsafe { log.read(log.id(), vp.offset(), vp.size()).unwrap() };
+/// assert_eq!(data, b"Hello, valog!");
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct Builder<S = Crc32>(super::Builder<S>);
+
+impl Default for Builder {
+  #[inline]
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl Builder {
+  /// Create a new `Builder` with default values.
+  #[inline]
+  pub fn new() -> Self {
+    Self(super::Builder::new())
+  }
+}
+
+macro_rules! forward_setters {
+  ($($name:ident($arg:ident: $ty:ty)),+ $(,)?) => {
+    $(
+      #[doc = concat!("Same as [`Builder::", stringify!($name), "`](crate::Builder::", stringify!($name), ").")]
+      #[inline]
+      pub fn $name(self, $arg: $ty) -> Self {
+        Self(self.0.$name($arg))
+      }
+    )+
+  };
+}
+
+impl<S> Builder<S> {
+  /// Returns a new builder with the new [`BuildChecksumer`](crate::checksum::BuildChecksumer),
+  /// same as [`Builder::with_checksumer`](crate::Builder::with_checksumer).
+  #[inline]
+  pub fn with_checksumer<NS>(self, cks: NS) -> Builder<NS> {
+    Builder(self.0.with_checksumer(cks))
+  }
+
+  /// Returns the [`Options`] of the builder.
+  #[inline]
+  pub const fn options(&self) -> &Options {
+    self.0.options()
+  }
+
+  forward_setters!(
+    with_options(opts: Options),
+    with_reserved(reserved: u32),
+    with_reserved_regions(regions: &[u32]),
+    with_validate_checksum(validate_checksum: bool),
+    with_magic_version(magic_version: u16),
+    with_freelist(freelist: Freelist),
+    with_unify(unify: bool),
+    with_maximum_value_size(size: u32),
+    with_oversize_policy(policy: OversizePolicy),
+    with_max_entries(max_entries: u32),
+    with_capacity(capacity: u32),
+    with_capacity_opt(capacity: Option<u32>),
+    with_checksum_len(checksum_len: ChecksumLen),
+    with_value_alignment(align: usize),
+    with_write_buffer(size: u32),
+  );
+
+  /// Creates a new in-memory value log, same as [`Builder::alloc`](crate::Builder::alloc).
+  ///
+  /// ## Example
+  ///
+  /// ```rust
+  /// use valog::wasm::{Builder, ValueLog};
+  ///
+  /// let log = Builder::new().with_capacity(1024).alloc::<ValueLog>(1).unwrap();
+  /// ```
+  #[inline]
+  pub fn alloc<C>(self, fid: C::Id) -> Result<C, Error>
+  where
+    C: Constructor<Checksumer = S> + super::Mutable,
+    S: BuildChecksumer + ChecksumAlgo,
+  {
+    self.0.alloc(fid)
+  }
+
+  /// Creates a new in-memory value log which is initialized from the given bytes,
+  /// same as [`Builder::from_bytes`](crate::Builder::from_bytes).
+  ///
+  /// ## Example
+  ///
+  /// ```rust
+  /// use valog::wasm::{Builder, ImmutableValueLog, Log, LogReader, LogWriter, ValueLog};
+  /// use valog::LogExt;
+  ///
+  /// let log = Builder::new()
+  ///   .with_capacity(1024)
+  ///   .with_unify(true)
+  ///   .alloc::<ValueLog>(1)
+  ///   .unwrap();
+  /// let vp = log.insert(b"Hello, valog!").unwrap();
+  ///
+  /// let mut buf = Vec::new();
+  /// log.flush_to(&mut buf).unwrap();
+  ///
+  /// let log = Builder::new().from_bytes::<ImmutableValueLog>(&buf, 1).unwrap();
+  /// let data = unsafe { log.read(log.id(), vp.offset(), vp.size()).unwrap() };
+  /// assert_eq!(data, b"Hello, valog!");
+  /// ```
+  #[inline]
+  pub fn from_bytes<C>(self, bytes: &[u8], fid: C::Id) -> Result<C, Error>
+  where
+    C: Constructor<Checksumer = S>,
+    S: BuildChecksumer + ChecksumAlgo,
+  {
+    self.0.from_bytes(bytes, fid)
+  }
+}
+
+#[cfg(test)]
+crate::__common_tests!(wasm(crate::wasm::ValueLog) {
+  basic,
+});